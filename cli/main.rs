@@ -15,6 +15,7 @@ fn main() {
     (version: "0.1.0")
     (author: "Craig M. <craigmc08@gmail.com>")
     (@arg DATA: -d --data +takes_value "Directory for config files; default: ./data")
+    (@arg MAPPINGS: -m --mappings +takes_value "Mappings (.usmap) for assets with unversioned properties")
     (@subcommand test =>
       (about: "Deserializes and serializes a single asset file")
       (@arg OUT: -o --out +takes_value "Filename to serialize asset to; default: ./out/out.[uasset/uexp]")
@@ -27,6 +28,24 @@ fn main() {
       (@arg OUT: -o --out +takes_value "Filename to output test information about; if not present, prints to stdout")
       (@arg DIRECTORY: +takes_value +required "Path to directory containing assets")
     )
+    (@subcommand disasm =>
+      (about: "Prints the Kismet bytecode of the functions in a blueprint asset")
+      (@arg FUNCTION: -f --function +takes_value "Only disassemble the export with this name")
+      (@arg ASSET: +takes_value +required "Path to asset to disassemble")
+    )
+    (@subcommand pak =>
+      (about: "Lists or extracts the files in a pak archive")
+      (@subcommand list =>
+        (about: "Lists the files in a pak")
+        (@arg PAK: +takes_value +required "Path to pak")
+      )
+      (@subcommand extract =>
+        (about: "Extracts the files in a pak")
+        (@arg OUT: -o --out +takes_value "Directory to extract files to; default: ./out")
+        (@arg FILTER: -f --filter +takes_value "Only extract files with paths starting with this")
+        (@arg PAK: +takes_value +required "Path to pak")
+      )
+    )
   ).get_matches();
 
   let data_dir: &Path = matches.value_of("DATA").unwrap_or("./data").as_ref();
@@ -35,6 +54,12 @@ fn main() {
     println!("Failed to load struct patterns: {:?}", err);
     std::process::exit(-1);
   }
+  if let Some(mappings_file) = matches.value_of("MAPPINGS") {
+    if let Err(err) = property::mappings::Mappings::load(mappings_file.as_ref()) {
+      println!("Failed to load mappings: {:?}", err);
+      std::process::exit(-1);
+    }
+  }
 
   if let Some(matches) = matches.subcommand_matches("test") {
     let out_file = matches.value_of("OUT").unwrap_or("./out/out");
@@ -46,6 +71,20 @@ fn main() {
     let out_file = matches.value_of("OUT");
     let dir = matches.value_of("DIRECTORY").unwrap();
     all_command(out_file, dir);
+  } else if let Some(matches) = matches.subcommand_matches("disasm") {
+    let asset_loc = matches.value_of("ASSET").unwrap();
+    let function = matches.value_of("FUNCTION");
+    disasm_command(asset_loc, function);
+  } else if let Some(matches) = matches.subcommand_matches("pak") {
+    if let Some(matches) = matches.subcommand_matches("list") {
+      let pak_loc = matches.value_of("PAK").unwrap();
+      pak_list_command(pak_loc);
+    } else if let Some(matches) = matches.subcommand_matches("extract") {
+      let out_dir = matches.value_of("OUT").unwrap_or("./out");
+      let filter = matches.value_of("FILTER");
+      let pak_loc = matches.value_of("PAK").unwrap();
+      pak_extract_command(out_dir, filter, pak_loc);
+    }
   }
 }
 
@@ -63,6 +102,45 @@ fn test_command(out_file: &str, asset_loc: &str, print_value: bool, print_header
     }
     Ok(asset) => {
       asset.recalculate_offsets();
+      let mismatched = asset.names().mismatched_hashes();
+      if !mismatched.is_empty() {
+        println!("Names with unexpected hashes: {:?}", mismatched);
+      }
+      for (export, strct) in asset.exports().exports.iter().zip(asset.structs()) {
+        if let Some(err) = &strct.native_error {
+          println!("{:?}", err);
+        }
+        let trailing = strct.unknown_trailing_data();
+        if !trailing.is_empty() {
+          println!(
+            "Export {} (class {}) has {} bytes of unknown native data",
+            export.object_name.to_string(asset.names()),
+            export.class_name(asset.names()),
+            trailing.len()
+          );
+        }
+      }
+      for (export_index, record) in asset.bulk_data_records() {
+        println!(
+          "Export {} has {} bytes of {:?} bulk data",
+          asset.exports().exports[export_index]
+            .object_name
+            .to_string(asset.names()),
+          record.header.size_on_disk,
+          record.header.location()
+        );
+      }
+      for candidate in asset.bulk_data_candidates() {
+        println!(
+          "Export {} may have {} bytes of {:?} bulk data at {:#X} of its unknown native data",
+          asset.exports().exports[candidate.export_index]
+            .object_name
+            .to_string(asset.names()),
+          candidate.bulk_data.size_on_disk,
+          candidate.bulk_data.location(),
+          candidate.position
+        );
+      }
       if print_header {
         println!("{:#?}", asset.header);
       }
@@ -77,6 +155,126 @@ fn test_command(out_file: &str, asset_loc: &str, print_value: bool, print_header
   }
 }
 
+fn disasm_command(asset_loc: &str, function: Option<&str>) {
+  let asset = match Asset::read_from(asset_loc.as_ref()) {
+    Err(err) => {
+      println!("Failed to read asset");
+      println!("{:?}", err);
+      std::process::exit(-1);
+    }
+    Ok(asset) => asset,
+  };
+
+  let names = asset.names();
+  for (i, export) in asset.exports().exports.iter().enumerate() {
+    let name = export.object_name.to_string(names);
+    let class_name = export.class_name(names);
+    let has_script = class_name == "Function" || class_name.ends_with("GeneratedClass");
+    if !has_script || function.is_some_and(|f| f != name) {
+      continue;
+    }
+
+    println!("{} ({})", name, class_name);
+    match asset.disassemble(i) {
+      Ok(script) => print!("{}", script.listing(names)),
+      Err(err) => println!("{:?}", err),
+    }
+    println!();
+  }
+}
+
+fn open_pak(pak_loc: &str) -> pak::Pak {
+  match pak::Pak::open(pak_loc.as_ref()) {
+    Err(err) => {
+      println!("Failed to open pak");
+      println!("{:?}", err);
+      std::process::exit(-1);
+    }
+    Ok(pak) => pak,
+  }
+}
+
+fn pak_list_command(pak_loc: &str) {
+  let pak = open_pak(pak_loc);
+  println!(
+    "Mount point {} (version {})",
+    pak.mount_point,
+    pak.version()
+  );
+  for entry in &pak.entries {
+    match pak.compression_method_name(entry) {
+      Some(method) => println!(
+        "{} ({} bytes, {} compressed to {})",
+        entry.path, entry.uncompressed_size, method, entry.size
+      ),
+      None => println!("{} ({} bytes)", entry.path, entry.uncompressed_size),
+    }
+  }
+}
+
+fn pak_extract_command(out_dir: &str, filter: Option<&str>, pak_loc: &str) {
+  let pak = open_pak(pak_loc);
+  let entries: Vec<&pak::PakEntry> = pak
+    .entries
+    .iter()
+    .filter(|entry| filter.is_none_or(|f| entry.path.starts_with(f)))
+    .collect();
+
+  let pb = ProgressBar::new(entries.len() as u64);
+  pb.set_style(
+    ProgressStyle::default_bar()
+      .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
+      .progress_chars("=>-"),
+  );
+  let mut failures = 0;
+  for entry in entries.iter() {
+    let out_fp = match extract_path(Path::new(out_dir), &entry.path) {
+      Some(out_fp) => out_fp,
+      None => {
+        pb.println(format!(
+          "Skipped {}, its path isn't inside the output directory",
+          entry.path
+        ));
+        failures += 1;
+        pb.inc(1);
+        continue;
+      }
+    };
+    let result = pak.read_entry(entry).and_then(|data| {
+      if let Some(parent) = out_fp.parent() {
+        std::fs::create_dir_all(parent)?;
+      }
+      std::fs::write(&out_fp, data).with_context(|| format!("Failed to write {:?}", out_fp))
+    });
+    if let Err(err) = result {
+      pb.println(format!("Failed to extract {}\n{:?}", entry.path, err));
+      failures += 1;
+    }
+    pb.inc(1);
+  }
+  pb.finish();
+  println!(
+    "Extracted {} of {} files",
+    entries.len() - failures,
+    entries.len()
+  );
+}
+
+/// Where to extract a pak entry to. Paths stored in a pak can be anything,
+/// so entries with a root, a prefix or `..` in their path get None instead of
+/// a path outside of `out_dir`.
+fn extract_path(out_dir: &Path, entry_path: &str) -> Option<PathBuf> {
+  let mut out_fp = out_dir.to_path_buf();
+  for component in Path::new(entry_path).components() {
+    match component {
+      Component::Normal(part) => out_fp.push(part),
+      Component::CurDir => {}
+      Component::RootDir | Component::Prefix(_) | Component::ParentDir => return None,
+    }
+  }
+  Some(out_fp)
+}
+
 fn all_command(out_file: Option<&str>, dir: &str) {
   let asset_locs: Vec<PathBuf> = WalkDir::new(dir)
     .into_iter()
@@ -137,3 +335,20 @@ fn all_command(out_file: Option<&str>, dir: &str) {
   )
   .unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn extract_paths_stay_in_the_output_directory() {
+    let out_dir = Path::new("out");
+    assert_eq!(
+      extract_path(out_dir, "FSD/Content/./Items/Item.uasset"),
+      Some(PathBuf::from("out/FSD/Content/Items/Item.uasset"))
+    );
+    assert_eq!(extract_path(out_dir, "../FSD/Item.uasset"), None);
+    assert_eq!(extract_path(out_dir, "FSD/../../Item.uasset"), None);
+    assert_eq!(extract_path(out_dir, "/etc/Item.uasset"), None);
+  }
+}
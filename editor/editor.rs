@@ -21,7 +21,7 @@ pub fn start_editor_with_path(fp: &Path) {
     Ok(header) => Editor {
       state: State::Header {
         header,
-        path: Box::from(fp),
+        source: AssetSource::File(Box::from(fp)),
         import_editor: ImportEditor::default(),
       },
       err: None,
@@ -32,6 +32,32 @@ pub fn start_editor_with_path(fp: &Path) {
   init_editor(editor)
 }
 
+pub fn start_editor_with_pak(pak_loc: &Path, asset_path: &str) {
+  let editor = match pak::Pak::open(pak_loc).and_then(|pak| pak.read_asset(asset_path)) {
+    Err(err) => Editor {
+      state: State::None,
+      err: Some(err),
+      tool: None,
+      keyboard: Keyboard::default(),
+    },
+    Ok(asset) => Editor {
+      state: State::Asset {
+        asset,
+        source: AssetSource::Pak {
+          pak_loc: Box::from(pak_loc),
+          asset_path: asset_path.to_string(),
+        },
+        import_editor: ImportEditor::default(),
+        export_editor: ExportEditor::default(),
+      },
+      err: None,
+      tool: None,
+      keyboard: Keyboard::default(),
+    },
+  };
+  init_editor(editor)
+}
+
 pub fn start_editor_empty() {
   init_editor(Editor::default())
 }
@@ -157,6 +183,14 @@ fn draw_menu(_pos: [f32; 2], _size: [f32; 2], run: &mut bool, ui: &Ui, editor: &
         crate::operations::io::open(editor, ui);
       }
 
+      // FILE > OPEN FROM PAK
+      if MenuItem::new(im_str!("Open from Pak"))
+        .shortcut(im_str!("Ctrl+Shift+O"))
+        .build(ui)
+      {
+        crate::operations::io::open_from_pak(editor, ui);
+      }
+
       // FILE > SAVE
       if MenuItem::new(im_str!("Save As"))
         .shortcut(im_str!("Ctrl+S"))
@@ -239,18 +273,13 @@ fn draw_imports_editor(
           .find(|im| selected == &im.name)
           .expect("Invalid Import select state");
 
-        // TODO: this name deserialization should probably happen after reading
-        // imports during Asset::read. But that will require a bunch of work
-        let outer = Reference::deserialize(import.outer_index, &header.imports, &header.exports)
-          .expect("Invalid Import outer");
-
         ui.text(format!(
           "Class Package: {}",
           import.class_package.to_string(&header.names)
         ));
         ui.text(format!("Class: {}", import.class.to_string(&header.names)));
         ui.text(format!("Name: {}", import.name.to_string(&header.names)));
-        ui.text(format!("Outer: {}", outer.to_string(&header.names)));
+        ui.text(format!("Outer: {}", import.outer.to_string(&header.names)));
       } else {
         ui.text("Select an import");
       }
@@ -318,14 +347,32 @@ fn draw_exports_selector(
     .position(pos, Condition::Always)
     .size(size, Condition::Always);
   w.build(&ui, || {
-    for export in asset.list_exports() {
-      let active = Some(export.clone()) == editor.selected_export;
+    for (i, export) in asset.list_exports().into_iter().enumerate() {
+      let is_selected_export = Some(export.clone()) == editor.selected_export;
+      let active = is_selected_export && editor.selected_row.is_none();
 
       if ui.radio_button_bool(&ImString::from(export.to_string(asset.names())), active) && !active {
         // The selected export is changing, so have to deselect the property
         editor.selected_export = Some(export.clone());
+        editor.selected_row = None;
         editor.properties_editor = Some(PropertiesEditor::default().with_flags(MAIN_WINDOW_FLAGS));
       }
+
+      // DataTable rows are listed under their export
+      if let Some(data_table) = asset.structs()[i].native_as::<DataTable>() {
+        ui.indent();
+        for (row_name, _) in data_table.rows.iter() {
+          let active = is_selected_export && Some(row_name) == editor.selected_row.as_ref();
+          let label = format!("Row {}", row_name.to_string(asset.names()));
+          if ui.radio_button_bool(&ImString::from(label), active) && !active {
+            editor.selected_export = Some(export.clone());
+            editor.selected_row = Some(row_name.clone());
+            editor.properties_editor =
+              Some(PropertiesEditor::default().with_flags(MAIN_WINDOW_FLAGS));
+          }
+        }
+        ui.unindent();
+      }
     }
   });
 }
@@ -340,6 +387,7 @@ fn draw_properties_editor(
   match export_editor {
     ExportEditor {
       selected_export: Some(selected_export),
+      selected_row,
       properties_editor: Some(properties_editor),
     } => {
       let struct_idx = asset
@@ -347,8 +395,20 @@ fn draw_properties_editor(
         .iter()
         .position(|x| x == selected_export)
         .expect("Invalid selected export. Report this crash to the maintainer.");
-      let properties = &mut asset.exports.structs[struct_idx].properties;
-      properties_editor.draw(left_top, width_height, ui, &mut asset.header, properties);
+      let strct = &mut asset.exports.structs[struct_idx];
+      let properties = match selected_row {
+        None => Some(&mut strct.properties),
+        Some(row_name) => strct
+          .native_as_mut::<DataTable>()
+          .and_then(|data_table| data_table.row_mut(row_name))
+          .and_then(|row| match row {
+            struct_pattern::StructValue::PropertyList { properties, .. } => Some(properties),
+            _ => None,
+          }),
+      };
+      if let Some(properties) = properties {
+        properties_editor.draw(left_top, width_height, ui, &mut asset.header, properties);
+      }
     }
     _ => {
       // don't draw properties if no editor/no selected export
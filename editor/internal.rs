@@ -33,19 +33,41 @@ pub struct ImportEditor {
 #[derive(Default)]
 pub struct ExportEditor {
   pub selected_export: Option<NameVariant>,
+  // Row of the selected export, if it is a DataTable
+  pub selected_row: Option<NameVariant>,
   pub properties_editor: Option<PropertiesEditor>,
 }
 
+/// Where the open asset was read from
+pub enum AssetSource {
+  File(Box<Path>),
+  // The asset path is only a path in the pak, not a location on disk
+  Pak {
+    pak_loc: Box<Path>,
+    asset_path: String,
+  },
+}
+
+impl AssetSource {
+  /// Location of the asset on disk, if it was read from one
+  pub fn file(&self) -> Option<&Path> {
+    match self {
+      Self::File(fp) => Some(fp),
+      Self::Pak { .. } => None,
+    }
+  }
+}
+
 pub enum State {
   None,
   Header {
     header: AssetHeader,
-    path: Box<Path>,
+    source: AssetSource,
     import_editor: ImportEditor,
   },
   Asset {
     asset: Asset,
-    path: Box<Path>,
+    source: AssetSource,
     import_editor: ImportEditor,
     export_editor: ExportEditor,
   },
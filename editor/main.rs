@@ -23,6 +23,8 @@ fn main() {
     (version: "0.1.0")
     (author: "Craig M. <craigmc08@gmail.com>")
     (@arg DATA: -d --data +takes_value "Directory for config files; default: ./data")
+    (@arg MAPPINGS: -m --mappings +takes_value "Mappings (.usmap) for assets with unversioned properties")
+    (@arg PAK: -p --pak +takes_value "Pak to open ASSET from, where ASSET is a path in the pak")
     (@arg ASSET: +takes_value "Asset to open; if not present, no asset is opened")
   )
   .get_matches();
@@ -40,8 +42,16 @@ fn main() {
     println!("Failed to load struct patterns: {:?}", err);
     std::process::exit(-1);
   }
+  if let Some(mappings_file) = matches.value_of("MAPPINGS") {
+    if let Err(err) = property::mappings::Mappings::load(mappings_file.as_ref()) {
+      println!("Failed to load mappings: {:?}", err);
+      std::process::exit(-1);
+    }
+  }
 
-  if let Some(asset_loc) = matches.value_of("ASSET") {
+  if let (Some(pak_loc), Some(asset_path)) = (matches.value_of("PAK"), matches.value_of("ASSET")) {
+    start_editor_with_pak(pak_loc.as_ref(), asset_path);
+  } else if let Some(asset_loc) = matches.value_of("ASSET") {
     start_editor_with_path(asset_loc.as_ref());
   } else {
     start_editor_empty();
@@ -3,10 +3,15 @@ use crate::operation;
 use crate::operations::*;
 use drg::*;
 use imgui::*;
-use tinyfiledialogs::{open_file_dialog, save_file_dialog_with_filter};
+use std::path::Path;
+use tinyfiledialogs::{input_box, open_file_dialog, save_file_dialog_with_filter};
 use winit::event::VirtualKeyCode;
 
 pub const OPEN: Operation = operation!(Shortcut::new(VirtualKeyCode::O).ctrl(true), open);
+pub const OPEN_FROM_PAK: Operation = operation!(
+  Shortcut::new(VirtualKeyCode::O).ctrl(true).shift(true),
+  open_from_pak
+);
 pub const SAVE: Operation = operation!(Shortcut::new(VirtualKeyCode::S).ctrl(true), save);
 pub const LOAD_EXPORTS: Operation = operation!(
   Shortcut::new(VirtualKeyCode::L).ctrl(true).shift(true),
@@ -27,7 +32,7 @@ pub fn open(editor: &mut Editor, ui: &Ui) {
       Ok(header) => {
         editor.state = State::Header {
           header,
-          path: Box::from(fp.as_ref()),
+          source: AssetSource::File(Box::from(fp.as_ref())),
           import_editor: ImportEditor::default(),
         }
       }
@@ -35,6 +40,39 @@ pub fn open(editor: &mut Editor, ui: &Ui) {
   }
 }
 
+pub fn open_from_pak(editor: &mut Editor, ui: &Ui) {
+  let pak_fp = match open_file_dialog("Open Pak", "", Some((&["*.pak"], "Pak file (*.pak)"))) {
+    None => return,
+    Some(pak_fp) => pak_fp,
+  };
+  let asset_path = match input_box(
+    "Open Asset from Pak",
+    "Path of the asset in the pak, like FSD/Content/Character/InventoryLists/BP_ScoutInventory",
+    "",
+  ) {
+    None => return,
+    Some(asset_path) => asset_path,
+  };
+
+  match pak::Pak::open(pak_fp.as_ref()).and_then(|pak| pak.read_asset(&asset_path)) {
+    Err(err) => {
+      editor.err = Some(err);
+      ui.open_popup(im_str!("Error"));
+    }
+    Ok(asset) => {
+      editor.state = State::Asset {
+        asset,
+        source: AssetSource::Pak {
+          pak_loc: Box::from(Path::new(&pak_fp)),
+          asset_path,
+        },
+        import_editor: ImportEditor::default(),
+        export_editor: ExportEditor::default(),
+      }
+    }
+  }
+}
+
 pub fn save(editor: &mut Editor, ui: &Ui) {
   if editor.state.is_none() {
     return;
@@ -47,18 +85,25 @@ pub fn save(editor: &mut Editor, ui: &Ui) {
       State::None => {
         unreachable!()
       }
-      State::Header { header, .. } => {
+      State::Header { header, source, .. } => {
         header.recalculate_offsets();
-        if let Err(err) = header.write_out(fp.as_ref()) {
-          editor.err = Some(err);
-          ui.open_popup(im_str!("Error"));
+        match header.write_out(fp.as_ref()) {
+          Ok(()) => *source = AssetSource::File(Box::from(Path::new(&fp))),
+          Err(err) => {
+            editor.err = Some(err);
+            ui.open_popup(im_str!("Error"));
+          }
         }
       }
-      State::Asset { asset, .. } => {
+      State::Asset { asset, source, .. } => {
         asset.recalculate_offsets();
-        if let Err(err) = asset.write_out(fp.as_ref()) {
-          editor.err = Some(err);
-          ui.open_popup(im_str!("Error"));
+        match asset.write_out(fp.as_ref()) {
+          // Once saved, the asset is on disk even if it came from a pak
+          Ok(()) => *source = AssetSource::File(Box::from(Path::new(&fp))),
+          Err(err) => {
+            editor.err = Some(err);
+            ui.open_popup(im_str!("Error"));
+          }
         }
       }
     }
@@ -71,16 +116,22 @@ pub fn load_exports(editor: &mut Editor, ui: &Ui) {
   if editor.state.is_header() {
     if let State::Header {
       header,
-      path,
+      source,
       import_editor,
     } = std::mem::take(&mut editor.state)
     {
-      match AssetExports::read_from(&header, &path) {
+      let uexp = match source.file() {
+        Some(fp) => AssetExports::read_from(&header, fp),
+        None => Err(anyhow::anyhow!(
+          "Only assets opened from a file have exports to load, save the asset first"
+        )),
+      };
+      match uexp {
         Err(err) => {
           editor.err = Some(err);
           editor.state = State::Header {
             header,
-            path,
+            source,
             import_editor,
           };
           ui.open_popup(im_str!("Error"));
@@ -88,7 +139,7 @@ pub fn load_exports(editor: &mut Editor, ui: &Ui) {
         Ok(uexp) => {
           editor.state = State::Asset {
             asset: Asset::new(header, uexp),
-            path,
+            source,
             import_editor,
             export_editor: ExportEditor::default(),
           }
@@ -33,7 +33,7 @@ impl<'a> Operations<'a> {
 impl<'a> Default for Operations<'a> {
   fn default() -> Self {
     Self {
-      operations: vec![OPEN, SAVE, LOAD_EXPORTS],
+      operations: vec![OPEN, OPEN_FROM_PAK, SAVE, LOAD_EXPORTS],
     }
   }
 }
@@ -2,6 +2,7 @@ use crate::internal::*;
 use crate::property_creator::*;
 use drg::asset::property::meta::*;
 use drg::asset::property::prop_type::*;
+use drg::asset::property::text::*;
 use drg::asset::*;
 use drg::bindings::*;
 
@@ -16,11 +17,13 @@ pub enum PluginType {
     dep: Reference,
   },
   PluginSoftObject {
+    prop_type: PropType, // SoftObjectProperty or SoftClassProperty
     object_name: NameVariant,
     parent: Reference,
   },
   PluginArray {
     value_type: PropType,
+    meta_tag: Option<(Meta, Tag)>,
     sub_editors: Vec<EditorPlugin>,
     value_creator: Option<ValueCreator>,
   },
@@ -33,9 +36,22 @@ pub enum PluginType {
   PluginInt {
     value: i32,
   },
+  PluginByte {
+    prop_type: PropType,
+    tag: Tag,
+    value: i32,
+  },
   PluginStr {
     value: ImString,
   },
+  PluginText {
+    flags: u32,
+    namespace: ImString,
+    key: ImString,
+    source_string: ImString,
+    // The strings that were read, to keep empty strings empty or null
+    original: [Option<String>; 3],
+  },
 }
 
 impl AsProperty for PluginType {
@@ -43,18 +59,21 @@ impl AsProperty for PluginType {
     match self {
       PluginType::PluginNone { original, .. } => original.meta.typ,
       PluginType::PluginObject { .. } => PropType::ObjectProperty,
-      PluginType::PluginSoftObject { .. } => PropType::SoftObjectProperty,
+      PluginType::PluginSoftObject { prop_type, .. } => *prop_type,
       PluginType::PluginArray { .. } => PropType::ArrayProperty,
       PluginType::PluginBool { .. } => PropType::BoolProperty,
       PluginType::PluginFloat { .. } => PropType::FloatProperty,
       PluginType::PluginInt { .. } => PropType::IntProperty,
+      PluginType::PluginByte { prop_type, .. } => *prop_type,
       PluginType::PluginStr { .. } => PropType::StrProperty,
+      PluginType::PluginText { .. } => PropType::TextProperty,
     }
   }
   fn as_tag(&self, _: &AssetHeader) -> Tag {
     match self {
       PluginType::PluginNone { original, .. } => original.tag.clone(),
       PluginType::PluginBool { value } => Tag::Bool(*value),
+      PluginType::PluginByte { tag, .. } => tag.clone(),
       PluginType::PluginArray { value_type, .. } => Tag::Array {
         inner_type: *value_type,
       },
@@ -68,19 +87,54 @@ impl AsProperty for PluginType {
       PluginType::PluginSoftObject {
         object_name,
         parent,
+        ..
       } => Value::SoftObject {
         object_name: object_name.clone(),
         parent: parent.clone(),
       },
-      PluginType::PluginArray { sub_editors, .. } => sub_editors.as_value(header),
+      PluginType::PluginArray {
+        meta_tag,
+        sub_editors,
+        ..
+      } => match sub_editors.as_value(header) {
+        Value::Array { values, .. } => Value::Array {
+          meta_tag: meta_tag.clone(),
+          values,
+        },
+        _ => unreachable!(),
+      },
       PluginType::PluginBool { value } => value.as_value(header),
       PluginType::PluginFloat { value } => value.as_value(header),
       PluginType::PluginInt { value } => value.as_value(header),
+      PluginType::PluginByte { value, .. } => Value::Byte(*value as u8),
       PluginType::PluginStr { value } => value.to_string().as_value(header),
+      PluginType::PluginText {
+        flags,
+        namespace,
+        key,
+        source_string,
+        original,
+      } => {
+        let mut text = Text::base(
+          edited_fstring(namespace, &original[0]),
+          edited_fstring(key, &original[1]),
+          edited_fstring(source_string, &original[2]),
+        );
+        text.flags = *flags;
+        text.as_value(header)
+      }
     }
   }
 }
 
+/// Empty strings stay the way they were read, as empty or null strings
+fn edited_fstring<'a>(value: &'a ImString, original: &'a Option<String>) -> Option<&'a str> {
+  match value.to_str() {
+    "" => original.as_deref().filter(|original| original.is_empty()),
+    value => Some(value),
+  }
+}
+
 pub struct EditorPlugin {
   name: NameVariant,
   plugin: PluginType,
@@ -94,19 +148,20 @@ impl EditorPlugin {
         object_name,
         parent,
       } => PluginType::PluginSoftObject {
+        prop_type: property.meta.typ,
         object_name: object_name.clone(),
         parent: parent.clone(),
       },
-      Value::Array { values, .. } => {
+      Value::Array { meta_tag, values } => {
         if let Tag::Array { inner_type } = property.tag {
           let mut sub_editors = vec![];
-          for editor in values.iter().map(|v| {
+          for editor in values.iter().map(|(tag, v)| {
             let mut sub_meta = property.meta.clone();
             sub_meta.typ = inner_type;
             Self::new(
               &Property {
                 meta: sub_meta,
-                tag: property.tag.clone(),
+                tag: tag.clone(),
                 value: v.clone(),
               },
               header,
@@ -116,6 +171,7 @@ impl EditorPlugin {
           }
           PluginType::PluginArray {
             value_type: inner_type,
+            meta_tag: meta_tag.clone(),
             sub_editors,
             value_creator: None,
           }
@@ -132,10 +188,37 @@ impl EditorPlugin {
       }
       Value::Float(value) => PluginType::PluginFloat { value: *value },
       Value::Int(value) => PluginType::PluginInt { value: *value },
+      Value::Byte(value) => PluginType::PluginByte {
+        prop_type: property.meta.typ,
+        tag: property.tag.clone(),
+        value: *value as i32,
+      },
       Value::Str(value) => {
         let str = ImString::from(value.clone());
         PluginType::PluginStr { value: str }
       }
+      Value::Text(Text {
+        flags,
+        history:
+          TextHistory::Base {
+            namespace,
+            key,
+            source_string,
+          },
+      }) => {
+        let imstr = |s: &Option<String>| ImString::from(s.clone().unwrap_or_default());
+        PluginType::PluginText {
+          flags: *flags,
+          namespace: imstr(namespace),
+          key: imstr(key),
+          source_string: imstr(source_string),
+          original: [namespace.clone(), key.clone(), source_string.clone()],
+        }
+      }
+      Value::Text(_) => PluginType::PluginNone {
+        original: property.clone(),
+        reason: "Only base texts can be edited".to_string(),
+      },
       _ => PluginType::PluginNone {
         original: property.clone(),
         reason: format!("Unsupported property type {}", property.meta.typ),
@@ -169,6 +252,7 @@ impl EditorPlugin {
       PluginType::PluginSoftObject {
         object_name,
         parent,
+        ..
       } => {
         let object_name_changed = if let Some(new_object_name) =
           input_name_variant(ui, "Object Name", header, object_name.clone())
@@ -191,6 +275,7 @@ impl EditorPlugin {
         value_type,
         sub_editors,
         value_creator,
+        ..
       } => {
         let mut changed = false;
 
@@ -262,10 +347,36 @@ impl EditorPlugin {
         ui.input_int(im_str!("Int"), value).build();
         prev != *value
       }
+      PluginType::PluginByte { value, .. } => {
+        let prev = *value;
+        ui.input_int(im_str!("Byte"), value).build();
+        *value = (*value).clamp(0, 255);
+        prev != *value
+      }
       PluginType::PluginStr { value } => ui
         .input_text(im_str!("String"), value)
         .resize_buffer(true)
         .build(),
+      PluginType::PluginText {
+        namespace,
+        key,
+        source_string,
+        ..
+      } => {
+        let namespace_changed = ui
+          .input_text(im_str!("Namespace"), namespace)
+          .resize_buffer(true)
+          .build();
+        let key_changed = ui
+          .input_text(im_str!("Key"), key)
+          .resize_buffer(true)
+          .build();
+        let source_string_changed = ui
+          .input_text(im_str!("Source String"), source_string)
+          .resize_buffer(true)
+          .build();
+        namespace_changed || key_changed || source_string_changed
+      }
     }
   }
 }
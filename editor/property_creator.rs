@@ -1,6 +1,7 @@
 use crate::internal::*;
 use drg::asset::property::meta::*;
 use drg::asset::property::prop_type::*;
+use drg::asset::property::text::*;
 use drg::asset::property::*;
 use drg::asset::*;
 use imgui::*;
@@ -37,25 +38,61 @@ impl ValueCreator {
         for_type,
         value: Value::Object(Reference::uobject()),
       },
-      PropType::SoftObjectProperty => ValueCreator::WithDefault {
+      PropType::SoftObjectProperty | PropType::SoftClassProperty => ValueCreator::WithDefault {
         for_type,
         value: Value::SoftObject {
           object_name: NameVariant::parse("None", &header.names),
           parent: Reference::uobject(),
         },
       },
+      PropType::Int8Property => ValueCreator::WithDefault {
+        for_type,
+        value: Value::Int8(0),
+      },
+      PropType::Int16Property => ValueCreator::WithDefault {
+        for_type,
+        value: Value::Int16(0),
+      },
       PropType::IntProperty => ValueCreator::WithDefault {
         for_type,
         value: Value::Int(0),
       },
+      PropType::Int64Property => ValueCreator::WithDefault {
+        for_type,
+        value: Value::Int64(0),
+      },
+      PropType::UInt8Property => ValueCreator::WithDefault {
+        for_type,
+        value: Value::UInt8(0),
+      },
+      PropType::UInt16Property => ValueCreator::WithDefault {
+        for_type,
+        value: Value::UInt16(0),
+      },
+      PropType::UInt32Property => ValueCreator::WithDefault {
+        for_type,
+        value: Value::UInt32(0),
+      },
+      PropType::UInt64Property => ValueCreator::WithDefault {
+        for_type,
+        value: Value::UInt64(0),
+      },
       PropType::FloatProperty => ValueCreator::WithDefault {
         for_type,
         value: Value::Float(0.0),
       },
+      PropType::DoubleProperty => ValueCreator::WithDefault {
+        for_type,
+        value: Value::Double(0.0),
+      },
       PropType::StrProperty => ValueCreator::WithDefault {
         for_type,
         value: Value::Str("".to_string()),
       },
+      PropType::TextProperty => ValueCreator::WithDefault {
+        for_type,
+        value: Value::Text(Text::empty()),
+      },
 
       _ => ValueCreator::NoCreator { for_type },
     }
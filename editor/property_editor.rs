@@ -13,6 +13,7 @@ pub struct PropertiesEditor {
 
 struct SelectedProperty {
   pub name: NameVariant,
+  pub array_index: u32,
   pub dirty: bool,
   pub plugin: EditorPlugin,
 }
@@ -80,14 +81,19 @@ impl PropertiesEditor {
       // Property list
       let mut to_remove = vec![];
       for (i, prop) in properties.iter().enumerate() {
-        let mut active = Some(&prop.meta.name) == self.selected.as_ref().map(|x| &x.name);
+        let mut active = self
+          .selected
+          .as_ref()
+          .map(|x| x.name == prop.meta.name && x.array_index == prop.meta.array_index)
+          .unwrap_or(false);
         if ui.radio_button_bool(
-          &ImString::from(prop.meta.name.to_string(&header.names)),
+          &ImString::from(prop.meta.indexed_name(&header.names)),
           active,
         ) && !active
         {
           self.selected = Some(SelectedProperty {
             name: prop.meta.name.clone(),
+            array_index: prop.meta.array_index,
             dirty: false,
             plugin: EditorPlugin::new(prop, header),
           });
@@ -129,25 +135,29 @@ impl PropertiesEditor {
       .size(size, Condition::Always);
     w.build(&ui, || {
       if let Some(selected) = &mut self.selected {
+        let index = properties.iter().position(|prop| {
+          prop.meta.name == selected.name && prop.meta.array_index == selected.array_index
+        });
+        let display_name = match index {
+          Some(index) => properties[index].meta.indexed_name(&header.names),
+          None => selected.name.to_string(&header.names),
+        };
         if selected.dirty {
-          ui.text(ImString::from(format!(
-            "*{}",
-            selected.name.to_string(&header.names)
-          )));
+          ui.text(ImString::from(format!("*{}", display_name)));
         } else {
-          ui.text(ImString::from(selected.name.to_string(&header.names)));
+          ui.text(ImString::from(display_name.clone()));
         }
         ui.same_line(0.0);
         if ui.button(im_str!("Save"), [0.0, 0.0]) {
-          let index = properties
-            .iter()
-            .position(|prop| prop.meta.name == selected.name)
-            .expect(&format!(
-              "Property {} was removed while editing. Report to maintaner",
-              selected.name.to_string(&header.names)
-            ));
+          let index = index.expect(&format!(
+            "Property {} was removed while editing. Report to maintaner",
+            display_name
+          ));
           selected.dirty = false;
-          properties[index] = selected.plugin.as_property(selected.name.clone(), header);
+          let mut property = selected.plugin.as_property(selected.name.clone(), header);
+          property.meta.array_index = selected.array_index;
+          property.meta.property_guid = properties[index].meta.property_guid;
+          properties[index] = property;
         }
 
         ui.separator();
@@ -118,6 +118,10 @@ impl Tool for NameReplacerTool {
       let header = state.header_mut();
       let from = NameVariant::parse(&self.from, &header.names);
       header.names.names[from.name_idx].name = self.to.to_string();
+      header.names.names[from.name_idx].rehash();
+      header
+        .assets
+        .rename(&self.from.to_string(), self.to.to_str());
     }
   }
 }
@@ -1,24 +1,44 @@
 pub mod asset_registry;
+pub mod bulk_data;
 pub mod depends;
 pub mod exports;
 pub mod file_summary;
+pub mod flags;
+pub mod gatherable_text;
 pub mod imports;
+pub mod kismet;
 pub mod names;
+pub mod native;
 pub mod preload_dependencies;
 pub mod property;
 pub mod reference;
+pub mod searchable_names;
+pub mod soft_package_references;
+pub mod thumbnails;
 
 pub use asset_registry::*;
+pub use bulk_data::*;
 pub use depends::*;
 pub use exports::*;
 pub use file_summary::*;
+pub use flags::*;
+pub use gatherable_text::*;
 pub use imports::*;
+pub use kismet::*;
 pub use names::*;
+pub use native::*;
 pub use preload_dependencies::*;
 pub use property::*;
 pub use reference::*;
+pub use searchable_names::*;
+pub use soft_package_references::*;
+pub use thumbnails::*;
 
+use crate::asset::property::meta::*;
+use crate::asset::property::prop_type::*;
+use crate::asset::struct_pattern::*;
 use crate::reader::*;
+use crate::util::read_bytes;
 use anyhow::*;
 use std::io::prelude::Write;
 use std::io::Cursor;
@@ -28,9 +48,12 @@ use std::path::Path;
 pub struct AssetHeader {
   pub summary: FileSummary,
   pub names: Names,
+  pub gatherable_text: Option<GatherableTextData>,
   pub imports: Imports,
   pub exports: Exports,
-  pub depends: Depends,
+  pub soft_package_references: Option<SoftPackageReferences>,
+  pub searchable_names: Option<SearchableNames>,
+  pub thumbnails: Option<Thumbnails>,
   pub assets: AssetRegistry,
   pub dependencies: PreloadDependencies,
 }
@@ -38,6 +61,10 @@ pub struct AssetHeader {
 #[derive(Debug)]
 pub struct AssetExports {
   pub structs: Vec<Properties>,
+  // Bulk data payloads stored at the end of the uexp, after the exports
+  pub bulk_data: Vec<u8>,
+  // Contents of the .ubulk file, if there is one
+  pub ubulk: Option<Vec<u8>>,
 }
 
 #[derive(Debug)]
@@ -70,22 +97,69 @@ impl AssetHeader {
     let mut rdr = ByteReader::new(uasset);
     let summary = FileSummary::read(&mut rdr).with_context(|| "Failed to read summary")?;
     let names = Names::read(&mut rdr, &summary).with_context(|| "Failed to read names")?;
+    // The editor only sections have offset 0 when they are missing
+    let gatherable_text = if summary.gatherable_text_data_offset != 0 {
+      Some(
+        GatherableTextData::read(&mut rdr, &summary)
+          .with_context(|| "Failed to read gatherable text data")?,
+      )
+    } else {
+      None
+    };
     let imports =
       Imports::read(&mut rdr, &summary, &names).with_context(|| "Failed to read imports")?;
-    let exports =
-      Exports::read(&mut rdr, &summary, &names).with_context(|| "Failed to read exports")?;
-    let depends =
-      Depends::read(&mut rdr, &summary).with_context(|| "Failed to read dependencies")?;
+    let mut exports = Exports::read(&mut rdr, &summary, &names, &imports)
+      .with_context(|| "Failed to read exports")?;
+    exports
+      .read_depends(&mut rdr, &summary, &imports)
+      .with_context(|| "Failed to read dependencies")?;
+    let soft_package_references = if summary.soft_package_references_offset != 0 {
+      Some(
+        SoftPackageReferences::read(&mut rdr, &summary, &names)
+          .with_context(|| "Failed to read soft package references")?,
+      )
+    } else {
+      None
+    };
+    let searchable_names = if summary.searchable_names_offset != 0 {
+      Some(
+        SearchableNames::read(&mut rdr, &summary, &names, &imports, &exports)
+          .with_context(|| "Failed to read searchable names")?,
+      )
+    } else {
+      None
+    };
+    let thumbnails = if summary.thumbnail_table_offset != 0 {
+      Some(Thumbnails::read(&mut rdr, &summary).with_context(|| "Failed to read thumbnails")?)
+    } else {
+      None
+    };
     let assets =
       AssetRegistry::read(&mut rdr, &summary).with_context(|| "Failed to read asset registry")?;
     let dependencies = PreloadDependencies::read(&mut rdr, &summary, &imports, &exports)
       .with_context(|| "Failed to read preload dependencies")?;
+
+    // Unversioned properties don't store their names, so the ones they can use
+    // are added from the mappings without being written to the name map
+    let mut names = names;
+    if summary.has_unversioned_properties() {
+      if let Some(mappings) = property::mappings::Mappings::get() {
+        for export in exports.exports.iter() {
+          let class_name = export.class_name(&names);
+          mappings.add_transient_names(&class_name, &mut names);
+        }
+      }
+    }
+
     Ok(Self {
       summary,
       names,
+      gatherable_text,
       imports,
       exports,
-      depends,
+      soft_package_references,
+      searchable_names,
+      thumbnails,
       assets,
       dependencies,
     })
@@ -101,18 +175,38 @@ impl AssetHeader {
       .names
       .write(&mut cursor)
       .with_context(|| "Failed to write names")?;
+    if let Some(gatherable_text) = &self.gatherable_text {
+      gatherable_text
+        .write(&mut cursor)
+        .with_context(|| "Failed to write gatherable text data")?;
+    }
     self
       .imports
       .write(&mut cursor, &self.names)
       .with_context(|| "Failed to write imports")?;
     self
       .exports
-      .write(&mut cursor, &self.names)
+      .write(&mut cursor, &self.summary, &self.names, &self.imports)
       .with_context(|| "Failed to write exports")?;
     self
-      .depends
-      .write(&mut cursor)
+      .exports
+      .write_depends(&mut cursor, &self.names, &self.imports)
       .with_context(|| "Failed to write dependencies")?;
+    if let Some(soft_package_references) = &self.soft_package_references {
+      soft_package_references
+        .write(&mut cursor, &self.names)
+        .with_context(|| "Failed to write soft package references")?;
+    }
+    if let Some(searchable_names) = &self.searchable_names {
+      searchable_names
+        .write(&mut cursor, &self.names, &self.imports, &self.exports)
+        .with_context(|| "Failed to write searchable names")?;
+    }
+    if let Some(thumbnails) = &self.thumbnails {
+      thumbnails
+        .write(&mut cursor)
+        .with_context(|| "Failed to write thumbnails")?;
+    }
     self
       .assets
       .write(&mut cursor)
@@ -125,42 +219,78 @@ impl AssetHeader {
     Ok(cursor.into_inner())
   }
 
-  /// Recalculates all offsets without using export data
-  pub fn recalculate_offsets(&mut self) {
-    let names_offset = self.summary.byte_size();
-    let import_offset = names_offset + self.names.byte_size();
-    let export_offset = import_offset + self.imports.byte_size();
-    let deps_offset = export_offset + self.exports.byte_size();
-    let assets_offset = deps_offset + self.depends.byte_size();
-    let preload_offset = assets_offset + self.assets.byte_size();
-    let structs_offset = deps_offset + self.dependencies.byte_size() + 4;
-    let total_header_size = structs_offset - 4; // Without the end tag? TODO check this
+  /// Places every header section after the previous one and updates the
+  /// offsets and counts in the summary. Returns the total header size.
+  fn layout_sections(&mut self) -> usize {
+    self.exports.remove_missing_depends(&self.imports);
 
-    let header_size_delta = (structs_offset as i64) - (self.summary.total_header_size as i64);
+    let mut offset = self.summary.byte_size();
+
+    self.summary.name_count = self.names.serialized_count() as u32;
+    self.summary.name_offset = offset as u32;
+    offset += self.names.byte_size();
+
+    if let Some(gatherable_text) = &self.gatherable_text {
+      self.summary.gatherable_text_data_count = gatherable_text.texts.len() as u32;
+      self.summary.gatherable_text_data_offset = offset as u32;
+      offset += gatherable_text.byte_size();
+    }
 
-    self.summary.total_header_size = total_header_size as u32;
-    self.summary.name_count = self.names.names.len() as u32;
-    self.summary.name_offset = names_offset as u32;
     self.summary.import_count = self.imports.objects.len() as u32;
-    self.summary.import_offset = import_offset as u32;
+    self.summary.import_offset = offset as u32;
+    offset += self.imports.byte_size();
+
     self.summary.export_count = self.exports.exports.len() as u32;
-    self.summary.export_offset = export_offset as u32;
-    self.summary.depends_offset = deps_offset as u32;
-    self.summary.asset_registry_data_offset = assets_offset as u32;
-    self.summary.bulk_data_start_offset =
-      (self.summary.bulk_data_start_offset as i64 + header_size_delta) as u32;
+    self.summary.export_offset = offset as u32;
+    offset += self.exports.byte_size(&self.summary);
+
+    self.summary.depends_offset = offset as u32;
+    offset += self.exports.depends_byte_size();
+
+    if let Some(soft_package_references) = &self.soft_package_references {
+      self.summary.soft_package_references_count = soft_package_references.references.len() as u32;
+      self.summary.soft_package_references_offset = offset as u32;
+      offset += soft_package_references.byte_size();
+    }
+
+    if let Some(searchable_names) = &self.searchable_names {
+      self.summary.searchable_names_offset = offset as u32;
+      offset += searchable_names.byte_size();
+    }
+
+    if let Some(thumbnails) = &self.thumbnails {
+      // The table comes after the images
+      self.summary.thumbnail_table_offset = (offset + thumbnails.images_size()) as u32;
+      offset += thumbnails.byte_size();
+    }
+
+    self.summary.asset_registry_data_offset = offset as u32;
+    offset += self.assets.byte_size();
+
     self.summary.preload_dependency_count = self.dependencies.dependencies.len() as u32;
-    self.summary.preload_dependency_offset = preload_offset as u32;
+    self.summary.preload_dependency_offset = offset as u32;
+    offset += self.dependencies.byte_size();
 
     for generation in self.summary.generations.iter_mut() {
       generation.export_count = self.summary.export_count;
       generation.name_count = self.summary.name_count;
     }
 
+    offset
+  }
+
+  /// Recalculates all offsets without using export data
+  pub fn recalculate_offsets(&mut self) {
+    let total_header_size = self.layout_sections();
+    let header_size_delta = (total_header_size as i64) - (self.summary.total_header_size as i64);
+
+    self.summary.total_header_size = total_header_size as u32;
+    self.summary.bulk_data_start_offset += header_size_delta;
+
     for export in self.exports.exports.iter_mut() {
       // No change to export_file_offset
       // No change to serial size
-      export.serial_offset = (export.serial_offset as i64 + header_size_delta) as u32;
+      export.serial_offset = (export.serial_offset as i64 + header_size_delta) as u64;
     }
   }
 }
@@ -172,7 +302,9 @@ impl AssetExports {
     let uexp = std::fs::read(uexp_fp.clone())
       .with_context(|| format!("Failed to read uexp from {:?}", uexp_fp))?;
 
-    Self::read(header, uexp)
+    let mut exports = Self::read(header, uexp)?;
+    exports.ubulk = read_ubulk(asset_loc)?;
+    Ok(exports)
   }
 
   pub fn read(header: &AssetHeader, uexp: Vec<u8>) -> Result<Self> {
@@ -188,11 +320,59 @@ impl AssetExports {
     );
     for export in header.exports.exports.iter() {
       let start_pos = cursor_uexp.position();
-      let strct = Properties::deserialize(&mut cursor_uexp, export, ctx)
+      let mut strct = Properties::deserialize(&mut cursor_uexp, export, ctx)
         .with_context(|| format!("Failed to read struct starting at {:#X}", start_pos))?;
+
+      let class_name = export.class_name(&header.names);
+      if let Some(serializer) = native_serializer_for(&class_name) {
+        let data = strct.take_unknown_trailing_data();
+        match serializer.deserialize(&data, &strct, ctx) {
+          Ok(native) => strct.native = Some(native),
+          Err(err) => {
+            // Keep the bytes as they were so the asset can still be opened
+            *strct.unknown_trailing_data_mut() = data;
+            strct.native_error = Some(err.context(format!(
+              "Failed to read {} native data of {}",
+              class_name,
+              export.object_name.to_string(&header.names)
+            )));
+          }
+        }
+      }
       structs.push(strct);
     }
-    Ok(AssetExports { structs })
+
+    // Everything up to the package tag at the end is bulk data
+    let remaining = cursor_uexp
+      .remaining_bytes()
+      .saturating_sub(header.summary.tag.len());
+    let bulk_data = read_bytes(&mut cursor_uexp, remaining).with_context(|| {
+      format!(
+        "Failed to read bulk data starting at {:#X}",
+        cursor_uexp.position()
+      )
+    })?;
+    Ok(AssetExports {
+      structs,
+      bulk_data,
+      ubulk: None,
+    })
+  }
+
+  /// Serialized size of each export's properties
+  pub fn byte_sizes(&self, header: &AssetHeader) -> Vec<usize> {
+    let ctx = PropertyContext::new(
+      &header.summary,
+      &header.names,
+      &header.imports,
+      &header.exports,
+      struct_pattern::StructPatterns::get().expect("struct-patterns was not initialized properly"),
+    );
+    self
+      .structs
+      .iter()
+      .map(|strct| strct.byte_size(ctx))
+      .collect()
   }
 
   pub fn write(&self, header: &AssetHeader) -> Result<Vec<u8>> {
@@ -214,11 +394,22 @@ impl AssetExports {
         )
       })?;
     }
+    cursor.write_all(&self.bulk_data)?;
     cursor.write_all(&header.summary.tag)?;
     Ok(cursor.into_inner())
   }
 }
 
+fn read_ubulk(asset_loc: &Path) -> Result<Option<Vec<u8>>> {
+  let ubulk_fp = asset_loc.with_extension("ubulk");
+  if !ubulk_fp.exists() {
+    return Ok(None);
+  }
+  let ubulk = std::fs::read(ubulk_fp.clone())
+    .with_context(|| format!("Failed to read ubulk from {:?}", ubulk_fp))?;
+  Ok(Some(ubulk))
+}
+
 impl Asset {
   pub fn new(header: AssetHeader, exports: AssetExports) -> Self {
     Self { header, exports }
@@ -233,7 +424,9 @@ impl Asset {
     let uexp = std::fs::read(uexp_fp.clone())
       .with_context(|| format!("Failed to read uexp from {:?}", uexp_fp))?;
 
-    Self::read(uasset, uexp)
+    let mut asset = Self::read(uasset, uexp)?;
+    asset.exports.ubulk = read_ubulk(asset_loc)?;
+    Ok(asset)
   }
 
   pub fn write_out(&self, asset_loc: &Path) -> Result<()> {
@@ -244,6 +437,9 @@ impl Asset {
 
     std::fs::write(uasset_fp, uasset)?;
     std::fs::write(uexp_fp, uexp)?;
+    if let Some(ubulk) = &self.exports.ubulk {
+      std::fs::write(asset_loc.with_extension("ubulk"), ubulk)?;
+    }
 
     Ok(())
   }
@@ -258,6 +454,7 @@ impl Asset {
       .with_context(|| format!("Failed to read uasset from {:?}", uexp_fp))?;
 
     let mut asset = Self::read(uasset.clone(), uexp.clone())?;
+    asset.exports.ubulk = read_ubulk(asset_loc)?;
     asset.recalculate_offsets();
     let (uasset_out, uexp_out) = asset.write()?;
 
@@ -352,66 +549,260 @@ impl Asset {
     &mut self.exports.structs
   }
 
-  pub fn recalculate_offsets(&mut self) {
-    let structs_size = self
+  /// The rows of the first DataTable export
+  pub fn data_table(&self) -> Option<&DataTable> {
+    self
       .structs()
       .iter()
-      .map(|ps| ps.byte_size())
-      .sum::<usize>();
-
-    self.summary_mut().total_header_size = (self.summary().byte_size()
-      + self.names().byte_size()
-      + self.imports().byte_size()
-      + self.exports().byte_size()
-      + self.header.depends.byte_size()
-      + self.assets().byte_size()
-      + self.deps().byte_size()) as u32;
-    self.summary_mut().name_count = self.names().names.len() as u32;
-    self.summary_mut().name_offset = self.summary().byte_size() as u32;
-    self.summary_mut().export_count = self.exports().exports.len() as u32;
-    self.summary_mut().export_offset =
-      (self.summary().byte_size() + self.names().byte_size() + self.imports().byte_size()) as u32;
-    self.summary_mut().import_count = self.imports().objects.len() as u32;
-    self.summary_mut().import_offset =
-      (self.summary().byte_size() + self.names().byte_size()) as u32;
-    self.summary_mut().depends_offset = (self.summary().byte_size()
-      + self.names().byte_size()
-      + self.imports().byte_size()
-      + self.exports().byte_size()) as u32;
-    self.summary_mut().asset_registry_data_offset = (self.summary().byte_size()
-      + self.names().byte_size()
-      + self.imports().byte_size()
-      + self.exports().byte_size()
-      + self.header.depends.byte_size()) as u32;
-    self.summary_mut().bulk_data_start_offset = (self.summary().byte_size()
-      + self.names().byte_size()
-      + self.imports().byte_size()
-      + self.exports().byte_size()
-      + self.header.depends.byte_size()
-      + self.assets().byte_size()
-      + self.deps().byte_size()
-      + structs_size) as u32;
-    self.summary_mut().preload_dependency_count = self.deps().dependencies.len() as u32;
-    self.summary_mut().preload_dependency_offset = (self.summary().byte_size()
-      + self.names().byte_size()
-      + self.imports().byte_size()
-      + self.exports().byte_size()
-      + self.header.depends.byte_size()
-      + self.assets().byte_size()) as u32;
-
-    // Update all generations counts
-    for generation in self.header.summary.generations.iter_mut() {
-      generation.export_count = self.header.summary.export_count;
-      generation.name_count = self.header.summary.name_count;
+      .find_map(|s| s.native_as::<DataTable>())
+  }
+  pub fn data_table_mut(&mut self) -> Option<&mut DataTable> {
+    self
+      .structs_mut()
+      .iter_mut()
+      .find_map(|s| s.native_as_mut::<DataTable>())
+  }
+
+  /// The entries of the first StringTable export
+  pub fn string_table(&self) -> Option<&StringTable> {
+    self
+      .structs()
+      .iter()
+      .find_map(|s| s.native_as::<StringTable>())
+  }
+  pub fn string_table_mut(&mut self) -> Option<&mut StringTable> {
+    self
+      .structs_mut()
+      .iter_mut()
+      .find_map(|s| s.native_as_mut::<StringTable>())
+  }
+
+  /// The rows of the first CurveTable export
+  pub fn curve_table(&self) -> Option<&CurveTable> {
+    self
+      .structs()
+      .iter()
+      .find_map(|s| s.native_as::<CurveTable>())
+  }
+  pub fn curve_table_mut(&mut self) -> Option<&mut CurveTable> {
+    self
+      .structs_mut()
+      .iter_mut()
+      .find_map(|s| s.native_as_mut::<CurveTable>())
+  }
+
+  /// Replaces the curve of a CurveTable row, adding the row if it doesn't
+  /// exist
+  pub fn set_curve_table_row(&mut self, row: &str, curve: &RichCurve) -> Result<()> {
+    let row = NameVariant::parse_and_add(row, &mut self.header.names);
+    let table = self
+      .exports
+      .structs
+      .iter_mut()
+      .find_map(|s| s.native_as_mut::<CurveTable>())
+      .ok_or_else(|| anyhow!("Asset has no CurveTable"))?;
+    table.set_curve(row, curve, &mut self.header.names)
+  }
+
+  fn float_curve_index(&self) -> Result<usize> {
+    let names = self.names();
+    self
+      .exports()
+      .exports
+      .iter()
+      .position(|export| export.class_name(names) == "CurveFloat")
+      .ok_or_else(|| anyhow!("Asset has no CurveFloat"))
+  }
+
+  /// The FloatCurve of the first CurveFloat export
+  pub fn float_curve(&self) -> Result<RichCurve> {
+    let index = self.float_curve_index()?;
+    let names = self.names();
+    let property = self.structs()[index]
+      .properties
+      .iter()
+      .find(|prop| prop.meta.name.to_string(names) == "FloatCurve");
+    match property.map(|prop| &prop.value) {
+      Some(Value::Struct {
+        value: StructValue::PropertyList { properties, .. },
+      }) => RichCurve::from_properties(properties, names),
+      Some(_) => bail!("FloatCurve is not a property list"),
+      None => Ok(RichCurve::default()),
+    }
+  }
+
+  /// Replaces the FloatCurve of the first CurveFloat export
+  pub fn set_float_curve(&mut self, curve: &RichCurve) -> Result<()> {
+    let index = self.float_curve_index()?;
+    let names = &mut self.header.names;
+    let properties = &mut self.exports.structs[index].properties;
+    let position = properties
+      .iter()
+      .position(|prop| prop.meta.name.to_string(names) == "FloatCurve");
+    let position = match position {
+      Some(position) => position,
+      None => {
+        NameVariant::parse_and_add("StructProperty", names);
+        let meta = Meta::new(
+          NameVariant::parse_and_add("FloatCurve", names),
+          PropType::StructProperty,
+          0,
+        );
+        let tag = Tag::Struct {
+          type_name: NameVariant::parse_and_add("RichCurve", names),
+          guid: [0; 16],
+        };
+        let value = Value::Struct {
+          value: StructValue::PropertyList {
+            properties: vec![],
+            has_none: true,
+          },
+        };
+        properties.push(Property { meta, tag, value });
+        properties.len() - 1
+      }
+    };
+    match &mut properties[position].value {
+      Value::Struct {
+        value: StructValue::PropertyList { properties, .. },
+      } => {
+        curve.write_to_properties(properties, names);
+        Ok(())
+      }
+      _ => bail!("FloatCurve is not a property list"),
     }
+  }
+
+  /// Disassembles the Kismet bytecode of a Function or BlueprintGeneratedClass
+  /// export
+  pub fn disassemble(&self, export_index: usize) -> Result<Script> {
+    let export = self
+      .exports()
+      .exports
+      .get(export_index)
+      .ok_or_else(|| anyhow!("No export {}", export_index))?;
+    let data = self.structs()[export_index].unknown_trailing_data();
+    let uexp_offset = export.export_file_offset + export.serial_size - data.len() as u64;
+    Script::disassemble(
+      data,
+      uexp_offset,
+      self.names(),
+      self.imports(),
+      self.exports(),
+    )
+    .with_context(|| {
+      format!(
+        "Failed to disassemble {}",
+        export.object_name.to_string(self.names())
+      )
+    })
+  }
+
+  /// Where bulk data payloads can be, using the current summary
+  fn bulk_data_bounds(&self) -> BulkDataBounds {
+    BulkDataBounds {
+      bulk_data_start_offset: self.summary().bulk_data_start_offset,
+      end_of_file_size: self.exports.bulk_data.len(),
+      ubulk_size: self.exports.ubulk.as_ref().map(|ubulk| ubulk.len()),
+    }
+  }
+
+  /// The bulk data records in the parsed native data of every export, with
+  /// the index of the export they're in
+  pub fn bulk_data_records(&self) -> Vec<(usize, &ByteBulkData)> {
+    let mut records = vec![];
+    for (export_index, strct) in self.structs().iter().enumerate() {
+      if let Some(native) = &strct.native {
+        for bulk_data in native.bulk_data() {
+          records.push((export_index, bulk_data));
+        }
+      }
+    }
+    records
+  }
+
+  /// Things that look like bulk data records in native data that isn't
+  /// parsed. These are only a guess and are never updated when offsets are
+  /// recalculated.
+  pub fn bulk_data_candidates(&self) -> Vec<BulkDataCandidate> {
+    let bounds = self.bulk_data_bounds();
+    let mut candidates = vec![];
+    for (export_index, strct) in self.structs().iter().enumerate() {
+      for (position, bulk_data) in find_bulk_data_candidates(strct.unknown_trailing_data(), bounds)
+      {
+        candidates.push(BulkDataCandidate {
+          export_index,
+          position,
+          bulk_data,
+        });
+      }
+    }
+    candidates
+  }
+
+  /// The payload of a bulk data record, from wherever it's stored
+  pub fn bulk_data_payload<'a>(&'a self, record: &'a ByteBulkData) -> Result<&'a [u8]> {
+    let bulk_data = &record.header;
+    let size = bulk_data.size_on_disk as usize;
+    let offset = bulk_data.payload_offset(self.summary().bulk_data_start_offset);
+    let (data, start) = match bulk_data.location() {
+      BulkDataLocation::Inline => return Ok(&record.inline_payload),
+      BulkDataLocation::EndOfFile => (
+        &self.exports.bulk_data[..],
+        (offset - self.summary().bulk_data_start_offset) as usize,
+      ),
+      BulkDataLocation::SeparateFile => (
+        &self
+          .exports
+          .ubulk
+          .as_ref()
+          .ok_or_else(|| anyhow!("Bulk data is in a .ubulk file, which wasn't loaded"))?[..],
+        offset as usize,
+      ),
+      BulkDataLocation::OptionalFile => bail!("Bulk data in .uptnl files isn't supported"),
+      BulkDataLocation::Unused => return Ok(&[]),
+    };
+    start
+      .checked_add(size)
+      .and_then(|end| data.get(start..end))
+      .ok_or_else(|| anyhow!("Bulk data payload at {:#X} is out of range", start))
+  }
+
+  pub fn recalculate_offsets(&mut self) {
+    let struct_sizes = self.exports.byte_sizes(&self.header);
+    let structs_size = struct_sizes.iter().sum::<usize>();
+
+    let total_header_size = self.header.layout_sections();
+    let old_bulk_data_start = self.summary().bulk_data_start_offset;
+    let new_bulk_data_start = (total_header_size + structs_size) as i64;
+    self.summary_mut().total_header_size = total_header_size as u32;
+    self.summary_mut().bulk_data_start_offset = new_bulk_data_start;
 
     let mut running_size_total = 0;
-    for i in 0..(self.summary().export_count as usize) {
-      self.exports_mut().exports[i].export_file_offset = running_size_total as u64;
-      self.exports_mut().exports[i].serial_size = self.structs()[i].byte_size() as u64;
+    for (i, struct_size) in struct_sizes.into_iter().enumerate() {
+      self.exports_mut().exports[i].export_file_offset = running_size_total;
+      self.exports_mut().exports[i].serial_size = struct_size as u64;
       self.exports_mut().exports[i].serial_offset =
-        running_size_total + self.summary().total_header_size;
-      running_size_total += self.exports().exports[i].serial_size as u32;
+        running_size_total + self.summary().total_header_size as u64;
+      running_size_total += self.exports().exports[i].serial_size;
+    }
+
+    // Only native data that was parsed is updated, since anything else could
+    // be mistaken for an offset
+    for (i, strct) in self.exports.structs.iter_mut().enumerate() {
+      let serial_offset = self.header.exports.exports[i].serial_offset;
+      let extra_size = strct.unknown_trailing_data().len() as u64;
+      if let Some(native) = &mut strct.native {
+        for bulk_data in native.bulk_data_mut() {
+          bulk_data
+            .header
+            .move_bulk_data_start(old_bulk_data_start, new_bulk_data_start);
+        }
+        let native_start = serial_offset + self.header.exports.exports[i].serial_size
+          - native.byte_size() as u64
+          - extra_size;
+        native.set_package_offset(native_start);
+      }
     }
   }
 }
@@ -4,35 +4,241 @@ use crate::util::*;
 use anyhow::*;
 use std::io::prelude::*;
 use std::io::Cursor;
+use std::io::SeekFrom;
+
+// Strings are None when they were written as null strings instead of empty
+// ones
+#[derive(Debug, Clone)]
+pub struct AssetData {
+  pub object_path: Option<String>,
+  pub object_class_name: Option<String>,
+  pub tags: Vec<(Option<String>, Option<String>)>,
+}
 
 #[derive(Debug)]
 pub struct AssetRegistry {
-  pub data: Vec<u8>,
+  pub objects: Vec<AssetData>,
+  // Empty sections don't even have an object count
+  has_object_count: bool,
+  // Bytes that couldn't be read as registry data. If the section doesn't
+  // parse, the whole section ends up here.
+  extra: Vec<u8>,
+}
+
+impl AssetData {
+  fn read(rdr: &mut ByteReader) -> Result<Self> {
+    let object_path = read_fstring(rdr).with_context(|| "object_path")?;
+    let object_class_name = read_fstring(rdr).with_context(|| "object_class_name")?;
+    let tag_count = read_u32(rdr)?;
+    let mut tags = vec![];
+    for i in 0..tag_count {
+      let key = read_fstring(rdr).with_context(|| format!("Tag {} key", i))?;
+      let value = read_fstring(rdr).with_context(|| format!("Tag {} value", i))?;
+      tags.push((key, value));
+    }
+    Ok(Self {
+      object_path,
+      object_class_name,
+      tags,
+    })
+  }
+
+  fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    write_fstring(curs, &self.object_path)?;
+    write_fstring(curs, &self.object_class_name)?;
+    write_u32(curs, self.tags.len() as u32)?;
+    for (key, value) in self.tags.iter() {
+      write_fstring(curs, key)?;
+      write_fstring(curs, value)?;
+    }
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    fstring_size(&self.object_path)
+      + fstring_size(&self.object_class_name)
+      + 4
+      + self
+        .tags
+        .iter()
+        .map(|(key, value)| fstring_size(key) + fstring_size(value))
+        .sum::<usize>()
+  }
+
+  pub fn get_tag(&self, key: &str) -> Option<&str> {
+    self
+      .tags
+      .iter()
+      .find(|(k, _)| k.as_deref().unwrap_or_default() == key)
+      .map(|(_, v)| v.as_deref().unwrap_or_default())
+  }
+
+  /// Sets the value of a tag, adding it if it doesn't exist
+  pub fn set_tag(&mut self, key: &str, value: &str) {
+    match self
+      .tags
+      .iter_mut()
+      .find(|(k, _)| k.as_deref().unwrap_or_default() == key)
+    {
+      Some((_, v)) => *v = new_fstring(value),
+      None => self.tags.push((new_fstring(key), new_fstring(value))),
+    }
+  }
 }
 
 impl AssetRegistry {
   pub fn read(rdr: &mut ByteReader, summary: &FileSummary) -> Result<Self> {
-    // Asset registry actually contains both Depends and AssetsRegistry
     if rdr.position() != summary.asset_registry_data_offset as u64 {
       bail!(
         "Wrong asset registry starting position: Expected to be at position {:#X}, but I'm at position {:#X}",
-        summary.depends_offset,
+        summary.asset_registry_data_offset,
         rdr.position(),
       );
     }
 
+    let start_pos = rdr.position();
     let assets_len =
-      (summary.preload_dependency_offset - summary.asset_registry_data_offset) as usize;
-    let data = read_bytes(rdr, assets_len)?;
-    Ok(AssetRegistry { data })
+      (summary.preload_dependency_offset - summary.asset_registry_data_offset) as u64;
+    let end_pos = start_pos + assets_len;
+
+    let (has_object_count, objects) = if assets_len == 0 {
+      (false, vec![])
+    } else {
+      match Self::read_objects(rdr) {
+        Ok(objects) if rdr.position() <= end_pos => (true, objects),
+        _ => {
+          rdr.seek(SeekFrom::Start(start_pos))?;
+          (false, vec![])
+        }
+      }
+    };
+    let extra = read_bytes(rdr, (end_pos - rdr.position()) as usize)?;
+    Ok(AssetRegistry {
+      objects,
+      has_object_count,
+      extra,
+    })
+  }
+
+  fn read_objects(rdr: &mut ByteReader) -> Result<Vec<AssetData>> {
+    let mut objects = vec![];
+    let object_count = read_u32(rdr)?;
+    for i in 0..object_count {
+      let object =
+        AssetData::read(rdr).with_context(|| format!("Failed to read asset data {}", i))?;
+      objects.push(object);
+    }
+    Ok(objects)
+  }
+
+  fn writes_object_count(&self) -> bool {
+    self.has_object_count || !self.objects.is_empty()
   }
 
   pub fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
-    curs.write_all(&self.data[..])?;
+    if self.writes_object_count() {
+      write_u32(curs, self.objects.len() as u32)?;
+      for object in self.objects.iter() {
+        object.write(curs)?;
+      }
+    }
+    curs.write_all(&self.extra[..])?;
     Ok(())
   }
 
   pub fn byte_size(&self) -> usize {
-    self.data.len()
+    let objects_size = if self.writes_object_count() {
+      4 + self.objects.iter().map(|o| o.byte_size()).sum::<usize>()
+    } else {
+      0
+    };
+    objects_size + self.extra.len()
+  }
+
+  /// Replaces `from` with `to` in object paths and tag values, e.g. after
+  /// renaming the asset. Only whole names or path segments are replaced, so
+  /// renaming Gun leaves Gunner alone.
+  pub fn rename(&mut self, from: &str, to: &str) {
+    for object in self.objects.iter_mut() {
+      if let Some(path) = &mut object.object_path {
+        *path = replace_path_segments(path, from, to);
+      }
+      for (_, value) in object.tags.iter_mut() {
+        if let Some(value) = value {
+          *value = replace_path_segments(value, from, to);
+        }
+      }
+    }
+  }
+}
+
+/// Replaces occurrences of `from` in `path` that are bounded by the start or
+/// end of the string or by object path separators
+fn replace_path_segments(path: &str, from: &str, to: &str) -> String {
+  let is_separator = |c: char| matches!(c, '/' | '.' | ':' | '\'' | '"');
+  if from.is_empty() {
+    return path.to_string();
+  }
+  let mut replaced = String::with_capacity(path.len());
+  let mut rest = path;
+  while let Some(pos) = rest.find(from) {
+    let end = pos + from.len();
+    let before = rest[..pos]
+      .chars()
+      .next_back()
+      .or_else(|| replaced.chars().next_back());
+    let starts_segment = before.is_none_or(is_separator) || from.starts_with(is_separator);
+    let ends_segment =
+      rest[end..].chars().next().is_none_or(is_separator) || from.ends_with(is_separator);
+    if starts_segment && ends_segment {
+      replaced.push_str(&rest[..pos]);
+      replaced.push_str(to);
+      rest = &rest[end..];
+    } else {
+      // Skip one character so overlapping matches are still found
+      let skip = pos + rest[pos..].chars().next().map_or(1, char::len_utf8);
+      replaced.push_str(&rest[..skip]);
+      rest = &rest[skip..];
+    }
+  }
+  replaced.push_str(rest);
+  replaced
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::asset::property::TestPackage;
+
+  fn registry_bytes(object_path: &str) -> Vec<u8> {
+    let mut curs = Cursor::new(vec![]);
+    write_u32(&mut curs, 1).unwrap();
+    write_fstring(&mut curs, &Some(object_path.to_string())).unwrap();
+    // An empty class name and a null tag key
+    write_u32(&mut curs, 1).unwrap();
+    curs.write_all(&[0]).unwrap();
+    write_u32(&mut curs, 1).unwrap();
+    write_u32(&mut curs, 0).unwrap();
+    write_fstring(&mut curs, &Some(object_path.to_string())).unwrap();
+    curs.into_inner()
+  }
+
+  #[test]
+  fn empty_and_null_strings_are_kept_apart() {
+    let bytes = registry_bytes("/Game/Gun.Gun");
+    let mut summary = TestPackage::new(&[]).summary;
+    summary.preload_dependency_offset = bytes.len() as u32;
+    let mut registry = AssetRegistry::read(&mut ByteReader::new(bytes), &summary).unwrap();
+    let object = &registry.objects[0];
+    assert_eq!(object.object_class_name, Some(String::new()));
+    assert_eq!(object.tags[0].0, None);
+    assert_eq!(object.get_tag(""), Some("/Game/Gun.Gun"));
+
+    registry.rename("Gun", "Rifle");
+    let expected = registry_bytes("/Game/Rifle.Rifle");
+    let mut curs = Cursor::new(vec![]);
+    registry.write(&mut curs).unwrap();
+    assert_eq!(registry.byte_size(), expected.len());
+    assert_eq!(curs.into_inner(), expected);
   }
 }
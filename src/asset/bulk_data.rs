@@ -0,0 +1,352 @@
+use crate::asset::*;
+use anyhow::*;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::prelude::*;
+use std::io::Cursor;
+
+/// Where the payload of a bulk data record is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkDataLocation {
+  /// Right after the record
+  Inline,
+  /// In the bulk data section at the end of the uexp
+  EndOfFile,
+  /// In the .ubulk file
+  SeparateFile,
+  /// In the .uptnl file
+  OptionalFile,
+  /// There is no payload
+  Unused,
+}
+
+/// The header of an FByteBulkData, which is written in the native data of
+/// textures, sounds, meshes, etc.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BulkData {
+  pub flags: BulkDataFlags,
+  pub element_count: i64,
+  pub size_on_disk: i64,
+  // Relative to the summary's bulk_data_start_offset unless NO_OFFSET_FIX_UP
+  // is set
+  pub offset_in_file: i64,
+}
+
+impl BulkData {
+  pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+    let flags = BulkDataFlags::from_bits_retain(rdr.read_u32::<LittleEndian>()?);
+    let (element_count, size_on_disk) = if flags.contains(BulkDataFlags::SIZE_64_BIT) {
+      (
+        rdr.read_i64::<LittleEndian>()?,
+        rdr.read_i64::<LittleEndian>()?,
+      )
+    } else {
+      (
+        rdr.read_i32::<LittleEndian>()?.into(),
+        rdr.read_i32::<LittleEndian>()?.into(),
+      )
+    };
+    let offset_in_file = rdr.read_i64::<LittleEndian>()?;
+    Ok(Self {
+      flags,
+      element_count,
+      size_on_disk,
+      offset_in_file,
+    })
+  }
+
+  pub fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    curs.write_u32::<LittleEndian>(self.flags.bits())?;
+    if self.flags.contains(BulkDataFlags::SIZE_64_BIT) {
+      curs.write_i64::<LittleEndian>(self.element_count)?;
+      curs.write_i64::<LittleEndian>(self.size_on_disk)?;
+    } else {
+      curs.write_i32::<LittleEndian>(self.element_count as i32)?;
+      curs.write_i32::<LittleEndian>(self.size_on_disk as i32)?;
+    }
+    curs.write_i64::<LittleEndian>(self.offset_in_file)?;
+    Ok(())
+  }
+
+  pub fn byte_size(&self) -> usize {
+    if self.flags.contains(BulkDataFlags::SIZE_64_BIT) {
+      28
+    } else {
+      20
+    }
+  }
+
+  pub fn location(&self) -> BulkDataLocation {
+    if self.flags.contains(BulkDataFlags::UNUSED) {
+      BulkDataLocation::Unused
+    } else if self.flags.contains(BulkDataFlags::OPTIONAL_PAYLOAD) {
+      BulkDataLocation::OptionalFile
+    } else if self.flags.contains(BulkDataFlags::PAYLOAD_IN_SEPERATE_FILE) {
+      BulkDataLocation::SeparateFile
+    } else if self.flags.contains(BulkDataFlags::PAYLOAD_AT_END_OF_FILE) {
+      BulkDataLocation::EndOfFile
+    } else {
+      BulkDataLocation::Inline
+    }
+  }
+
+  /// Offset of the payload in its file, after the engine's offset fix up
+  pub fn payload_offset(&self, bulk_data_start_offset: i64) -> i64 {
+    if self.flags.contains(BulkDataFlags::NO_OFFSET_FIX_UP) {
+      self.offset_in_file
+    } else {
+      self.offset_in_file + bulk_data_start_offset
+    }
+  }
+
+  /// Updates the stored offset after the bulk data section moved, so the
+  /// payload offset stays the same relative to the file it is in.
+  pub fn move_bulk_data_start(&mut self, old_start: i64, new_start: i64) {
+    let fixed_up = !self.flags.contains(BulkDataFlags::NO_OFFSET_FIX_UP);
+    match self.location() {
+      // Absolute offsets into the uexp move with the section
+      BulkDataLocation::EndOfFile if !fixed_up => self.offset_in_file += new_start - old_start,
+      // Offsets into other files shouldn't change, but the fix up does
+      BulkDataLocation::SeparateFile | BulkDataLocation::OptionalFile if fixed_up => {
+        self.offset_in_file -= new_start - old_start
+      }
+      _ => {}
+    }
+  }
+
+  /// Whether the sizes make sense for byte, word, or float bulk data
+  fn has_valid_sizes(&self) -> bool {
+    let compressed = self.flags.intersects(
+      BulkDataFlags::SERIALIZE_COMPRESSED_ZLIB | BulkDataFlags::SERIALIZE_COMPRESSED_BIT_WINDOW,
+    );
+    if self.element_count <= 0 || self.size_on_disk <= 0 {
+      return false;
+    }
+    compressed
+      || [1, 2, 4].iter().any(|element_size| {
+        self.element_count.checked_mul(*element_size) == Some(self.size_on_disk)
+      })
+  }
+}
+
+/// An FByteBulkData as it's written in native data: the header, followed by
+/// the payload when it's stored inline
+#[derive(Debug, Clone, PartialEq)]
+pub struct ByteBulkData {
+  pub header: BulkData,
+  pub inline_payload: Vec<u8>,
+}
+
+impl ByteBulkData {
+  pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+    let header = BulkData::read(rdr)?;
+    if header
+      .flags
+      .contains(BulkDataFlags::DUPLICATE_NON_OPTIONAL_PAYLOAD)
+    {
+      bail!("Bulk data with a duplicate payload isn't supported");
+    }
+    let inline_payload = match header.location() {
+      BulkDataLocation::Inline if header.size_on_disk > 0 => {
+        let mut payload = vec![0; header.size_on_disk as usize];
+        rdr
+          .read_exact(&mut payload)
+          .with_context(|| format!("Inline payload of {:#X} bytes", header.size_on_disk))?;
+        payload
+      }
+      _ => vec![],
+    };
+    Ok(Self {
+      header,
+      inline_payload,
+    })
+  }
+
+  pub fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    self.header.write(curs)?;
+    curs.write_all(&self.inline_payload)?;
+    Ok(())
+  }
+
+  pub fn byte_size(&self) -> usize {
+    self.header.byte_size() + self.inline_payload.len()
+  }
+}
+
+/// Something in unparsed native data that looks like a bulk data record
+#[derive(Debug, Clone, PartialEq)]
+pub struct BulkDataCandidate {
+  pub export_index: usize,
+  // Position in the export's unknown trailing data
+  pub position: usize,
+  pub bulk_data: BulkData,
+}
+
+/// Sizes of the places bulk data payloads can be in, used to tell records
+/// apart from other native data
+#[derive(Debug, Clone, Copy)]
+pub struct BulkDataBounds {
+  pub bulk_data_start_offset: i64,
+  pub end_of_file_size: usize,
+  pub ubulk_size: Option<usize>,
+}
+
+/// Looks for bulk data records in native data that isn't parsed, returning
+/// each with its position in `data`. Any match can be a coincidence, so these
+/// are only good for reporting and must never be written back.
+pub fn find_bulk_data_candidates(data: &[u8], bounds: BulkDataBounds) -> Vec<(usize, BulkData)> {
+  let known_flags = BulkDataFlags::all()
+    .iter_names()
+    .fold(0, |bits, (_, flag)| bits | flag.bits());
+  let mut found = vec![];
+  let mut pos = 0;
+  while pos + 20 <= data.len() {
+    let bulk_data = match BulkData::read(&mut &data[pos..]) {
+      Ok(bulk_data) => bulk_data,
+      Err(_) => {
+        pos += 1;
+        continue;
+      }
+    };
+    if bulk_data.flags.bits() & !known_flags != 0 || !bulk_data.has_valid_sizes() {
+      pos += 1;
+      continue;
+    }
+
+    let offset = bulk_data.payload_offset(bounds.bulk_data_start_offset);
+    let size = bulk_data.size_on_disk;
+    let payload_start = pos + bulk_data.byte_size();
+    let in_range = |start: i64, len: usize| {
+      start >= 0 && start.checked_add(size).is_some_and(|end| end <= len as i64)
+    };
+    let is_record = match bulk_data.location() {
+      // Records without flags would match too much other data
+      BulkDataLocation::Inline => {
+        bulk_data
+          .flags
+          .contains(BulkDataFlags::FORCE_INLINE_PAYLOAD)
+          && in_range(payload_start as i64, data.len())
+      }
+      BulkDataLocation::EndOfFile => in_range(
+        offset - bounds.bulk_data_start_offset,
+        bounds.end_of_file_size,
+      ),
+      BulkDataLocation::SeparateFile => bounds
+        .ubulk_size
+        .map(|ubulk_size| in_range(offset, ubulk_size))
+        .unwrap_or(false),
+      BulkDataLocation::OptionalFile | BulkDataLocation::Unused => false,
+    };
+    if !is_record {
+      pos += 1;
+      continue;
+    }
+
+    found.push((pos, bulk_data.clone()));
+    pos = if bulk_data.location() == BulkDataLocation::Inline {
+      payload_start + size as usize
+    } else {
+      payload_start
+    };
+  }
+  found
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn bulk_data(flags: BulkDataFlags, offset_in_file: i64) -> BulkData {
+    BulkData {
+      flags,
+      element_count: 0x40,
+      size_on_disk: 0x40,
+      offset_in_file,
+    }
+  }
+
+  fn round_trip(record: &ByteBulkData) -> Vec<u8> {
+    let mut curs = Cursor::new(vec![]);
+    record.write(&mut curs).unwrap();
+    let bytes = curs.into_inner();
+    assert_eq!(record.byte_size(), bytes.len());
+    assert_eq!(&ByteBulkData::read(&mut &bytes[..]).unwrap(), record);
+    bytes
+  }
+
+  #[test]
+  fn records_round_trip() {
+    let end_of_file = ByteBulkData {
+      header: bulk_data(BulkDataFlags::PAYLOAD_AT_END_OF_FILE, 0x10),
+      inline_payload: vec![],
+    };
+    assert_eq!(round_trip(&end_of_file).len(), 20);
+
+    let wide = ByteBulkData {
+      header: bulk_data(
+        BulkDataFlags::PAYLOAD_IN_SEPERATE_FILE | BulkDataFlags::SIZE_64_BIT,
+        0x20,
+      ),
+      inline_payload: vec![],
+    };
+    assert_eq!(round_trip(&wide).len(), 28);
+
+    let inline = ByteBulkData {
+      header: bulk_data(BulkDataFlags::FORCE_INLINE_PAYLOAD, 0),
+      inline_payload: (0..0x40).collect(),
+    };
+    assert_eq!(round_trip(&inline).len(), 20 + 0x40);
+  }
+
+  #[test]
+  fn offsets_move_with_the_bulk_data_section() {
+    let moved = |flags, offset_in_file| {
+      let mut bulk_data = bulk_data(flags, offset_in_file);
+      bulk_data.move_bulk_data_start(0x1000, 0x1100);
+      bulk_data.offset_in_file
+    };
+    // Relative to the section, so nothing changes
+    assert_eq!(moved(BulkDataFlags::PAYLOAD_AT_END_OF_FILE, 0x10), 0x10);
+    // Absolute in the uexp, so it moves with the section
+    assert_eq!(
+      moved(
+        BulkDataFlags::PAYLOAD_AT_END_OF_FILE | BulkDataFlags::NO_OFFSET_FIX_UP,
+        0x1010
+      ),
+      0x1110
+    );
+    // In the ubulk, where the payload stays put but the fix up changes
+    let ubulk = bulk_data(BulkDataFlags::PAYLOAD_IN_SEPERATE_FILE, -0x1000);
+    assert_eq!(ubulk.payload_offset(0x1000), 0);
+    assert_eq!(
+      moved(BulkDataFlags::PAYLOAD_IN_SEPERATE_FILE, -0x1000),
+      -0x1100
+    );
+    assert_eq!(
+      moved(
+        BulkDataFlags::PAYLOAD_IN_SEPERATE_FILE | BulkDataFlags::NO_OFFSET_FIX_UP,
+        0x30
+      ),
+      0x30
+    );
+  }
+
+  #[test]
+  fn candidates_need_their_payload_in_range() {
+    let bounds = BulkDataBounds {
+      bulk_data_start_offset: 0x1000,
+      end_of_file_size: 0x50,
+      ubulk_size: None,
+    };
+    let mut curs = Cursor::new(vec![0xAA; 3]);
+    curs.set_position(3);
+    bulk_data(BulkDataFlags::PAYLOAD_AT_END_OF_FILE, 0x10)
+      .write(&mut curs)
+      .unwrap();
+    bulk_data(BulkDataFlags::PAYLOAD_AT_END_OF_FILE, 0x20)
+      .write(&mut curs)
+      .unwrap();
+    let candidates = find_bulk_data_candidates(&curs.into_inner(), bounds);
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].0, 3);
+  }
+}
@@ -2,16 +2,59 @@ use crate::asset::*;
 use crate::reader::*;
 use crate::util::*;
 use anyhow::*;
-use std::io::prelude::*;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::io::Cursor;
 
-#[derive(Debug)]
-pub struct Depends {
-  pub data: Vec<u8>,
+/// An object an export depends on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+  pub reference: Reference,
+  // Index the reference was read from, which is written back while it still
+  // points at an object with the same name
+  original_index: i32,
 }
 
-impl Depends {
-  pub fn read(rdr: &mut ByteReader, summary: &FileSummary) -> Result<Self> {
+impl Dependency {
+  pub fn new(reference: Reference) -> Self {
+    Self {
+      reference,
+      original_index: 0,
+    }
+  }
+
+  fn read(rdr: &mut ByteReader, imports: &Imports, exports: &Exports) -> Result<Self> {
+    let original_index = rdr.read_i32::<LittleEndian>()?;
+    Ok(Self {
+      reference: Reference::deserialize(original_index, imports, exports)?,
+      original_index,
+    })
+  }
+
+  fn write(
+    &self,
+    curs: &mut Cursor<Vec<u8>>,
+    names: &Names,
+    imports: &Imports,
+    exports: &Exports,
+  ) -> Result<()> {
+    let index = self
+      .reference
+      .index_for_write(self.original_index, names, imports, exports)?;
+    curs.write_i32::<LittleEndian>(index)?;
+    Ok(())
+  }
+}
+
+// The depends map has one list of dependencies for each export, in the same
+// order as the exports. The lists are kept on the exports so they stay with
+// them when exports are added, removed, or moved.
+impl Exports {
+  pub fn read_depends(
+    &mut self,
+    rdr: &mut ByteReader,
+    summary: &FileSummary,
+    imports: &Imports,
+  ) -> Result<()> {
     if rdr.position() != summary.depends_offset as u64 {
       bail!(
         "Wrong depends starting position: Expected to be at position {:#X}, but I'm at position {:#X}",
@@ -20,17 +63,104 @@ impl Depends {
       );
     }
 
-    let depends_len = (summary.asset_registry_data_offset - summary.depends_offset) as usize;
-    let data = read_bytes(rdr, depends_len)?;
-    Ok(Self { data })
+    let mut dependencies = vec![];
+    for i in 0..summary.export_count {
+      let count = read_u32(rdr)?;
+      let mut export_dependencies = vec![];
+      for _ in 0..count {
+        let start_pos = rdr.position();
+        let dependency = Dependency::read(rdr, imports, self).with_context(|| {
+          format!(
+            "Failed to parse depends of export {} starting at {:#X}",
+            i, start_pos
+          )
+        })?;
+        export_dependencies.push(dependency);
+      }
+      dependencies.push(export_dependencies);
+    }
+    for (export, export_dependencies) in self.exports.iter_mut().zip(dependencies) {
+      export.depends = export_dependencies;
+    }
+    Ok(())
   }
 
-  pub fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
-    curs.write_all(&self.data[..])?;
+  pub fn write_depends(
+    &self,
+    curs: &mut Cursor<Vec<u8>>,
+    names: &Names,
+    imports: &Imports,
+  ) -> Result<()> {
+    for export in self.exports.iter() {
+      write_u32(curs, export.depends.len() as u32)?;
+      for dep in export.depends.iter() {
+        dep
+          .write(curs, names, imports, self)
+          .with_context(|| format!("Depends of {}", export.object_name.to_string(names)))?;
+      }
+    }
     Ok(())
   }
 
-  pub fn byte_size(&self) -> usize {
-    self.data.len()
+  /// Removes dependencies on objects that are no longer imported or exported
+  pub fn remove_missing_depends(&mut self, imports: &Imports) {
+    let exported: Vec<NameVariant> = self
+      .exports
+      .iter()
+      .map(|export| export.object_name.clone())
+      .collect();
+    for export in self.exports.iter_mut() {
+      export.depends.retain(|dep| match &dep.reference {
+        Reference::UObject => true,
+        Reference::Import { class, name } => imports.index_of(class, name).is_some(),
+        Reference::Export(name) => exported.contains(name),
+      });
+    }
+  }
+
+  pub fn depends_byte_size(&self) -> usize {
+    // Length of each list, then 4 bytes per package index
+    self
+      .exports
+      .iter()
+      .map(|export| 4 + 4 * export.depends.len())
+      .sum()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn dependencies_on_duplicate_names_keep_their_index() {
+    let package = TestPackage::new(&["/Script/Engine", "Class", "Actor", "Pawn"]);
+    let names = &package.names;
+    let name = |name| NameVariant::new(name, 0, names);
+    let mut imports = Imports { objects: vec![] };
+    for object in ["Actor", "Pawn"] {
+      imports.add(
+        name("/Script/Engine"),
+        name("Class"),
+        name(object),
+        Reference::UObject,
+      );
+    }
+    // Both imports are now Class # Actor
+    imports.objects[1].name = name("Actor");
+
+    let write = |dependency: &Dependency| {
+      let mut curs = Cursor::new(vec![]);
+      dependency
+        .write(&mut curs, names, &imports, &package.exports)
+        .map(|_| curs.into_inner())
+    };
+    let mut rdr = ByteReader::new((-2i32).to_le_bytes().to_vec());
+    let dependency = Dependency::read(&mut rdr, &imports, &package.exports).unwrap();
+    assert_eq!(write(&dependency).unwrap(), (-2i32).to_le_bytes());
+
+    // Without an index to go by, the name is ambiguous
+    let err = write(&Dependency::new(dependency.reference)).unwrap_err();
+    assert!(err.to_string().contains("more than one import"));
   }
 }
@@ -8,21 +8,20 @@ use std::io::Cursor;
 
 #[derive(Debug)]
 pub struct Export {
-  pub class: u32,       // just storing u32 because idc what this is
-  pub super_index: i32, // not sure what this represents
-  pub template: u32,    // just storing u32 because idc what this is
-  pub outer: i32,       // again, idk
+  pub class: Reference,        // UObject for UClass exports
+  pub super_struct: Reference, // Parent struct, only set for struct exports
+  pub template: Reference,     // Archetype this was created from
+  pub outer: Reference,        // UObject for top level exports
   pub object_name: NameVariant,
-  pub object_flags: u32,       // just some bytes
+  pub object_flags: ObjectFlags,
   pub serial_size: u64, // size of uexp struct, 4 bytes of padding after this are incorporated into the value?
-  pub serial_offset: u32, // same as file_summary.total_header_size
+  pub serial_offset: u64, // same as file_summary.total_header_size
   pub export_file_offset: u64, // NOT STORED IN FILE
   pub forced_export: bool, // 4 bytes
   pub not_for_client: bool, // 4 bytes
   pub not_for_server: bool, // 4 bytes
-  pub was_filtered: bool, // 4 bytes
   pub package_guid: [u8; 16],
-  pub package_flags: u32,
+  pub package_flags: PackageFlags,
   pub not_always_loaded_for_editor_game: bool, // 4 bytes
   pub is_asset: bool,                          // 4 bytes
   pub first_export_dependency: u32,
@@ -30,6 +29,11 @@ pub struct Export {
   pub create_before_serialization_dependencies: u32,
   pub serialization_before_create_dependencies: u32,
   pub create_before_create_dependencies: u32,
+  // Objects this export depends on, from the depends map
+  pub depends: Vec<Dependency>,
+  // Indices the references were read from, which are written back while they
+  // still point at objects with the same names
+  original_indices: ExportIndices,
 }
 
 #[derive(Debug)]
@@ -37,34 +41,54 @@ pub struct Exports {
   pub exports: Vec<Export>,
 }
 
+// Unresolved class, super_struct, template, and outer indices of an export.
+// They can point at exports that come later in the table, so they are only
+// resolved once the whole table is read.
+type ExportIndices = [i32; 4];
+
 impl Export {
-  fn read(rdr: &mut ByteReader, names: &Names) -> Result<Self> {
-    let class = read_u32(rdr)?;
-    let super_index = rdr.read_i32::<LittleEndian>()?;
-    let template = read_u32(rdr)?;
+  fn read(rdr: &mut ByteReader, ue4_version: i32, names: &Names) -> Result<(Self, ExportIndices)> {
+    let class = rdr.read_i32::<LittleEndian>()?;
+    let super_struct = rdr.read_i32::<LittleEndian>()?;
+    let template = if ue4_version >= ue4_version::TEMPLATE_INDEX_IN_COOKED_EXPORTS {
+      rdr.read_i32::<LittleEndian>()?
+    } else {
+      0
+    };
     let outer = rdr.read_i32::<LittleEndian>()?;
     let object_name = NameVariant::read(rdr, names).with_context(|| "object_name")?;
-    let object_flags = rdr.read_u32::<LittleEndian>()?;
-    let serial_size = rdr.read_u64::<LittleEndian>()?;
-    let serial_offset = read_u32(rdr)?;
+    let object_flags = ObjectFlags::from_bits_retain(rdr.read_u32::<LittleEndian>()?);
+    let (serial_size, serial_offset) = if ue4_version >= ue4_version::EXPORT_MAP_64BIT_SERIAL_SIZES
+    {
+      (
+        rdr.read_u64::<LittleEndian>()?,
+        rdr.read_u64::<LittleEndian>()?,
+      )
+    } else {
+      (read_u32(rdr)? as u64, read_u32(rdr)? as u64)
+    };
     let forced_export = read_bool(rdr)?;
     let not_for_client = read_bool(rdr)?;
     let not_for_server = read_bool(rdr)?;
-    let was_filtered = read_bool(rdr)?;
     let package_guid: [u8; 16] = read_bytes(rdr, 16)?;
-    let package_flags = read_u32(rdr)?;
-    let not_always_loaded_for_editor_game = read_bool(rdr)?;
-    let is_asset = read_bool(rdr)?;
-    let first_export_dependency = read_u32(rdr)?;
-    let serialization_before_serialization_dependencies = read_u32(rdr)?;
-    let create_before_serialization_dependencies = read_u32(rdr)?;
-    let serialization_before_create_dependencies = read_u32(rdr)?;
-    let create_before_create_dependencies = read_u32(rdr)?;
-    Ok(Export {
-      class,
-      super_index,
-      template,
-      outer,
+    let package_flags = PackageFlags::from_bits_retain(read_u32(rdr)?);
+    let not_always_loaded_for_editor_game = ue4_version >= ue4_version::LOAD_FOR_EDITOR_GAME
+      && read_bool(rdr).with_context(|| "not_always_loaded_for_editor_game")?;
+    let is_asset = ue4_version >= ue4_version::COOKED_ASSETS_IN_EDITOR_SUPPORT
+      && read_bool(rdr).with_context(|| "is_asset")?;
+    let mut dependencies = [0; 5];
+    if ue4_version >= ue4_version::PRELOAD_DEPENDENCIES_IN_COOKED_EXPORTS {
+      for dependency in dependencies.iter_mut() {
+        *dependency = read_u32(rdr)?;
+      }
+    }
+    let [first_export_dependency, serialization_before_serialization_dependencies, create_before_serialization_dependencies, serialization_before_create_dependencies, create_before_create_dependencies] =
+      dependencies;
+    let export = Export {
+      class: Reference::UObject,
+      super_struct: Reference::UObject,
+      template: Reference::UObject,
+      outer: Reference::UObject,
       object_name,
       object_flags,
       serial_size,
@@ -73,7 +97,6 @@ impl Export {
       forced_export,
       not_for_client,
       not_for_server,
-      was_filtered,
       package_guid,
       package_flags,
       not_always_loaded_for_editor_game,
@@ -83,40 +106,112 @@ impl Export {
       create_before_serialization_dependencies,
       serialization_before_create_dependencies,
       create_before_create_dependencies,
-    })
+      depends: vec![],
+      original_indices: [class, super_struct, template, outer],
+    };
+    Ok((export, [class, super_struct, template, outer]))
+  }
+
+  /// Name of the class of this export. Exports without a class are UClasses.
+  pub fn class_name(&self, names: &Names) -> String {
+    match &self.class {
+      Reference::UObject => "Class".to_string(),
+      Reference::Import { name, .. } => name.to_string(names),
+      Reference::Export(name) => name.to_string(names),
+    }
   }
 
-  pub fn write(&self, curs: &mut Cursor<Vec<u8>>, names: &Names) -> Result<()> {
-    write_u32(curs, self.class)?;
-    curs.write_i32::<LittleEndian>(self.super_index)?;
-    write_u32(curs, self.template)?;
-    curs.write_i32::<LittleEndian>(self.outer)?;
+  pub fn is_class_default_object(&self) -> bool {
+    self
+      .object_flags
+      .contains(ObjectFlags::CLASS_DEFAULT_OBJECT)
+  }
+
+  pub fn write(
+    &self,
+    curs: &mut Cursor<Vec<u8>>,
+    ue4_version: i32,
+    names: &Names,
+    imports: &Imports,
+    exports: &Exports,
+  ) -> Result<()> {
+    let [class, super_struct, template, outer] = self.original_indices;
+    let index_of = |reference: &Reference, original: i32, field: &str| {
+      reference
+        .index_for_write(original, names, imports, exports)
+        .with_context(|| field.to_string())
+    };
+    curs.write_i32::<LittleEndian>(index_of(&self.class, class, "class")?)?;
+    curs.write_i32::<LittleEndian>(index_of(&self.super_struct, super_struct, "super_struct")?)?;
+    if ue4_version >= ue4_version::TEMPLATE_INDEX_IN_COOKED_EXPORTS {
+      curs.write_i32::<LittleEndian>(index_of(&self.template, template, "template")?)?;
+    }
+    curs.write_i32::<LittleEndian>(index_of(&self.outer, outer, "outer")?)?;
     self
       .object_name
       .write(curs, names)
       .with_context(|| "object_name")?;
-    curs.write_u32::<LittleEndian>(self.object_flags)?;
-    curs.write_u64::<LittleEndian>(self.serial_size)?;
-    write_u32(curs, self.serial_offset)?;
+    curs.write_u32::<LittleEndian>(self.object_flags.bits())?;
+    if ue4_version >= ue4_version::EXPORT_MAP_64BIT_SERIAL_SIZES {
+      curs.write_u64::<LittleEndian>(self.serial_size)?;
+      curs.write_u64::<LittleEndian>(self.serial_offset)?;
+    } else {
+      write_u32(curs, self.serial_size as u32)?;
+      write_u32(curs, self.serial_offset as u32)?;
+    }
     write_bool(curs, self.forced_export)?;
     write_bool(curs, self.not_for_client)?;
     write_bool(curs, self.not_for_server)?;
-    write_bool(curs, self.was_filtered)?;
     curs.write_all(&self.package_guid)?;
-    write_u32(curs, self.package_flags)?;
-    write_bool(curs, self.not_always_loaded_for_editor_game)?;
-    write_bool(curs, self.is_asset)?;
-    write_u32(curs, self.first_export_dependency)?;
-    write_u32(curs, self.serialization_before_serialization_dependencies)?;
-    write_u32(curs, self.create_before_serialization_dependencies)?;
-    write_u32(curs, self.serialization_before_create_dependencies)?;
-    write_u32(curs, self.create_before_create_dependencies)?;
+    write_u32(curs, self.package_flags.bits())?;
+    if ue4_version >= ue4_version::LOAD_FOR_EDITOR_GAME {
+      write_bool(curs, self.not_always_loaded_for_editor_game)?;
+    }
+    if ue4_version >= ue4_version::COOKED_ASSETS_IN_EDITOR_SUPPORT {
+      write_bool(curs, self.is_asset)?;
+    }
+    if ue4_version >= ue4_version::PRELOAD_DEPENDENCIES_IN_COOKED_EXPORTS {
+      write_u32(curs, self.first_export_dependency)?;
+      write_u32(curs, self.serialization_before_serialization_dependencies)?;
+      write_u32(curs, self.create_before_serialization_dependencies)?;
+      write_u32(curs, self.serialization_before_create_dependencies)?;
+      write_u32(curs, self.create_before_create_dependencies)?;
+    }
     Ok(())
   }
+
+  fn byte_size(ue4_version: i32) -> usize {
+    // Class, super struct, outer, name, flags, forced export, not for
+    // client/server, package guid, and package flags
+    let mut size = 4 + 4 + 4 + 8 + 4 + 12 + 16 + 4;
+    if ue4_version >= ue4_version::TEMPLATE_INDEX_IN_COOKED_EXPORTS {
+      size += 4;
+    }
+    size += if ue4_version >= ue4_version::EXPORT_MAP_64BIT_SERIAL_SIZES {
+      16
+    } else {
+      8
+    };
+    if ue4_version >= ue4_version::LOAD_FOR_EDITOR_GAME {
+      size += 4;
+    }
+    if ue4_version >= ue4_version::COOKED_ASSETS_IN_EDITOR_SUPPORT {
+      size += 4;
+    }
+    if ue4_version >= ue4_version::PRELOAD_DEPENDENCIES_IN_COOKED_EXPORTS {
+      size += 20;
+    }
+    size
+  }
 }
 
 impl Exports {
-  pub fn read(rdr: &mut ByteReader, summary: &FileSummary, names: &Names) -> Result<Self> {
+  pub fn read(
+    rdr: &mut ByteReader,
+    summary: &FileSummary,
+    names: &Names,
+    imports: &Imports,
+  ) -> Result<Self> {
     if rdr.position() != summary.export_offset as u64 {
       bail!(
         "Wrong exports starting position: Expected to be at position {:#X}, but I'm at position {:#X}",
@@ -126,10 +221,11 @@ impl Exports {
     }
 
     let mut exports = vec![];
+    let mut all_indices = vec![];
     let mut export_file_offset = 0;
     for _ in 0..summary.export_count {
       let start_pos = rdr.position();
-      let mut object = Export::read(rdr, names)
+      let (mut object, indices) = Export::read(rdr, summary.ue4_version(), names)
         .with_context(|| format!("Failed to parse export starting at {:#X}", start_pos))?;
 
       // Compute export_file_offset based on the size of preceeding exports
@@ -137,13 +233,38 @@ impl Exports {
       export_file_offset += object.serial_size;
 
       exports.push(object);
+      all_indices.push(indices);
+    }
+
+    let mut exports = Exports { exports };
+    for (i, [class, super_struct, template, outer]) in all_indices.into_iter().enumerate() {
+      let resolve = |idx: i32, field: &str| {
+        Reference::deserialize(idx, imports, &exports)
+          .with_context(|| format!("Export {} {}", i, field))
+      };
+      let class = resolve(class, "class")?;
+      let super_struct = resolve(super_struct, "super_struct")?;
+      let template = resolve(template, "template")?;
+      let outer = resolve(outer, "outer")?;
+
+      let export = &mut exports.exports[i];
+      export.class = class;
+      export.super_struct = super_struct;
+      export.template = template;
+      export.outer = outer;
     }
-    Ok(Exports { exports })
+    Ok(exports)
   }
 
-  pub fn write(&self, curs: &mut Cursor<Vec<u8>>, names: &Names) -> Result<()> {
+  pub fn write(
+    &self,
+    curs: &mut Cursor<Vec<u8>>,
+    summary: &FileSummary,
+    names: &Names,
+    imports: &Imports,
+  ) -> Result<()> {
     for export in self.exports.iter() {
-      export.write(curs, names)?;
+      export.write(curs, summary.ue4_version(), names, imports, self)?;
     }
     Ok(())
   }
@@ -157,6 +278,59 @@ impl Exports {
     None
   }
 
+  /// Gets the index of the only export with the name `object`
+  pub fn unique_index_of(&self, object: &NameVariant, names: &Names) -> Result<i32> {
+    let found: Vec<i32> = self
+      .exports
+      .iter()
+      .enumerate()
+      .filter(|(_, export)| export.object_name == *object)
+      .map(|(i, _)| i as i32 + 1)
+      .collect();
+    match found[..] {
+      [index] => Ok(index),
+      [] => bail!("Name {} is not exported", object.to_string(names)),
+      _ => bail!(
+        "Name {} is the name of more than one export",
+        object.to_string(names)
+      ),
+    }
+  }
+
+  /// Gets the export an export is in, if its outer is an export
+  pub fn outer_of(&self, export: &Export) -> Option<&Export> {
+    let name = match &export.outer {
+      Reference::Export(name) => name,
+      _ => return None,
+    };
+    let original = export.original_indices[3];
+    let by_index = if original > 0 {
+      self
+        .exports
+        .get(original as usize - 1)
+        .filter(|outer| outer.object_name == *name)
+    } else {
+      None
+    };
+    by_index.or_else(|| self.exports.iter().find(|outer| outer.object_name == *name))
+  }
+
+  /// Whether an export is a template, meaning it or one of its outers is a
+  /// class default object or an archetype, like UObject::IsTemplate
+  pub fn is_template(&self, export: &Export) -> bool {
+    let template_flags = ObjectFlags::CLASS_DEFAULT_OBJECT | ObjectFlags::ARCHETYPE_OBJECT;
+    let mut object = Some(export);
+    // Bounded in case an outer chain loops
+    for _ in 0..=self.exports.len() {
+      match object {
+        Some(export) if export.object_flags.intersects(template_flags) => return true,
+        Some(export) => object = self.outer_of(export),
+        None => return false,
+      }
+    }
+    false
+  }
+
   pub fn lookup(&self, index: u64) -> Result<&Export> {
     if index > self.exports.len() as u64 {
       bail!(
@@ -168,8 +342,198 @@ impl Exports {
     Ok(&self.exports[index as usize])
   }
 
-  pub fn byte_size(&self) -> usize {
-    // Always 104 bytes long
-    104 * self.exports.len()
+  pub fn byte_size(&self, summary: &FileSummary) -> usize {
+    Export::byte_size(summary.ue4_version()) * self.exports.len()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// An export table entry, with every other field zeroed
+  fn export_bytes(
+    indices: ExportIndices,
+    name: &NameVariant,
+    flags: u32,
+    names: &Names,
+  ) -> Vec<u8> {
+    let mut curs = Cursor::new(vec![]);
+    for index in indices.iter() {
+      curs.write_i32::<LittleEndian>(*index).unwrap();
+    }
+    name.write(&mut curs, names).unwrap();
+    curs.write_u32::<LittleEndian>(flags).unwrap();
+    curs.write_all(&[0; 16 + 12 + 16 + 4 + 8 + 20]).unwrap();
+    curs.into_inner()
+  }
+
+  /// A package with a blueprint class, its class default object, and a
+  /// subobject of the class default object. Returns the export table too.
+  fn blueprint_package() -> (TestPackage, Vec<u8>) {
+    let mut package = TestPackage::new(&[
+      "/Script/Engine",
+      "Class",
+      "BlueprintGeneratedClass",
+      "Actor",
+      "Default__Actor",
+      "BP_C",
+      "Default__BP_C",
+      "Root",
+    ]);
+    let names = &package.names;
+    let name = |name| NameVariant::new(name, 0, names);
+    for (class, object) in [
+      ("Class", "BlueprintGeneratedClass"),
+      ("Class", "Actor"),
+      ("Actor", "Default__Actor"),
+    ] {
+      package.imports.add(
+        name("/Script/Engine"),
+        name(class),
+        name(object),
+        Reference::UObject,
+      );
+    }
+    let public = ObjectFlags::PUBLIC.bits();
+    let cdo_flags = public | ObjectFlags::CLASS_DEFAULT_OBJECT.bits();
+    let subobject_flags = ObjectFlags::DEFAULT_SUB_OBJECT.bits();
+    let data = [
+      export_bytes([-1, -2, 0, 0], &name("BP_C"), public, names),
+      export_bytes([1, 0, -3, 0], &name("Default__BP_C"), cdo_flags, names),
+      export_bytes([-2, 0, 0, 2], &name("Root"), subobject_flags, names),
+    ]
+    .concat();
+    package.summary.export_count = 3;
+    (package, data)
+  }
+
+  #[test]
+  fn export_references_and_flags_are_resolved() {
+    let (package, data) = blueprint_package();
+    let names = &package.names;
+    let mut rdr = ByteReader::new(data.clone());
+    let exports = Exports::read(&mut rdr, &package.summary, names, &package.imports).unwrap();
+    let (class, cdo, root) = (
+      &exports.exports[0],
+      &exports.exports[1],
+      &exports.exports[2],
+    );
+    assert_eq!(class.class_name(names), "BlueprintGeneratedClass");
+    assert!(
+      matches!(&class.super_struct, Reference::Import { name, .. } if name.to_string(names) == "Actor")
+    );
+    assert_eq!(cdo.class_name(names), "BP_C");
+    assert!(
+      matches!(&cdo.template, Reference::Import { name, .. } if name.to_string(names) == "Default__Actor")
+    );
+    assert!(cdo.is_class_default_object());
+    assert!(matches!(&root.outer, Reference::Export(outer) if *outer == cdo.object_name));
+    // Subobjects of the class default object are templates too
+    assert!(!exports.is_template(class));
+    assert!(exports.is_template(root));
+
+    assert_eq!(exports.byte_size(&package.summary), data.len());
+    let mut curs = Cursor::new(vec![]);
+    exports
+      .write(&mut curs, &package.summary, names, &package.imports)
+      .unwrap();
+    assert_eq!(curs.into_inner(), data);
+  }
+
+  /// Reads properties of the export at `index`, checking that they're
+  /// written back the same
+  fn read_properties(package: &mut TestPackage, index: usize, data: &[&[u8]]) -> Properties {
+    let data = data.concat();
+    package.exports.exports[index].serial_size = data.len() as u64;
+    let export = &package.exports.exports[index];
+    let mut rdr = ByteReader::new(data.clone());
+    let properties = Properties::deserialize(&mut rdr, export, package.ctx()).unwrap();
+    assert_eq!(properties.byte_size(package.ctx()), data.len());
+    let mut curs = Cursor::new(vec![]);
+    properties.serialize(&mut curs, package.ctx()).unwrap();
+    assert_eq!(curs.into_inner(), data);
+    properties
+  }
+
+  #[test]
+  fn only_objects_that_arent_templates_have_a_guid_flag() {
+    let (mut package, data) = blueprint_package();
+    let mut rdr = ByteReader::new(data);
+    package.exports =
+      Exports::read(&mut rdr, &package.summary, &package.names, &package.imports).unwrap();
+    let none = &[0; 8][..];
+    let native = &[0xAA; 4][..];
+    let guid: Vec<u8> = (1..=16).collect();
+    let has_guid = &[&[1, 0, 0, 0][..], &guid].concat()[..];
+
+    let class = read_properties(&mut package, 0, &[none, &[0; 4], native]);
+    assert_eq!(class.object_guid, None);
+    assert_eq!(class.unknown_trailing_data(), native);
+    let class = read_properties(&mut package, 0, &[none, has_guid]);
+    assert_eq!(class.object_guid.as_ref().map(|g| &g[..]), Some(&guid[..]));
+    assert!(class.unknown_trailing_data().is_empty());
+
+    // Templates don't have the flag, so it's all native data
+    let cdo = read_properties(&mut package, 1, &[none, has_guid]);
+    assert_eq!(cdo.object_guid, None);
+    assert_eq!(cdo.unknown_trailing_data(), has_guid);
+    let root = read_properties(&mut package, 2, &[none, &[0; 4], native]);
+    assert_eq!(root.unknown_trailing_data(), [&[0; 4][..], native].concat());
+  }
+
+  #[test]
+  fn older_export_tables_leave_out_newer_fields() {
+    let mut package = TestPackage::new(&["Thing"]);
+    package.summary.export_count = 1;
+    let name = NameVariant::new("Thing", 0, &package.names);
+    let names = &package.names;
+    let entry = |ue4_version| {
+      let mut curs = Cursor::new(vec![]);
+      // Class, super struct, and outer, without a template
+      curs.write_all(&[0; 12]).unwrap();
+      name.write(&mut curs, names).unwrap();
+      curs
+        .write_u32::<LittleEndian>(ObjectFlags::PUBLIC.bits())
+        .unwrap();
+      // 32 bit serial size and offset
+      curs.write_u32::<LittleEndian>(0x30).unwrap();
+      curs.write_u32::<LittleEndian>(0x200).unwrap();
+      curs.write_all(&[0; 12 + 16 + 4]).unwrap();
+      if ue4_version >= ue4_version::LOAD_FOR_EDITOR_GAME {
+        curs.write_all(&[0; 4]).unwrap();
+      }
+      if ue4_version >= ue4_version::COOKED_ASSETS_IN_EDITOR_SUPPORT {
+        curs.write_all(&[1, 0, 0, 0]).unwrap();
+      }
+      curs.into_inner()
+    };
+
+    for (ue4_version, size, is_asset) in [(500, 72, true), (400, 68, false), (300, 64, false)] {
+      package.summary.file_version_ue4 = ue4_version;
+      let data = entry(ue4_version);
+      assert_eq!(data.len(), size);
+      let mut rdr = ByteReader::new(data.clone());
+      let exports =
+        Exports::read(&mut rdr, &package.summary, &package.names, &package.imports).unwrap();
+      let export = &exports.exports[0];
+      assert_eq!(export.object_name, name);
+      assert!(matches!(export.template, Reference::UObject));
+      assert_eq!(export.serial_size, 0x30);
+      assert_eq!(export.serial_offset, 0x200);
+      assert_eq!(export.is_asset, is_asset);
+
+      assert_eq!(exports.byte_size(&package.summary), size);
+      let mut curs = Cursor::new(vec![]);
+      exports
+        .write(
+          &mut curs,
+          &package.summary,
+          &package.names,
+          &package.imports,
+        )
+        .unwrap();
+      assert_eq!(curs.into_inner(), data);
+    }
   }
 }
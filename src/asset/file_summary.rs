@@ -1,3 +1,4 @@
+use crate::asset::*;
 use crate::reader::*;
 use crate::util::*;
 use anyhow::*;
@@ -5,40 +6,91 @@ use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::io::prelude::*;
 use std::io::Cursor;
 
+/// Package file versions (`EUnrealEngineObjectUE4Version`) that change the
+/// layout of the header
+pub mod ue4_version {
+  pub const WORLD_LEVEL_INFO: i32 = 224;
+  pub const ADDED_CHUNKID_TO_ASSETDATA_AND_UPACKAGE: i32 = 278;
+  pub const CHANGED_CHUNKID_TO_BE_AN_ARRAY_OF_CHUNKIDS: i32 = 326;
+  pub const ENGINE_VERSION_OBJECT: i32 = 336;
+  pub const LOAD_FOR_EDITOR_GAME: i32 = 365;
+  pub const ADD_STRING_ASSET_REFERENCES_MAP: i32 = 384;
+  pub const PACKAGE_SUMMARY_HAS_COMPATIBLE_ENGINE_VERSION: i32 = 444;
+  pub const SERIALIZE_TEXT_IN_PACKAGES: i32 = 459;
+  pub const COOKED_ASSETS_IN_EDITOR_SUPPORT: i32 = 485;
+  pub const PRELOAD_DEPENDENCIES_IN_COOKED_EXPORTS: i32 = 507;
+  pub const TEMPLATE_INDEX_IN_COOKED_EXPORTS: i32 = 508;
+  pub const ADDED_SEARCHABLE_NAMES: i32 = 510;
+  pub const EXPORT_MAP_64BIT_SERIAL_SIZES: i32 = 511;
+  pub const ADDED_PACKAGE_SUMMARY_LOCALIZATION_ID: i32 = 516;
+
+  /// Version assumed for unversioned packages, the latest version of UE 4.25
+  pub const UNVERSIONED: i32 = 518;
+}
+
+const PACKAGE_FILE_TAG: [u8; 4] = [0xC1, 0x83, 0x2A, 0x9E];
+
+// Oldest and newest LegacyFileVersion that can be read. Versions below -7
+// are UE5 packages.
+const OLDEST_LEGACY_FILE_VERSION: i32 = -1;
+const NEWEST_LEGACY_FILE_VERSION: i32 = -7;
+
 #[derive(Debug)]
 pub struct Generation {
   pub export_count: u32,
   pub name_count: u32,
 }
 
+/// Version of one of the engine's custom serialization formats
+#[derive(Debug, Clone)]
+pub struct CustomVersion {
+  pub key: [u8; 16],
+  pub version: i32,
+  // Only serialized by packages with a LegacyFileVersion of -3 to -5
+  pub friendly_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EngineVersion {
+  pub major: u16,
+  pub minor: u16,
+  pub patch: u16,
+  pub changelist: u32,
+  pub branch: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct FileSummary {
-  pub tag: [u8; 4],                  // 0000-0003
-  pub file_version_ue4: u32,         // 0004-0007 ?
-  pub file_version_license_ue4: u32, // 0008-000B
-  pub custom_version: [u8; 12],      // 000C-0017
+  pub tag: [u8; 4],
+  pub legacy_file_version: i32,
+  // Not present when legacy_file_version is -4
+  pub legacy_ue3_version: i32,
+  // Unversioned packages have 0 for both versions and no custom versions
+  pub file_version_ue4: i32,
+  pub file_version_licensee_ue4: i32,
+  pub custom_versions: Vec<CustomVersion>,
 
-  pub total_header_size: u32, // 0018-001B
+  pub total_header_size: u32,
 
-  pub package_flags: u32, // 0025-0028 ?
-
-  // in drg this is always from "None" and from 001C-0024
   pub folder_name: String, // u32 size, then null terminated string
 
-  pub name_count: u32,     // 0029-002C
-  pub name_offset: u32,    // 002D-0030
-  pub localization_id: (), // seems to always be null and take up 0 bytes
+  pub package_flags: u32,
 
-  pub gatherable_text_data_count: u32,  // 0031-0034
-  pub gatherable_text_data_offset: u32, // 0035-0038
+  pub name_count: u32,
+  pub name_offset: u32,
+  // Only present in packages that aren't filtered for editor only data
+  pub localization_id: Option<String>,
 
-  pub export_count: u32,  // 0039-003C
-  pub export_offset: u32, // 003D-0040
+  pub gatherable_text_data_count: u32,
+  pub gatherable_text_data_offset: u32,
 
-  pub import_count: u32,  // 0041-0044
-  pub import_offset: u32, // 0045-0049
+  pub export_count: u32,
+  pub export_offset: u32,
 
-  pub depends_offset: u32, // 0049-004C
+  pub import_count: u32,
+  pub import_offset: u32,
+
+  pub depends_offset: u32,
 
   pub soft_package_references_count: u32,
   pub soft_package_references_offset: u32,
@@ -47,17 +99,20 @@ pub struct FileSummary {
   pub guid: [u8; 16],
   pub generations: Vec<Generation>,
 
-  pub saved_by_engine_version: [u8; 16],
-  pub compatible_with_engine_version: [u8; 16],
+  pub saved_by_engine_version: EngineVersion,
+  pub compatible_with_engine_version: EngineVersion,
   pub compression_flags: u32,
-  pub package_source: i64,
-
-  pub asset_registry_data_offset: u32,  // 00A5-00A8
-  pub bulk_data_start_offset: u32,      // 00A9-00AC
-  pub world_tile_info_data_offset: u32, // 00AD-00B0
-  pub chunk_ids: u64, // this should be an array of some structs, idk what they are though
-  pub preload_dependency_count: u32, // 00B9-00BC
-  pub preload_dependency_offset: u32, // 00BD-00CA
+  pub package_source: u32,
+  pub additional_packages_to_cook: Vec<Option<String>>,
+  // Only present when legacy_file_version is above -7
+  pub num_texture_allocations: i32,
+
+  pub asset_registry_data_offset: u32,
+  pub bulk_data_start_offset: i64,
+  pub world_tile_info_data_offset: i32,
+  pub chunk_ids: Vec<i32>,
+  pub preload_dependency_count: u32,
+  pub preload_dependency_offset: u32,
 }
 
 impl Generation {
@@ -94,52 +149,254 @@ impl Generation {
   }
 }
 
+impl CustomVersion {
+  fn read_array(rdr: &mut ByteReader, legacy_file_version: i32) -> Result<Vec<Self>> {
+    let count = read_u32(rdr)?;
+    let mut custom_versions = vec![];
+    for _ in 0..count {
+      let custom_version = match legacy_file_version {
+        // Enum keys, kept in the last 4 bytes of the key
+        -2 => {
+          let tag = read_u32(rdr)?;
+          let mut key = [0; 16];
+          key[12..].copy_from_slice(&tag.to_le_bytes());
+          let version = rdr.read_i32::<LittleEndian>()?;
+          Self {
+            key,
+            version,
+            friendly_name: None,
+          }
+        }
+        // Guid keys with friendly names
+        -5..=-3 => {
+          let key = read_bytes(rdr, 16)?;
+          let version = rdr.read_i32::<LittleEndian>()?;
+          let friendly_name = read_fstring(rdr)?;
+          Self {
+            key,
+            version,
+            friendly_name,
+          }
+        }
+        _ => {
+          let key = read_bytes(rdr, 16)?;
+          let version = rdr.read_i32::<LittleEndian>()?;
+          Self {
+            key,
+            version,
+            friendly_name: None,
+          }
+        }
+      };
+      custom_versions.push(custom_version);
+    }
+    Ok(custom_versions)
+  }
+
+  fn write_array(
+    custom_versions: &[Self],
+    curs: &mut Cursor<Vec<u8>>,
+    legacy_file_version: i32,
+  ) -> Result<()> {
+    write_u32(curs, custom_versions.len() as u32)?;
+    for custom_version in custom_versions.iter() {
+      match legacy_file_version {
+        -2 => curs.write_all(&custom_version.key[12..])?,
+        _ => curs.write_all(&custom_version.key)?,
+      }
+      curs.write_i32::<LittleEndian>(custom_version.version)?;
+      if let -5..=-3 = legacy_file_version {
+        write_fstring(curs, &custom_version.friendly_name)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn array_size(custom_versions: &[Self], legacy_file_version: i32) -> usize {
+    4 + custom_versions
+      .iter()
+      .map(|custom_version| match legacy_file_version {
+        -2 => 8,
+        -5..=-3 => 20 + fstring_size(&custom_version.friendly_name),
+        _ => 20,
+      })
+      .sum::<usize>()
+  }
+}
+
+impl EngineVersion {
+  /// Reads an engine version. Packages before `ENGINE_VERSION_OBJECT` only
+  /// store the changelist.
+  fn read(rdr: &mut ByteReader, ue4_version: i32) -> Result<Self> {
+    if ue4_version < ue4_version::ENGINE_VERSION_OBJECT {
+      let changelist = read_u32(rdr)?;
+      return Ok(Self {
+        changelist,
+        ..Self::default()
+      });
+    }
+    let major = rdr.read_u16::<LittleEndian>()?;
+    let minor = rdr.read_u16::<LittleEndian>()?;
+    let patch = rdr.read_u16::<LittleEndian>()?;
+    let changelist = read_u32(rdr)?;
+    let branch = read_fstring(rdr)?;
+    Ok(Self {
+      major,
+      minor,
+      patch,
+      changelist,
+      branch,
+    })
+  }
+
+  fn write(&self, curs: &mut Cursor<Vec<u8>>, ue4_version: i32) -> Result<()> {
+    if ue4_version < ue4_version::ENGINE_VERSION_OBJECT {
+      return write_u32(curs, self.changelist);
+    }
+    curs.write_u16::<LittleEndian>(self.major)?;
+    curs.write_u16::<LittleEndian>(self.minor)?;
+    curs.write_u16::<LittleEndian>(self.patch)?;
+    write_u32(curs, self.changelist)?;
+    write_fstring(curs, &self.branch)?;
+    Ok(())
+  }
+
+  fn byte_size(&self, ue4_version: i32) -> usize {
+    if ue4_version < ue4_version::ENGINE_VERSION_OBJECT {
+      4
+    } else {
+      10 + fstring_size(&self.branch)
+    }
+  }
+}
+
 impl FileSummary {
   pub fn read(rdr: &mut ByteReader) -> Result<Self> {
     let tag: [u8; 4] = read_bytes(rdr, 4)?;
-    let file_version_ue4 = read_u32(rdr)?;
-    let file_version_license_ue4 = read_u32(rdr)?;
-    let custom_version: [u8; 12] = read_bytes(rdr, 12)?;
+    if tag != PACKAGE_FILE_TAG {
+      bail!("Not a package file, the tag is {:02X?}", tag);
+    }
+    let legacy_file_version = rdr.read_i32::<LittleEndian>()?;
+    if !(NEWEST_LEGACY_FILE_VERSION..=OLDEST_LEGACY_FILE_VERSION).contains(&legacy_file_version) {
+      bail!(
+        "Unsupported legacy file version {}, expected {} to {}",
+        legacy_file_version,
+        OLDEST_LEGACY_FILE_VERSION,
+        NEWEST_LEGACY_FILE_VERSION
+      );
+    }
+    let legacy_ue3_version = if legacy_file_version != -4 {
+      rdr.read_i32::<LittleEndian>()?
+    } else {
+      0
+    };
+    let file_version_ue4 = rdr.read_i32::<LittleEndian>()?;
+    let file_version_licensee_ue4 = rdr.read_i32::<LittleEndian>()?;
+    let custom_versions = if legacy_file_version <= -2 {
+      CustomVersion::read_array(rdr, legacy_file_version).with_context(|| "Custom versions")?
+    } else {
+      vec![]
+    };
+    let ue4_version = Self::effective_ue4_version(file_version_ue4);
+
     let total_header_size = read_u32(rdr)?;
     let folder_name = read_string(rdr)?;
     let package_flags = read_u32(rdr)?;
     let name_count = read_u32(rdr)?;
     let name_offset = read_u32(rdr)?;
-    let gatherable_text_data_count = read_u32(rdr)?;
-    let gatherable_text_data_offset = read_u32(rdr)?;
+    let localization_id = if Self::has_localization_id(ue4_version, package_flags) {
+      read_fstring(rdr)?
+    } else {
+      None
+    };
+    let (gatherable_text_data_count, gatherable_text_data_offset) =
+      if ue4_version >= ue4_version::SERIALIZE_TEXT_IN_PACKAGES {
+        (read_u32(rdr)?, read_u32(rdr)?)
+      } else {
+        (0, 0)
+      };
     let export_count = read_u32(rdr)?;
     let export_offset = read_u32(rdr)?;
     let import_count = read_u32(rdr)?;
     let import_offset = read_u32(rdr)?;
     let depends_offset = read_u32(rdr)?;
-    let soft_package_references_count = read_u32(rdr)?;
-    let soft_package_references_offset = read_u32(rdr)?;
-    let searchable_names_offset = read_u32(rdr)?;
+    let (soft_package_references_count, soft_package_references_offset) =
+      if ue4_version >= ue4_version::ADD_STRING_ASSET_REFERENCES_MAP {
+        (read_u32(rdr)?, read_u32(rdr)?)
+      } else {
+        (0, 0)
+      };
+    let searchable_names_offset = if ue4_version >= ue4_version::ADDED_SEARCHABLE_NAMES {
+      read_u32(rdr)?
+    } else {
+      0
+    };
     let thumbnail_table_offset = read_u32(rdr)?;
     let guid: [u8; 16] = read_bytes(rdr, 16)?;
     let generations = Generation::read_array(rdr)?;
-    let saved_by_engine_version: [u8; 16] = read_bytes(rdr, 16)?;
-    let compatible_with_engine_version: [u8; 16] = read_bytes(rdr, 16)?;
+    let saved_by_engine_version =
+      EngineVersion::read(rdr, ue4_version).with_context(|| "Saved by engine version")?;
+    let compatible_with_engine_version =
+      if ue4_version >= ue4_version::PACKAGE_SUMMARY_HAS_COMPATIBLE_ENGINE_VERSION {
+        EngineVersion::read(rdr, ue4_version).with_context(|| "Compatible with engine version")?
+      } else {
+        saved_by_engine_version.clone()
+      };
     let compression_flags = read_u32(rdr)?;
-    let package_source = rdr.read_i64::<LittleEndian>()?;
+    let compressed_chunk_count = read_u32(rdr)?;
+    if compressed_chunk_count != 0 {
+      bail!("Compressed packages are not supported");
+    }
+    let package_source = read_u32(rdr)?;
+    let additional_package_count = read_u32(rdr)?;
+    let mut additional_packages_to_cook = vec![];
+    for _ in 0..additional_package_count {
+      additional_packages_to_cook.push(read_fstring(rdr)?);
+    }
+    let num_texture_allocations = if legacy_file_version > -7 {
+      rdr.read_i32::<LittleEndian>()?
+    } else {
+      0
+    };
     let asset_registry_data_offset = read_u32(rdr)?;
-    let bulk_data_start_offset = read_u32(rdr)?;
-    let world_tile_info_data_offset = read_u32(rdr)?;
-    let chunk_ids = rdr.read_u64::<LittleEndian>()?;
-    let preload_dependency_count = read_u32(rdr)?;
-    let preload_dependency_offset = read_u32(rdr)?;
+    let bulk_data_start_offset = rdr.read_i64::<LittleEndian>()?;
+    let world_tile_info_data_offset = if ue4_version >= ue4_version::WORLD_LEVEL_INFO {
+      rdr.read_i32::<LittleEndian>()?
+    } else {
+      0
+    };
+    let chunk_ids = if ue4_version >= ue4_version::CHANGED_CHUNKID_TO_BE_AN_ARRAY_OF_CHUNKIDS {
+      let count = read_u32(rdr)?;
+      let mut chunk_ids = vec![];
+      for _ in 0..count {
+        chunk_ids.push(rdr.read_i32::<LittleEndian>()?);
+      }
+      chunk_ids
+    } else if ue4_version >= ue4_version::ADDED_CHUNKID_TO_ASSETDATA_AND_UPACKAGE {
+      vec![rdr.read_i32::<LittleEndian>()?]
+    } else {
+      vec![]
+    };
+    let (preload_dependency_count, preload_dependency_offset) =
+      if ue4_version >= ue4_version::PRELOAD_DEPENDENCIES_IN_COOKED_EXPORTS {
+        (read_u32(rdr)?, read_u32(rdr)?)
+      } else {
+        (0, 0)
+      };
 
     Ok(FileSummary {
       tag,
+      legacy_file_version,
+      legacy_ue3_version,
       file_version_ue4,
-      file_version_license_ue4,
-      custom_version,
+      file_version_licensee_ue4,
+      custom_versions,
       total_header_size,
       package_flags,
       folder_name,
       name_count,
       name_offset,
-      localization_id: (),
+      localization_id,
       gatherable_text_data_count,
       gatherable_text_data_offset,
       export_count,
@@ -157,6 +414,8 @@ impl FileSummary {
       compatible_with_engine_version,
       compression_flags,
       package_source,
+      additional_packages_to_cook,
+      num_texture_allocations,
       asset_registry_data_offset,
       bulk_data_start_offset,
       world_tile_info_data_offset,
@@ -167,43 +426,275 @@ impl FileSummary {
   }
 
   pub fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    let ue4_version = self.ue4_version();
     curs.write_all(&self.tag)?;
-    write_u32(curs, self.file_version_ue4)?;
-    write_u32(curs, self.file_version_license_ue4)?;
-    curs.write_all(&self.custom_version)?;
+    curs.write_i32::<LittleEndian>(self.legacy_file_version)?;
+    if self.legacy_file_version != -4 {
+      curs.write_i32::<LittleEndian>(self.legacy_ue3_version)?;
+    }
+    curs.write_i32::<LittleEndian>(self.file_version_ue4)?;
+    curs.write_i32::<LittleEndian>(self.file_version_licensee_ue4)?;
+    if self.legacy_file_version <= -2 {
+      CustomVersion::write_array(&self.custom_versions, curs, self.legacy_file_version)?;
+    }
     write_u32(curs, self.total_header_size)?;
     write_string(curs, &self.folder_name)?;
     write_u32(curs, self.package_flags)?;
     write_u32(curs, self.name_count)?;
     write_u32(curs, self.name_offset)?;
-    write_u32(curs, self.gatherable_text_data_count)?;
-    write_u32(curs, self.gatherable_text_data_offset)?;
+    if Self::has_localization_id(ue4_version, self.package_flags) {
+      write_fstring(curs, &self.localization_id)?;
+    }
+    if ue4_version >= ue4_version::SERIALIZE_TEXT_IN_PACKAGES {
+      write_u32(curs, self.gatherable_text_data_count)?;
+      write_u32(curs, self.gatherable_text_data_offset)?;
+    }
     write_u32(curs, self.export_count)?;
     write_u32(curs, self.export_offset)?;
     write_u32(curs, self.import_count)?;
     write_u32(curs, self.import_offset)?;
     write_u32(curs, self.depends_offset)?;
-    write_u32(curs, self.soft_package_references_count)?;
-    write_u32(curs, self.soft_package_references_offset)?;
-    write_u32(curs, self.searchable_names_offset)?;
+    if ue4_version >= ue4_version::ADD_STRING_ASSET_REFERENCES_MAP {
+      write_u32(curs, self.soft_package_references_count)?;
+      write_u32(curs, self.soft_package_references_offset)?;
+    }
+    if ue4_version >= ue4_version::ADDED_SEARCHABLE_NAMES {
+      write_u32(curs, self.searchable_names_offset)?;
+    }
     write_u32(curs, self.thumbnail_table_offset)?;
     curs.write_all(&self.guid)?;
     Generation::write_array(&self.generations, curs)?;
-    curs.write_all(&self.saved_by_engine_version)?;
-    curs.write_all(&self.compatible_with_engine_version)?;
+    self.saved_by_engine_version.write(curs, ue4_version)?;
+    if ue4_version >= ue4_version::PACKAGE_SUMMARY_HAS_COMPATIBLE_ENGINE_VERSION {
+      self
+        .compatible_with_engine_version
+        .write(curs, ue4_version)?;
+    }
     write_u32(curs, self.compression_flags)?;
-    curs.write_i64::<LittleEndian>(self.package_source)?;
+    write_u32(curs, 0)?; // No compressed chunks
+    write_u32(curs, self.package_source)?;
+    write_u32(curs, self.additional_packages_to_cook.len() as u32)?;
+    for package in self.additional_packages_to_cook.iter() {
+      write_fstring(curs, package)?;
+    }
+    if self.legacy_file_version > -7 {
+      curs.write_i32::<LittleEndian>(self.num_texture_allocations)?;
+    }
     write_u32(curs, self.asset_registry_data_offset)?;
-    write_u32(curs, self.bulk_data_start_offset)?;
-    write_u32(curs, self.world_tile_info_data_offset)?;
-    curs.write_u64::<LittleEndian>(self.chunk_ids)?;
-    write_u32(curs, self.preload_dependency_count)?;
-    write_u32(curs, self.preload_dependency_offset)?;
+    curs.write_i64::<LittleEndian>(self.bulk_data_start_offset)?;
+    if ue4_version >= ue4_version::WORLD_LEVEL_INFO {
+      curs.write_i32::<LittleEndian>(self.world_tile_info_data_offset)?;
+    }
+    if ue4_version >= ue4_version::CHANGED_CHUNKID_TO_BE_AN_ARRAY_OF_CHUNKIDS {
+      write_u32(curs, self.chunk_ids.len() as u32)?;
+      for chunk_id in self.chunk_ids.iter() {
+        curs.write_i32::<LittleEndian>(*chunk_id)?;
+      }
+    } else if ue4_version >= ue4_version::ADDED_CHUNKID_TO_ASSETDATA_AND_UPACKAGE {
+      let chunk_id = self.chunk_ids.first().copied().unwrap_or(-1);
+      curs.write_i32::<LittleEndian>(chunk_id)?;
+    }
+    if ue4_version >= ue4_version::PRELOAD_DEPENDENCIES_IN_COOKED_EXPORTS {
+      write_u32(curs, self.preload_dependency_count)?;
+      write_u32(curs, self.preload_dependency_offset)?;
+    }
     Ok(())
   }
 
   pub fn byte_size(&self) -> usize {
-    // Size is 188 + (len(folder_name) + 1)
-    188 + self.folder_name.len() + 1
+    let ue4_version = self.ue4_version();
+    let mut size = 4 + 4 + 8;
+    if self.legacy_file_version != -4 {
+      size += 4;
+    }
+    if self.legacy_file_version <= -2 {
+      size += CustomVersion::array_size(&self.custom_versions, self.legacy_file_version);
+    }
+    // Total header size, folder name, package flags, and names
+    size += 4 + (4 + self.folder_name.len() + 1) + 4 + 8;
+    if Self::has_localization_id(ue4_version, self.package_flags) {
+      size += fstring_size(&self.localization_id);
+    }
+    if ue4_version >= ue4_version::SERIALIZE_TEXT_IN_PACKAGES {
+      size += 8;
+    }
+    // Exports, imports, and depends
+    size += 8 + 8 + 4;
+    if ue4_version >= ue4_version::ADD_STRING_ASSET_REFERENCES_MAP {
+      size += 8;
+    }
+    if ue4_version >= ue4_version::ADDED_SEARCHABLE_NAMES {
+      size += 4;
+    }
+    // Thumbnail table, guid, and generations
+    size += 4 + 16 + 4 + 8 * self.generations.len();
+    size += self.saved_by_engine_version.byte_size(ue4_version);
+    if ue4_version >= ue4_version::PACKAGE_SUMMARY_HAS_COMPATIBLE_ENGINE_VERSION {
+      size += self.compatible_with_engine_version.byte_size(ue4_version);
+    }
+    // Compression flags, compressed chunks, package source, and additional
+    // packages to cook
+    size += 4
+      + 4
+      + 4
+      + 4
+      + self
+        .additional_packages_to_cook
+        .iter()
+        .map(fstring_size)
+        .sum::<usize>();
+    if self.legacy_file_version > -7 {
+      size += 4;
+    }
+    // Asset registry and bulk data
+    size += 4 + 8;
+    if ue4_version >= ue4_version::WORLD_LEVEL_INFO {
+      size += 4;
+    }
+    if ue4_version >= ue4_version::CHANGED_CHUNKID_TO_BE_AN_ARRAY_OF_CHUNKIDS {
+      size += 4 + 4 * self.chunk_ids.len();
+    } else if ue4_version >= ue4_version::ADDED_CHUNKID_TO_ASSETDATA_AND_UPACKAGE {
+      size += 4;
+    }
+    if ue4_version >= ue4_version::PRELOAD_DEPENDENCIES_IN_COOKED_EXPORTS {
+      size += 8;
+    }
+    size
+  }
+
+  /// The package version that decides the layout of the package. Unversioned
+  /// packages are assumed to be from the latest supported version.
+  pub fn ue4_version(&self) -> i32 {
+    Self::effective_ue4_version(self.file_version_ue4)
+  }
+
+  pub fn is_unversioned(&self) -> bool {
+    self.file_version_ue4 == 0
+      && self.file_version_licensee_ue4 == 0
+      && self.custom_versions.is_empty()
+  }
+
+  /// Gets the version of a custom serialization format by its key
+  pub fn custom_version(&self, key: &[u8; 16]) -> Option<i32> {
+    self
+      .custom_versions
+      .iter()
+      .find(|custom_version| custom_version.key == *key)
+      .map(|custom_version| custom_version.version)
+  }
+
+  /// Whether export properties are serialized without tags, which needs
+  /// mappings to read them
+  pub fn has_unversioned_properties(&self) -> bool {
+    PackageFlags::from_bits_retain(self.package_flags)
+      .contains(PackageFlags::UNVERSIONED_PROPERTIES)
+  }
+
+  fn effective_ue4_version(file_version_ue4: i32) -> i32 {
+    if file_version_ue4 == 0 {
+      ue4_version::UNVERSIONED
+    } else {
+      file_version_ue4
+    }
+  }
+
+  fn has_localization_id(ue4_version: i32, package_flags: u32) -> bool {
+    ue4_version >= ue4_version::ADDED_PACKAGE_SUMMARY_LOCALIZATION_ID
+      && !PackageFlags::from_bits_retain(package_flags).contains(PackageFlags::FILTER_EDITOR_ONLY)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::asset::property::TestPackage;
+
+  /// Writes and reads back `summary`, returning what was read and its size
+  fn round_trip(summary: &FileSummary) -> (FileSummary, usize) {
+    let mut curs = Cursor::new(vec![]);
+    summary.write(&mut curs).unwrap();
+    let bytes = curs.into_inner();
+    assert_eq!(summary.byte_size(), bytes.len());
+    let read = FileSummary::read(&mut ByteReader::new(bytes.clone())).unwrap();
+    let mut curs = Cursor::new(vec![]);
+    read.write(&mut curs).unwrap();
+    assert_eq!(curs.into_inner(), bytes);
+    (read, bytes.len())
+  }
+
+  #[test]
+  fn fields_are_gated_on_the_package_version() {
+    let mut summary = TestPackage::new(&[]).summary;
+    summary.chunk_ids = vec![7];
+    summary.searchable_names_offset = 0x40;
+    summary.saved_by_engine_version.changelist = 1234;
+    summary.compatible_with_engine_version.changelist = 1234;
+
+    let mut sizes = vec![];
+    for ue4_version in [0, 509, 506, 400, 300].iter() {
+      summary.file_version_ue4 = *ue4_version;
+      let (read, size) = round_trip(&summary);
+      sizes.push(size);
+      assert_eq!(read.chunk_ids, [7]);
+      assert_eq!(read.compatible_with_engine_version.changelist, 1234);
+      if *ue4_version == 300 {
+        assert_eq!(read.saved_by_engine_version.minor, 0);
+        assert_eq!(read.searchable_names_offset, 0);
+      }
+    }
+    // Unversioned packages have the newest layout. Then searchable names and
+    // the localization id, preload dependencies, compatible engine version
+    // and gatherable text, and the old engine version, soft package
+    // references, and single chunk id are left out.
+    assert_eq!(sizes, [193, 185, 177, 155, 133]);
+
+    // The localization id isn't kept in packages filtered for editor only data
+    summary.file_version_ue4 = 0;
+    summary.package_flags = PackageFlags::FILTER_EDITOR_ONLY.bits();
+    assert_eq!(round_trip(&summary).1, 189);
+  }
+
+  #[test]
+  fn custom_versions_follow_the_legacy_file_version() {
+    let mut summary = TestPackage::new(&[]).summary;
+    summary.file_version_ue4 = 400;
+    let mut key = [0; 16];
+    key[12..].copy_from_slice(b"CORE");
+    summary.custom_versions = vec![CustomVersion {
+      key,
+      version: 3,
+      friendly_name: Some("Core".to_string()),
+    }];
+
+    let mut sizes = vec![];
+    for legacy_file_version in [-7, -5, -4, -2, -1].iter() {
+      summary.legacy_file_version = *legacy_file_version;
+      let (read, size) = round_trip(&summary);
+      sizes.push(size);
+      let name = read
+        .custom_versions
+        .first()
+        .map(|v| v.friendly_name.clone());
+      match legacy_file_version {
+        -1 => assert!(read.custom_versions.is_empty()),
+        -5 | -4 => assert_eq!(name, Some(Some("Core".to_string()))),
+        _ => assert_eq!(name, Some(None)),
+      }
+      if *legacy_file_version != -1 {
+        assert_eq!(read.custom_version(&key), Some(3));
+      }
+    }
+    // Guid keys, with friendly names, without the UE3 version, enum keys,
+    // and no custom versions. Texture allocations are only left out at -7.
+    assert_eq!(sizes, [171, 184, 180, 163, 151]);
+
+    summary.legacy_file_version = -8;
+    let mut curs = Cursor::new(vec![]);
+    summary.write(&mut curs).unwrap();
+    let err = FileSummary::read(&mut ByteReader::new(curs.into_inner())).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Unsupported legacy file version -8, expected -1 to -7"
+    );
   }
 }
@@ -0,0 +1,97 @@
+use bitflags::bitflags;
+
+bitflags! {
+  /// EObjectFlags
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct ObjectFlags: u32 {
+    const PUBLIC = 0x0000_0001;
+    const STANDALONE = 0x0000_0002;
+    const MARK_AS_NATIVE = 0x0000_0004;
+    const TRANSACTIONAL = 0x0000_0008;
+    const CLASS_DEFAULT_OBJECT = 0x0000_0010;
+    const ARCHETYPE_OBJECT = 0x0000_0020;
+    const TRANSIENT = 0x0000_0040;
+    const MARK_AS_ROOT_SET = 0x0000_0080;
+    const TAG_GARBAGE_TEMP = 0x0000_0100;
+    const NEED_INITIALIZATION = 0x0000_0200;
+    const NEED_LOAD = 0x0000_0400;
+    const KEEP_FOR_COOKER = 0x0000_0800;
+    const NEED_POST_LOAD = 0x0000_1000;
+    const NEED_POST_LOAD_SUBOBJECTS = 0x0000_2000;
+    const NEWER_VERSION_EXISTS = 0x0000_4000;
+    const BEGIN_DESTROYED = 0x0000_8000;
+    const FINISH_DESTROYED = 0x0001_0000;
+    const BEING_REGENERATED = 0x0002_0000;
+    const DEFAULT_SUB_OBJECT = 0x0004_0000;
+    const WAS_LOADED = 0x0008_0000;
+    const TEXT_EXPORT_TRANSIENT = 0x0010_0000;
+    const LOAD_COMPLETED = 0x0020_0000;
+    const INHERITABLE_COMPONENT_TEMPLATE = 0x0040_0000;
+    const DUPLICATE_TRANSIENT = 0x0080_0000;
+    const STRONG_REF_ON_FRAME = 0x0100_0000;
+    const NON_PIE_DUPLICATE_TRANSIENT = 0x0200_0000;
+    const DYNAMIC = 0x0400_0000;
+    const WILL_BE_LOADED = 0x0800_0000;
+
+    // Keep unknown bits so flags are written back unchanged
+    const _ = !0;
+  }
+}
+
+bitflags! {
+  /// EPackageFlags
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct PackageFlags: u32 {
+    const NEWLY_CREATED = 0x0000_0001;
+    const CLIENT_OPTIONAL = 0x0000_0002;
+    const SERVER_SIDE_ONLY = 0x0000_0004;
+    const COMPILED_IN = 0x0000_0010;
+    const FOR_DIFFING = 0x0000_0020;
+    const EDITOR_ONLY = 0x0000_0040;
+    const DEVELOPER = 0x0000_0080;
+    const UNCOOKED_ONLY_HEADERS = 0x0000_0100;
+    const COOKED = 0x0000_0200;
+    const CONTAINS_NO_ASSET = 0x0000_0400;
+    const UNVERSIONED_PROPERTIES = 0x0000_2000;
+    const CONTAINS_MAP_DATA = 0x0000_4000;
+    const COMPILING = 0x0001_0000;
+    const CONTAINS_MAP = 0x0002_0000;
+    const REQUIRES_LOCALIZATION_GATHER = 0x0004_0000;
+    const PLAY_IN_EDITOR = 0x0010_0000;
+    const CONTAINS_SCRIPT = 0x0020_0000;
+    const DISALLOW_EXPORT = 0x0040_0000;
+    const DYNAMIC_IMPORTS = 0x1000_0000;
+    const RUNTIME_GENERATED = 0x2000_0000;
+    const RELOADING_FOR_COOKER = 0x4000_0000;
+    const FILTER_EDITOR_ONLY = 0x8000_0000;
+
+    // Keep unknown bits so flags are written back unchanged
+    const _ = !0;
+  }
+}
+
+bitflags! {
+  /// EBulkDataFlags
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct BulkDataFlags: u32 {
+    const PAYLOAD_AT_END_OF_FILE = 0x0000_0001;
+    const SERIALIZE_COMPRESSED_ZLIB = 0x0000_0002;
+    const FORCE_SINGLE_ELEMENT_SERIALIZATION = 0x0000_0004;
+    const SINGLE_USE = 0x0000_0008;
+    const UNUSED = 0x0000_0020;
+    const FORCE_INLINE_PAYLOAD = 0x0000_0040;
+    const FORCE_STREAM_PAYLOAD = 0x0000_0080;
+    const PAYLOAD_IN_SEPERATE_FILE = 0x0000_0100;
+    const SERIALIZE_COMPRESSED_BIT_WINDOW = 0x0000_0200;
+    const FORCE_NOT_INLINE_PAYLOAD = 0x0000_0400;
+    const OPTIONAL_PAYLOAD = 0x0000_0800;
+    const MEMORY_MAPPED_PAYLOAD = 0x0000_1000;
+    const SIZE_64_BIT = 0x0000_2000;
+    const DUPLICATE_NON_OPTIONAL_PAYLOAD = 0x0000_4000;
+    const BAD_DATA_VERSION = 0x0000_8000;
+    const NO_OFFSET_FIX_UP = 0x0001_0000;
+
+    // Keep unknown bits so flags are written back unchanged
+    const _ = !0;
+  }
+}
@@ -0,0 +1,246 @@
+use crate::asset::*;
+use crate::reader::*;
+use crate::util::*;
+use anyhow::*;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::Cursor;
+
+#[derive(Debug, Clone)]
+pub enum LocMetadataValue {
+  Boolean(bool),
+  String(Option<String>),
+  Array(Vec<LocMetadataValue>),
+  Object(LocMetadataObject),
+}
+
+#[derive(Debug, Clone)]
+pub struct LocMetadataObject {
+  pub values: Vec<(Option<String>, LocMetadataValue)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextSourceSiteContext {
+  pub key_name: Option<String>,
+  pub site_description: Option<String>,
+  pub is_editor_only: bool,
+  pub is_optional: bool,
+  pub info_meta_data: LocMetadataObject,
+  pub key_meta_data: LocMetadataObject,
+}
+
+/// Text in the package that can be gathered for localization
+#[derive(Debug, Clone)]
+pub struct GatherableText {
+  pub namespace_name: Option<String>,
+  pub source_string: Option<String>,
+  pub source_string_meta_info: LocMetadataObject,
+  pub source_site_contexts: Vec<TextSourceSiteContext>,
+}
+
+/// Only written for packages that aren't cooked.
+#[derive(Debug)]
+pub struct GatherableTextData {
+  pub texts: Vec<GatherableText>,
+}
+
+impl LocMetadataValue {
+  fn read(rdr: &mut ByteReader) -> Result<Self> {
+    let typ = rdr.read_i32::<LittleEndian>()?;
+    match typ {
+      1 => Ok(Self::Boolean(read_bool(rdr)?)),
+      2 => Ok(Self::String(read_fstring(rdr)?)),
+      3 => {
+        let count = read_u32(rdr)?;
+        let mut values = vec![];
+        for _ in 0..count {
+          values.push(Self::read(rdr)?);
+        }
+        Ok(Self::Array(values))
+      }
+      4 => Ok(Self::Object(LocMetadataObject::read(rdr)?)),
+      _ => bail!("Invalid loc metadata type {}", typ),
+    }
+  }
+
+  fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    match self {
+      Self::Boolean(value) => {
+        curs.write_i32::<LittleEndian>(1)?;
+        write_bool(curs, *value)?;
+      }
+      Self::String(value) => {
+        curs.write_i32::<LittleEndian>(2)?;
+        write_fstring(curs, value)?;
+      }
+      Self::Array(values) => {
+        curs.write_i32::<LittleEndian>(3)?;
+        write_u32(curs, values.len() as u32)?;
+        for value in values.iter() {
+          value.write(curs)?;
+        }
+      }
+      Self::Object(object) => {
+        curs.write_i32::<LittleEndian>(4)?;
+        object.write(curs)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    4 + match self {
+      Self::Boolean(_) => 4,
+      Self::String(value) => fstring_size(value),
+      Self::Array(values) => 4 + values.iter().map(|v| v.byte_size()).sum::<usize>(),
+      Self::Object(object) => object.byte_size(),
+    }
+  }
+}
+
+impl LocMetadataObject {
+  fn read(rdr: &mut ByteReader) -> Result<Self> {
+    let count = read_u32(rdr)?;
+    let mut values = vec![];
+    for _ in 0..count {
+      let key = read_fstring(rdr)?;
+      let value = LocMetadataValue::read(rdr)?;
+      values.push((key, value));
+    }
+    Ok(Self { values })
+  }
+
+  fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    write_u32(curs, self.values.len() as u32)?;
+    for (key, value) in self.values.iter() {
+      write_fstring(curs, key)?;
+      value.write(curs)?;
+    }
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    4 + self
+      .values
+      .iter()
+      .map(|(key, value)| fstring_size(key) + value.byte_size())
+      .sum::<usize>()
+  }
+}
+
+impl TextSourceSiteContext {
+  fn read(rdr: &mut ByteReader) -> Result<Self> {
+    let key_name = read_fstring(rdr)?;
+    let site_description = read_fstring(rdr)?;
+    let is_editor_only = read_bool(rdr)?;
+    let is_optional = read_bool(rdr)?;
+    let info_meta_data = LocMetadataObject::read(rdr).with_context(|| "info_meta_data")?;
+    let key_meta_data = LocMetadataObject::read(rdr).with_context(|| "key_meta_data")?;
+    Ok(Self {
+      key_name,
+      site_description,
+      is_editor_only,
+      is_optional,
+      info_meta_data,
+      key_meta_data,
+    })
+  }
+
+  fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    write_fstring(curs, &self.key_name)?;
+    write_fstring(curs, &self.site_description)?;
+    write_bool(curs, self.is_editor_only)?;
+    write_bool(curs, self.is_optional)?;
+    self.info_meta_data.write(curs)?;
+    self.key_meta_data.write(curs)?;
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    fstring_size(&self.key_name)
+      + fstring_size(&self.site_description)
+      + 8
+      + self.info_meta_data.byte_size()
+      + self.key_meta_data.byte_size()
+  }
+}
+
+impl GatherableText {
+  fn read(rdr: &mut ByteReader) -> Result<Self> {
+    let namespace_name = read_fstring(rdr)?;
+    let source_string = read_fstring(rdr)?;
+    let source_string_meta_info =
+      LocMetadataObject::read(rdr).with_context(|| "source_string_meta_info")?;
+    let count = read_u32(rdr)?;
+    let mut source_site_contexts = vec![];
+    for i in 0..count {
+      let context =
+        TextSourceSiteContext::read(rdr).with_context(|| format!("Source site context {}", i))?;
+      source_site_contexts.push(context);
+    }
+    Ok(Self {
+      namespace_name,
+      source_string,
+      source_string_meta_info,
+      source_site_contexts,
+    })
+  }
+
+  fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    write_fstring(curs, &self.namespace_name)?;
+    write_fstring(curs, &self.source_string)?;
+    self.source_string_meta_info.write(curs)?;
+    write_u32(curs, self.source_site_contexts.len() as u32)?;
+    for context in self.source_site_contexts.iter() {
+      context.write(curs)?;
+    }
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    fstring_size(&self.namespace_name)
+      + fstring_size(&self.source_string)
+      + self.source_string_meta_info.byte_size()
+      + 4
+      + self
+        .source_site_contexts
+        .iter()
+        .map(|c| c.byte_size())
+        .sum::<usize>()
+  }
+}
+
+impl GatherableTextData {
+  pub fn read(rdr: &mut ByteReader, summary: &FileSummary) -> Result<Self> {
+    if rdr.position() != summary.gatherable_text_data_offset as u64 {
+      bail!(
+        "Wrong gatherable text data starting position: Expected to be at position {:#X}, but I'm at position {:#X}",
+        summary.gatherable_text_data_offset,
+        rdr.position(),
+      );
+    }
+
+    let mut texts = vec![];
+    for i in 0..summary.gatherable_text_data_count {
+      let start_pos = rdr.position();
+      let text = GatherableText::read(rdr).with_context(|| {
+        format!(
+          "Failed to read gatherable text {} starting at {:#X}",
+          i, start_pos
+        )
+      })?;
+      texts.push(text);
+    }
+    Ok(Self { texts })
+  }
+
+  pub fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    for text in self.texts.iter() {
+      text.write(curs)?;
+    }
+    Ok(())
+  }
+
+  pub fn byte_size(&self) -> usize {
+    self.texts.iter().map(|t| t.byte_size()).sum()
+  }
+}
@@ -8,8 +8,11 @@ use std::io::Cursor;
 pub struct Import {
   pub class_package: NameVariant,
   pub class: NameVariant,
-  pub outer_index: i32,
+  pub outer: Reference,
   pub name: NameVariant,
+  // Index the outer was read from, which is written back while it still
+  // points at an import with the same name
+  original_outer: i32,
 }
 
 #[derive(Debug)]
@@ -18,26 +21,33 @@ pub struct Imports {
 }
 
 impl Import {
-  fn read(rdr: &mut ByteReader, name_map: &Names) -> Result<Self> {
+  /// Reads an import with an unresolved outer, which is returned separately
+  /// because it can point at an import later in the table.
+  fn read(rdr: &mut ByteReader, name_map: &Names) -> Result<(Self, i32)> {
     let class_package = NameVariant::read(rdr, name_map).with_context(|| "class_package")?;
     let class = NameVariant::read(rdr, name_map).with_context(|| "class")?;
     let outer_index = rdr.read_i32::<LittleEndian>()?;
     let name = NameVariant::read(rdr, name_map).with_context(|| "name")?;
-    Ok(Import {
+    let import = Import {
       class_package,
       class,
-      outer_index,
+      outer: Reference::UObject,
       name,
-    })
+      original_outer: outer_index,
+    };
+    Ok((import, outer_index))
   }
 
-  fn write(&self, curs: &mut Cursor<Vec<u8>>, names: &Names) -> Result<()> {
+  fn write(&self, curs: &mut Cursor<Vec<u8>>, names: &Names, imports: &Imports) -> Result<()> {
     self
       .class_package
       .write(curs, names)
       .with_context(|| "class_package")?;
     self.class.write(curs, names).with_context(|| "class")?;
-    curs.write_i32::<LittleEndian>(self.outer_index)?;
+    let outer_index = imports
+      .outer_index_of(&self.outer, self.original_outer, names)
+      .with_context(|| format!("outer {}", self.outer.to_string(names)))?;
+    curs.write_i32::<LittleEndian>(outer_index)?;
     self.name.write(curs, names).with_context(|| "name")?;
     Ok(())
   }
@@ -62,22 +72,56 @@ impl Imports {
     }
 
     let mut objects = vec![];
+    let mut outer_indices = vec![];
     for _ in 0..summary.import_count {
       let start_pos = rdr.position();
-      let object = Import::read(rdr, name_map)
+      let (object, outer_index) = Import::read(rdr, name_map)
         .with_context(|| format!("Failed to parse import starting at {:#X}", start_pos))?;
       objects.push(object);
+      outer_indices.push(outer_index);
+    }
+
+    let mut imports = Imports { objects };
+    for (i, outer_index) in outer_indices.into_iter().enumerate() {
+      let outer = imports
+        .resolve_outer(outer_index)
+        .with_context(|| format!("Failed to resolve outer of import {}", i))?;
+      imports.objects[i].outer = outer;
     }
-    Ok(Imports { objects })
+    Ok(imports)
   }
 
   pub fn write(&self, curs: &mut Cursor<Vec<u8>>, names: &Names) -> Result<()> {
     for object in self.objects.iter() {
-      object.write(curs, names)?;
+      object.write(curs, names, self)?;
     }
     Ok(())
   }
 
+  /// Imports are read before exports, so an import's outer can only be
+  /// another import or nothing.
+  fn resolve_outer(&self, outer_index: i32) -> Result<Reference> {
+    if outer_index == 0 {
+      Ok(Reference::UObject)
+    } else if outer_index < 0 {
+      let outer = self.lookup((-outer_index - 1) as u64)?;
+      Ok(Reference::import(outer.class.clone(), outer.name.clone()))
+    } else {
+      bail!("Import outer {} is an export", outer_index)
+    }
+  }
+
+  fn outer_index_of(&self, outer: &Reference, original: i32, names: &Names) -> Result<i32> {
+    if original < 0 && self.resolve_outer(original).ok().as_ref() == Some(outer) {
+      return Ok(original);
+    }
+    match outer {
+      Reference::UObject => Ok(0),
+      Reference::Import { class, name } => self.unique_index_of(class, name, names),
+      Reference::Export(_) => bail!("Import outer can't be an export"),
+    }
+  }
+
   pub fn byte_size(&self) -> usize {
     // Each ObjectImport is 28 bytes long
     28 * self.objects.len()
@@ -92,6 +136,29 @@ impl Imports {
     None
   }
 
+  /// Gets the index of the only import with the class `class` and name
+  /// `object`
+  pub fn unique_index_of(
+    &self,
+    class: &NameVariant,
+    object: &NameVariant,
+    names: &Names,
+  ) -> Result<i32> {
+    let found: Vec<i32> = self
+      .objects
+      .iter()
+      .enumerate()
+      .filter(|(_, import)| import.name == *object && import.class == *class)
+      .map(|(i, _)| -(i as i32) - 1)
+      .collect();
+    let to_string = || format!("{} # {}", class.to_string(names), object.to_string(names));
+    match found[..] {
+      [index] => Ok(index),
+      [] => bail!("{} is not imported", to_string()),
+      _ => bail!("{} matches more than one import", to_string()),
+    }
+  }
+
   pub fn serialized_index_of(&self, class: &NameVariant, object: &NameVariant) -> Option<u32> {
     self.index_of(class, object).map(|i| i as u32)
   }
@@ -112,22 +179,78 @@ impl Imports {
     class_package: NameVariant,
     class: NameVariant,
     name: NameVariant,
-    outer_index: i32,
+    outer: Reference,
   ) -> i32 {
-    if let Some(index) = self.serialized_index_of(&class, &name) {
+    if let Some(index) = self.index_of(&class, &name) {
       // No-op if the object is already imported
-      // TODO what to do if different class_package/class/outer_index?
-      return -(index as i32) - 1;
+      // TODO what to do if different class_package/class/outer?
+      return index;
     }
 
     let object = Import {
       class_package,
       class,
-      outer_index,
+      outer,
       name,
+      original_outer: 0,
     };
     let len = self.objects.len();
     self.objects.push(object);
     -(len as i32) - 1
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn outers_follow_their_import_when_it_moves() {
+    let mut package = TestPackage::new(&[
+      "/Script/CoreUObject",
+      "/Script/Engine",
+      "Package",
+      "Class",
+      "Actor",
+    ]);
+    let names = &package.names;
+    let name = |name| NameVariant::new(name, 0, names);
+    let import_bytes = |class_package, class, outer: i32, object| {
+      let mut curs = Cursor::new(vec![]);
+      name(class_package).write(&mut curs, names).unwrap();
+      name(class).write(&mut curs, names).unwrap();
+      curs.write_i32::<LittleEndian>(outer).unwrap();
+      name(object).write(&mut curs, names).unwrap();
+      curs.into_inner()
+    };
+    let data = [
+      import_bytes("/Script/CoreUObject", "Package", 0, "/Script/Engine"),
+      import_bytes("/Script/Engine", "Class", -1, "Actor"),
+    ]
+    .concat();
+    package.summary.import_count = 2;
+    let mut imports = Imports::read(&mut ByteReader::new(data), &package.summary, names).unwrap();
+    let engine = Reference::import(name("Package"), name("/Script/Engine"));
+    assert_eq!(imports.objects[1].outer, engine);
+
+    // Moving the package after the class changes the index of the outer
+    imports.add(
+      name("/Script/CoreUObject"),
+      name("Package"),
+      name("/Script/CoreUObject"),
+      Reference::UObject,
+    );
+    imports.objects.swap(0, 2);
+    let mut curs = Cursor::new(vec![]);
+    imports.write(&mut curs, names).unwrap();
+    assert_eq!(
+      curs.into_inner(),
+      [
+        import_bytes("/Script/CoreUObject", "Package", 0, "/Script/CoreUObject"),
+        import_bytes("/Script/Engine", "Class", -3, "Actor"),
+        import_bytes("/Script/CoreUObject", "Package", 0, "/Script/Engine"),
+      ]
+      .concat()
+    );
+  }
+}
@@ -0,0 +1,699 @@
+use crate::asset::*;
+use crate::reader::*;
+use crate::util::*;
+use anyhow::*;
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::fmt::Write;
+use std::io::{Seek, SeekFrom};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+/// Opcodes of serialized Kismet bytecode
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter)]
+#[allow(non_camel_case_types)]
+#[repr(u8)]
+pub enum ExprToken {
+  EX_LocalVariable = 0x00,
+  EX_InstanceVariable = 0x01,
+  EX_DefaultVariable = 0x02,
+  EX_Return = 0x04,
+  EX_Jump = 0x06,
+  EX_JumpIfNot = 0x07,
+  EX_Assert = 0x09,
+  EX_Nothing = 0x0B,
+  EX_Let = 0x0F,
+  EX_ClassContext = 0x12,
+  EX_MetaCast = 0x13,
+  EX_LetBool = 0x14,
+  EX_EndParmValue = 0x15,
+  EX_EndFunctionParms = 0x16,
+  EX_Self = 0x17,
+  EX_Skip = 0x18,
+  EX_Context = 0x19,
+  EX_Context_FailSilent = 0x1A,
+  EX_VirtualFunction = 0x1B,
+  EX_FinalFunction = 0x1C,
+  EX_IntConst = 0x1D,
+  EX_FloatConst = 0x1E,
+  EX_StringConst = 0x1F,
+  EX_ObjectConst = 0x20,
+  EX_NameConst = 0x21,
+  EX_RotationConst = 0x22,
+  EX_VectorConst = 0x23,
+  EX_ByteConst = 0x24,
+  EX_IntZero = 0x25,
+  EX_IntOne = 0x26,
+  EX_True = 0x27,
+  EX_False = 0x28,
+  EX_TextConst = 0x29,
+  EX_NoObject = 0x2A,
+  EX_TransformConst = 0x2B,
+  EX_IntConstByte = 0x2C,
+  EX_NoInterface = 0x2D,
+  EX_DynamicCast = 0x2E,
+  EX_StructConst = 0x2F,
+  EX_EndStructConst = 0x30,
+  EX_SetArray = 0x31,
+  EX_EndArray = 0x32,
+  EX_PropertyConst = 0x33,
+  EX_UnicodeStringConst = 0x34,
+  EX_Int64Const = 0x35,
+  EX_UInt64Const = 0x36,
+  EX_PrimitiveCast = 0x38,
+  EX_SetSet = 0x39,
+  EX_EndSet = 0x3A,
+  EX_SetMap = 0x3B,
+  EX_EndMap = 0x3C,
+  EX_SetConst = 0x3D,
+  EX_EndSetConst = 0x3E,
+  EX_MapConst = 0x3F,
+  EX_EndMapConst = 0x40,
+  EX_StructMemberContext = 0x42,
+  EX_LetMulticastDelegate = 0x43,
+  EX_LetDelegate = 0x44,
+  EX_LocalVirtualFunction = 0x45,
+  EX_LocalFinalFunction = 0x46,
+  EX_LocalOutVariable = 0x48,
+  EX_DeprecatedOp4A = 0x4A,
+  EX_InstanceDelegate = 0x4B,
+  EX_PushExecutionFlow = 0x4C,
+  EX_PopExecutionFlow = 0x4D,
+  EX_ComputedJump = 0x4E,
+  EX_PopExecutionFlowIfNot = 0x4F,
+  EX_Breakpoint = 0x50,
+  EX_InterfaceContext = 0x51,
+  EX_ObjToInterfaceCast = 0x52,
+  EX_EndOfScript = 0x53,
+  EX_CrossInterfaceCast = 0x54,
+  EX_InterfaceToObjCast = 0x55,
+  EX_WireTracepoint = 0x5A,
+  EX_SkipOffsetConst = 0x5B,
+  EX_AddMulticastDelegate = 0x5C,
+  EX_ClearMulticastDelegate = 0x5D,
+  EX_Tracepoint = 0x5E,
+  EX_LetObj = 0x5F,
+  EX_LetWeakObjPtr = 0x60,
+  EX_BindDelegate = 0x61,
+  EX_RemoveMulticastDelegate = 0x62,
+  EX_CallMulticastDelegate = 0x63,
+  EX_LetValueOnPersistentFrame = 0x64,
+  EX_ArrayConst = 0x65,
+  EX_EndArrayConst = 0x66,
+  EX_SoftObjectConst = 0x67,
+  EX_CallMath = 0x68,
+  EX_SwitchValue = 0x69,
+  EX_InstrumentationEvent = 0x6A,
+  EX_ArrayGetByRef = 0x6B,
+  EX_ClassSparseDataVariable = 0x6C,
+  EX_FieldPathConst = 0x6D,
+}
+
+/// Immediate data of an expression
+#[derive(Debug, Clone)]
+pub enum Operand {
+  Int(i64),
+  UInt(u64),
+  Float(f32),
+  Str(String),
+  Name(NameVariant),
+  Object(Reference),
+  // A property is stored as the path to it and the object that owns it
+  Property {
+    path: Vec<NameVariant>,
+    owner: Reference,
+  },
+  // A byte offset into the in-memory bytecode, e.g. a jump target
+  CodeOffset(u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct Expr {
+  // Offset of the opcode from the start of the serialized bytecode
+  pub offset: usize,
+  // Offset of the opcode in the bytecode once it's loaded, which is what
+  // jump targets refer to. Object and name references are larger in memory.
+  pub memory_offset: usize,
+  pub token: ExprToken,
+  pub operands: Vec<Operand>,
+  pub children: Vec<Expr>,
+}
+
+/// The bytecode of a function or blueprint class
+#[derive(Debug)]
+pub struct Script {
+  // Offset of the bytecode in the uexp
+  pub uexp_offset: u64,
+  pub memory_size: u32,
+  pub exprs: Vec<Expr>,
+}
+
+struct Disassembler<'a> {
+  rdr: ByteReader,
+  names: &'a Names,
+  imports: &'a Imports,
+  exports: &'a Exports,
+  memory_offset: usize,
+}
+
+// Serialized sizes of a pointer and a script name in loaded bytecode
+const MEMORY_POINTER_SIZE: usize = 8;
+const MEMORY_NAME_SIZE: usize = 12;
+
+impl ExprToken {
+  pub fn from_u8(opcode: u8) -> Option<Self> {
+    Self::iter().find(|token| *token as u8 == opcode)
+  }
+}
+
+impl<'a> Disassembler<'a> {
+  fn u8(&mut self) -> Result<u8> {
+    self.memory_offset += 1;
+    Ok(self.rdr.read_u8()?)
+  }
+
+  fn u16(&mut self) -> Result<u16> {
+    self.memory_offset += 2;
+    Ok(self.rdr.read_u16::<LittleEndian>()?)
+  }
+
+  fn u32(&mut self) -> Result<u32> {
+    self.memory_offset += 4;
+    Ok(self.rdr.read_u32::<LittleEndian>()?)
+  }
+
+  fn i32(&mut self) -> Result<i32> {
+    self.memory_offset += 4;
+    Ok(self.rdr.read_i32::<LittleEndian>()?)
+  }
+
+  fn f32(&mut self) -> Result<f32> {
+    self.memory_offset += 4;
+    Ok(self.rdr.read_f32::<LittleEndian>()?)
+  }
+
+  fn floats(&mut self, count: usize) -> Result<Vec<Operand>> {
+    (0..count)
+      .map(|_| Ok(Operand::Float(self.f32()?)))
+      .collect()
+  }
+
+  fn object(&mut self) -> Result<Operand> {
+    self.memory_offset += MEMORY_POINTER_SIZE;
+    let reference = Reference::read(&mut self.rdr, self.imports, self.exports)?;
+    Ok(Operand::Object(reference))
+  }
+
+  fn name(&mut self) -> Result<Operand> {
+    self.memory_offset += MEMORY_NAME_SIZE;
+    Ok(Operand::Name(NameVariant::read(&mut self.rdr, self.names)?))
+  }
+
+  fn property(&mut self) -> Result<Operand> {
+    self.memory_offset += MEMORY_POINTER_SIZE;
+    let len = read_u32(&mut self.rdr)?;
+    let mut path = vec![];
+    for _ in 0..len {
+      path.push(NameVariant::read(&mut self.rdr, self.names)?);
+    }
+    let owner = Reference::read(&mut self.rdr, self.imports, self.exports)?;
+    Ok(Operand::Property { path, owner })
+  }
+
+  fn code_offset(&mut self) -> Result<Operand> {
+    Ok(Operand::CodeOffset(self.u32()?))
+  }
+
+  fn string(&mut self) -> Result<Operand> {
+    let mut bytes = vec![];
+    loop {
+      match self.u8()? {
+        0 => break,
+        byte => bytes.push(byte),
+      }
+    }
+    Ok(Operand::Str(bytes.into_iter().map(|b| b as char).collect()))
+  }
+
+  fn unicode_string(&mut self) -> Result<Operand> {
+    let mut chars = vec![];
+    loop {
+      match self.u16()? {
+        0 => break,
+        c => chars.push(c),
+      }
+    }
+    Ok(Operand::Str(String::from_utf16_lossy(&chars)))
+  }
+
+  /// Reads expressions up to and including the one with the `end` token
+  fn exprs_until(&mut self, end: ExprToken, children: &mut Vec<Expr>) -> Result<()> {
+    loop {
+      let expr = self.expr()?;
+      let token = expr.token;
+      children.push(expr);
+      if token == end {
+        return Ok(());
+      }
+    }
+  }
+
+  fn expr(&mut self) -> Result<Expr> {
+    let offset = self.rdr.position() as usize;
+    let memory_offset = self.memory_offset;
+    let opcode = self.u8()?;
+    let token = ExprToken::from_u8(opcode)
+      .ok_or_else(|| anyhow!("Unknown opcode {:#04X} at {:#X}", opcode, offset))?;
+
+    use ExprToken::*;
+    let mut operands = vec![];
+    let mut children = vec![];
+    match token {
+      EX_LocalVariable
+      | EX_InstanceVariable
+      | EX_DefaultVariable
+      | EX_LocalOutVariable
+      | EX_PropertyConst
+      | EX_ClassSparseDataVariable => operands.push(self.property()?),
+      EX_Return
+      | EX_ComputedJump
+      | EX_PopExecutionFlowIfNot
+      | EX_InterfaceContext
+      | EX_ClearMulticastDelegate
+      | EX_SoftObjectConst
+      | EX_FieldPathConst => children.push(self.expr()?),
+      EX_Jump | EX_PushExecutionFlow | EX_SkipOffsetConst => operands.push(self.code_offset()?),
+      EX_JumpIfNot | EX_Skip => {
+        operands.push(self.code_offset()?);
+        children.push(self.expr()?);
+      }
+      EX_Assert => {
+        operands.push(Operand::UInt(self.u16()? as u64));
+        operands.push(Operand::UInt(self.u8()? as u64));
+        children.push(self.expr()?);
+      }
+      EX_Let | EX_LetValueOnPersistentFrame | EX_StructMemberContext => {
+        operands.push(self.property()?);
+        children.push(self.expr()?);
+        if token == EX_Let {
+          children.push(self.expr()?);
+        }
+      }
+      EX_LetBool
+      | EX_LetObj
+      | EX_LetWeakObjPtr
+      | EX_LetDelegate
+      | EX_LetMulticastDelegate
+      | EX_AddMulticastDelegate
+      | EX_RemoveMulticastDelegate
+      | EX_ArrayGetByRef => {
+        children.push(self.expr()?);
+        children.push(self.expr()?);
+      }
+      EX_ClassContext | EX_Context | EX_Context_FailSilent => {
+        children.push(self.expr()?);
+        operands.push(self.code_offset()?);
+        operands.push(self.property()?);
+        children.push(self.expr()?);
+      }
+      EX_MetaCast
+      | EX_DynamicCast
+      | EX_ObjToInterfaceCast
+      | EX_CrossInterfaceCast
+      | EX_InterfaceToObjCast => {
+        operands.push(self.object()?);
+        children.push(self.expr()?);
+      }
+      EX_VirtualFunction | EX_LocalVirtualFunction => {
+        operands.push(self.name()?);
+        self.exprs_until(EX_EndFunctionParms, &mut children)?;
+      }
+      EX_FinalFunction | EX_LocalFinalFunction | EX_CallMath | EX_CallMulticastDelegate => {
+        operands.push(self.object()?);
+        self.exprs_until(EX_EndFunctionParms, &mut children)?;
+      }
+      EX_IntConst => operands.push(Operand::Int(self.i32()? as i64)),
+      EX_FloatConst => operands.push(Operand::Float(self.f32()?)),
+      EX_StringConst => operands.push(self.string()?),
+      EX_UnicodeStringConst => operands.push(self.unicode_string()?),
+      EX_ObjectConst => operands.push(self.object()?),
+      EX_NameConst | EX_InstanceDelegate => operands.push(self.name()?),
+      EX_RotationConst | EX_VectorConst => operands.extend(self.floats(3)?),
+      EX_TransformConst => operands.extend(self.floats(10)?),
+      EX_ByteConst | EX_IntConstByte => operands.push(Operand::UInt(self.u8()? as u64)),
+      EX_Int64Const => {
+        self.memory_offset += 8;
+        operands.push(Operand::Int(self.rdr.read_i64::<LittleEndian>()?));
+      }
+      EX_UInt64Const => {
+        self.memory_offset += 8;
+        operands.push(Operand::UInt(self.rdr.read_u64::<LittleEndian>()?));
+      }
+      EX_TextConst => {
+        let literal_type = self.u8()?;
+        operands.push(Operand::UInt(literal_type as u64));
+        match literal_type {
+          // Empty
+          0 => {}
+          // Localized text: source, key, and namespace
+          1 => {
+            for _ in 0..3 {
+              children.push(self.expr()?);
+            }
+          }
+          // Invariant text or a literal string
+          2 | 3 => children.push(self.expr()?),
+          // String table entry: table asset, table id, and key
+          4 => {
+            operands.push(self.object()?);
+            children.push(self.expr()?);
+            children.push(self.expr()?);
+          }
+          _ => bail!(
+            "Unknown text literal type {} at {:#X}",
+            literal_type,
+            offset
+          ),
+        }
+      }
+      EX_StructConst => {
+        operands.push(self.object()?);
+        operands.push(Operand::Int(self.i32()? as i64));
+        self.exprs_until(EX_EndStructConst, &mut children)?;
+      }
+      EX_SetArray => {
+        children.push(self.expr()?);
+        self.exprs_until(EX_EndArray, &mut children)?;
+      }
+      EX_PrimitiveCast => {
+        operands.push(Operand::UInt(self.u8()? as u64));
+        children.push(self.expr()?);
+      }
+      EX_SetSet | EX_SetMap => {
+        children.push(self.expr()?);
+        operands.push(Operand::Int(self.i32()? as i64));
+        let end = if token == EX_SetSet {
+          EX_EndSet
+        } else {
+          EX_EndMap
+        };
+        self.exprs_until(end, &mut children)?;
+      }
+      EX_SetConst | EX_ArrayConst => {
+        operands.push(self.property()?);
+        operands.push(Operand::Int(self.i32()? as i64));
+        let end = if token == EX_SetConst {
+          EX_EndSetConst
+        } else {
+          EX_EndArrayConst
+        };
+        self.exprs_until(end, &mut children)?;
+      }
+      EX_MapConst => {
+        operands.push(self.property()?);
+        operands.push(self.property()?);
+        operands.push(Operand::Int(self.i32()? as i64));
+        self.exprs_until(EX_EndMapConst, &mut children)?;
+      }
+      EX_BindDelegate => {
+        operands.push(self.name()?);
+        children.push(self.expr()?);
+        children.push(self.expr()?);
+      }
+      EX_SwitchValue => {
+        let case_count = self.u16()?;
+        operands.push(Operand::UInt(case_count as u64));
+        operands.push(self.code_offset()?);
+        children.push(self.expr()?);
+        // Each case is its value, the offset of the next case, and its result
+        for _ in 0..case_count {
+          children.push(self.expr()?);
+          operands.push(self.code_offset()?);
+          children.push(self.expr()?);
+        }
+        children.push(self.expr()?);
+      }
+      EX_InstrumentationEvent => {
+        let event_type = self.u8()?;
+        operands.push(Operand::UInt(event_type as u64));
+        // Inline events are named
+        if event_type == 4 {
+          operands.push(self.name()?);
+        }
+      }
+      EX_Nothing | EX_EndParmValue | EX_EndFunctionParms | EX_Self | EX_IntZero | EX_IntOne
+      | EX_True | EX_False | EX_NoObject | EX_NoInterface | EX_EndStructConst | EX_EndArray
+      | EX_EndSet | EX_EndMap | EX_EndSetConst | EX_EndMapConst | EX_EndArrayConst
+      | EX_DeprecatedOp4A | EX_PopExecutionFlow | EX_Breakpoint | EX_EndOfScript
+      | EX_WireTracepoint | EX_Tracepoint => {}
+    }
+    Ok(Expr {
+      offset,
+      memory_offset,
+      token,
+      operands,
+      children,
+    })
+  }
+}
+
+/// Skips a serialized `FProperty`, including the properties inside it. The
+/// property's type name has already been read.
+fn skip_field(rdr: &mut ByteReader, type_name: &str, names: &Names) -> Result<()> {
+  // FField name and flags, then array dim, element size, property flags, rep
+  // index, rep notify function, and replication condition
+  rdr.seek(SeekFrom::Current(8 + 4 + 4 + 4 + 8 + 2 + 8 + 1))?;
+  match type_name {
+    "ObjectProperty"
+    | "WeakObjectProperty"
+    | "LazyObjectProperty"
+    | "SoftObjectProperty"
+    | "InterfaceProperty"
+    | "StructProperty"
+    | "ByteProperty"
+    | "DelegateProperty"
+    | "MulticastDelegateProperty"
+    | "MulticastInlineDelegateProperty"
+    | "MulticastSparseDelegateProperty"
+    | "FieldPathProperty" => {
+      let size = if type_name == "FieldPathProperty" {
+        8
+      } else {
+        4
+      };
+      rdr.seek(SeekFrom::Current(size))?;
+    }
+    "ClassProperty" | "SoftClassProperty" => {
+      rdr.seek(SeekFrom::Current(8))?;
+    }
+    "BoolProperty" => {
+      rdr.seek(SeekFrom::Current(6))?;
+    }
+    "EnumProperty" => {
+      rdr.seek(SeekFrom::Current(4))?;
+      skip_single_field(rdr, names)?;
+    }
+    "ArrayProperty" | "SetProperty" => skip_single_field(rdr, names)?,
+    "MapProperty" => {
+      skip_single_field(rdr, names)?;
+      skip_single_field(rdr, names)?;
+    }
+    _ => {}
+  }
+  Ok(())
+}
+
+fn skip_single_field(rdr: &mut ByteReader, names: &Names) -> Result<()> {
+  let type_name = NameVariant::read(rdr, names)?.to_string(names);
+  if type_name != "None" {
+    skip_field(rdr, &type_name, names).with_context(|| type_name.clone())?;
+  }
+  Ok(())
+}
+
+impl Script {
+  /// Disassembles the bytecode of an export. `data` is the export's native
+  /// data, which starts with the serialized `UStruct`.
+  pub fn disassemble(
+    data: &[u8],
+    uexp_offset: u64,
+    names: &Names,
+    imports: &Imports,
+    exports: &Exports,
+  ) -> Result<Self> {
+    let mut rdr = ByteReader::new(data.to_vec());
+    let _super_struct =
+      Reference::read(&mut rdr, imports, exports).with_context(|| "SuperStruct")?;
+    let child_count = read_u32(&mut rdr)?;
+    rdr.seek(SeekFrom::Current(4 * child_count as i64))?;
+
+    let property_count = read_u32(&mut rdr)?;
+    for i in 0..property_count {
+      let type_name = NameVariant::read(&mut rdr, names)?.to_string(names);
+      skip_field(&mut rdr, &type_name, names)
+        .with_context(|| format!("Child property {} ({})", i, type_name))?;
+    }
+
+    let memory_size = read_u32(&mut rdr)?;
+    let storage_size = read_u32(&mut rdr)?;
+    let script_start = rdr.position() as usize;
+    let script = data
+      .get(script_start..script_start + storage_size as usize)
+      .ok_or_else(|| {
+        anyhow!(
+          "Bytecode is {:#X} bytes, but there is less data",
+          storage_size
+        )
+      })?;
+
+    let mut disassembler = Disassembler {
+      rdr: ByteReader::new(script.to_vec()),
+      names,
+      imports,
+      exports,
+      memory_offset: 0,
+    };
+    let mut exprs = vec![];
+    while disassembler.rdr.remaining_bytes() > 0 {
+      let expr = disassembler
+        .expr()
+        .with_context(|| format!("Expression {}", exprs.len()))?;
+      exprs.push(expr);
+    }
+    Ok(Self {
+      uexp_offset: uexp_offset + script_start as u64,
+      memory_size,
+      exprs,
+    })
+  }
+
+  /// Formats the expressions one per line with their uexp offset, in-memory
+  /// offset, and operands. Arguments of an expression are indented below it.
+  pub fn listing(&self, names: &Names) -> String {
+    let mut out = String::new();
+    for expr in self.exprs.iter() {
+      self.write_expr(&mut out, expr, 0, names);
+    }
+    out
+  }
+
+  fn write_expr(&self, out: &mut String, expr: &Expr, depth: usize, names: &Names) {
+    let operands = expr
+      .operands
+      .iter()
+      .map(|operand| operand.to_string(names))
+      .collect::<Vec<_>>()
+      .join(", ");
+    writeln!(
+      out,
+      "{:08X} {:04X} {}{} {}",
+      self.uexp_offset + expr.offset as u64,
+      expr.memory_offset,
+      "  ".repeat(depth),
+      expr.token,
+      operands
+    )
+    .expect("Writing to a string can't fail");
+    for child in expr.children.iter() {
+      self.write_expr(out, child, depth + 1, names);
+    }
+  }
+}
+
+impl Operand {
+  pub fn to_string(&self, names: &Names) -> String {
+    match self {
+      Self::Int(value) => value.to_string(),
+      Self::UInt(value) => value.to_string(),
+      Self::Float(value) => format!("{:?}", value),
+      Self::Str(value) => format!("{:?}", value),
+      Self::Name(name) => name.to_string(names),
+      Self::Object(reference) => reference.to_string(names),
+      Self::Property { path, .. } => path
+        .iter()
+        .map(|name| name.to_string(names))
+        .collect::<Vec<_>>()
+        .join("."),
+      Self::CodeOffset(offset) => format!("-> {:04X}", offset),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::asset::property::TestPackage;
+  use std::io::{Cursor, Write};
+
+  /// A function with no super struct or child properties around `script`
+  fn function(script: &[u8], memory_size: u32) -> Vec<u8> {
+    let mut curs = Cursor::new(vec![]);
+    for value in [0, 0, 0, memory_size, script.len() as u32].iter() {
+      write_u32(&mut curs, *value).unwrap();
+    }
+    curs.write_all(script).unwrap();
+    curs.into_inner()
+  }
+
+  #[test]
+  fn listing_has_uexp_and_memory_offsets() {
+    let package = TestPackage::new(&["Heal"]);
+    let mut script = Cursor::new(vec![]);
+    // if (!true) jump to the end
+    script.write_all(&[0x07, 0x1F, 0, 0, 0, 0x27]).unwrap();
+    // Heal(42, "hi")
+    script.write_all(&[0x45]).unwrap();
+    let heal = NameVariant::new("Heal", 0, &package.names);
+    heal.write(&mut script, &package.names).unwrap();
+    script.write_all(&[0x1D, 42, 0, 0, 0]).unwrap();
+    script.write_all(&[0x1F, b'h', b'i', 0, 0x16]).unwrap();
+    // return nothing
+    script.write_all(&[0x04, 0x0B, 0x53]).unwrap();
+
+    let data = function(&script.into_inner(), 0x20);
+    let disassembled = Script::disassemble(
+      &data,
+      0x100,
+      &package.names,
+      &package.imports,
+      &package.exports,
+    )
+    .unwrap();
+    assert_eq!(disassembled.uexp_offset, 0x114);
+    assert_eq!(disassembled.exprs.len(), 4);
+    assert_eq!(
+      disassembled.listing(&package.names),
+      [
+        "00000114 0000 EX_JumpIfNot -> 001F",
+        "00000119 0005   EX_True ",
+        "0000011A 0006 EX_LocalVirtualFunction Heal",
+        "00000123 0013   EX_IntConst 42",
+        "00000128 0018   EX_StringConst \"hi\"",
+        "0000012C 001C   EX_EndFunctionParms ",
+        "0000012D 001D EX_Return ",
+        "0000012E 001E   EX_Nothing ",
+        "0000012F 001F EX_EndOfScript ",
+        "",
+      ]
+      .join("\n")
+    );
+  }
+
+  #[test]
+  fn unknown_opcodes_are_errors() {
+    let package = TestPackage::new(&[]);
+    let data = function(&[0x0B, 0xFF], 2);
+    let err = Script::disassemble(&data, 0, &package.names, &package.imports, &package.exports)
+      .unwrap_err();
+    assert_eq!(
+      format!("{:#}", err),
+      "Expression 1: Unknown opcode 0xFF at 0x1"
+    );
+
+    // The bytecode is cut off
+    let mut data = function(&[0x0B, 0x0B], 2);
+    data.pop();
+    assert!(
+      Script::disassemble(&data, 0, &package.names, &package.imports, &package.exports).is_err()
+    );
+  }
+}
@@ -8,12 +8,108 @@ use std::io::Cursor;
 #[derive(Debug)]
 pub struct Name {
   pub index: u32,
-  pub name: String,                  // Size as a uint32, then null terminated string
-  pub non_case_preserving_hash: u16, // idk how this is calculated, idk if it matters
-  pub case_preserving_hash: u16,     // ditto
+  pub name: String, // Size as a uint32, then null terminated string
+  pub non_case_preserving_hash: u16,
+  pub case_preserving_hash: u16,
+  // Transient names are only used in memory (e.g. the property names of
+  // unversioned properties) and aren't written to the name map
+  transient: bool,
 }
 
+// Big endian CRC32 table (FCrc::CRCTable_DEPRECATED), used by the old
+// case-insensitive string hash
+const CRC_TABLE_DEPRECATED: [u32; 256] = {
+  let mut table = [0; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut crc = (i as u32) << 24;
+    let mut j = 0;
+    while j < 8 {
+      crc = if crc & 0x8000_0000 != 0 {
+        (crc << 1) ^ 0x04C1_1DB7
+      } else {
+        crc << 1
+      };
+      j += 1;
+    }
+    table[i] = crc;
+    i += 1;
+  }
+  table
+};
+
+// Little endian CRC32 table (FCrc::CRCTablesSB8[0])
+const CRC_TABLE: [u32; 256] = {
+  let mut table = [0; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut crc = i as u32;
+    let mut j = 0;
+    while j < 8 {
+      crc = if crc & 1 != 0 {
+        (crc >> 1) ^ 0xEDB8_8320
+      } else {
+        crc >> 1
+      };
+      j += 1;
+    }
+    table[i] = crc;
+    i += 1;
+  }
+  table
+};
+
 impl Name {
+  pub fn new(index: u32, name: &str) -> Self {
+    Self {
+      index,
+      name: name.to_string(),
+      non_case_preserving_hash: Self::non_case_preserving_hash(name),
+      case_preserving_hash: Self::case_preserving_hash(name),
+      transient: false,
+    }
+  }
+
+  pub fn is_transient(&self) -> bool {
+    self.transient
+  }
+
+  /// FCrc::Strihash_DEPRECATED of the uppercased name, truncated to 16 bits
+  pub fn non_case_preserving_hash(name: &str) -> u16 {
+    let mut hash: u32 = 0;
+    for b in name.bytes() {
+      let b = b.to_ascii_uppercase() as u32;
+      hash = ((hash >> 8) & 0x00FF_FFFF) ^ CRC_TABLE_DEPRECATED[((hash ^ b) & 0xFF) as usize];
+    }
+    (hash & 0xFFFF) as u16
+  }
+
+  /// FCrc::StrCrc32 of the name, truncated to 16 bits. StrCrc32 hashes every
+  /// character as 4 bytes.
+  pub fn case_preserving_hash(name: &str) -> u16 {
+    let mut crc: u32 = !0;
+    for b in name.bytes() {
+      let mut ch = b as u32;
+      for _ in 0..4 {
+        crc = (crc >> 8) ^ CRC_TABLE[((crc ^ ch) & 0xFF) as usize];
+        ch >>= 8;
+      }
+    }
+    (!crc & 0xFFFF) as u16
+  }
+
+  /// Recalculates both hashes from the name
+  pub fn rehash(&mut self) {
+    self.non_case_preserving_hash = Self::non_case_preserving_hash(&self.name);
+    self.case_preserving_hash = Self::case_preserving_hash(&self.name);
+  }
+
+  /// Whether the stored hashes are the ones calculated from the name
+  pub fn has_expected_hashes(&self) -> bool {
+    self.non_case_preserving_hash == Self::non_case_preserving_hash(&self.name)
+      && self.case_preserving_hash == Self::case_preserving_hash(&self.name)
+  }
+
   fn read(rdr: &mut ByteReader) -> Result<Self> {
     let name = read_string(rdr)?;
     let non_case_preserving_hash = rdr.read_u16::<LittleEndian>()?;
@@ -23,6 +119,7 @@ impl Name {
       name,
       non_case_preserving_hash,
       case_preserving_hash,
+      transient: false,
     })
   }
 
@@ -96,8 +193,14 @@ impl NameVariant {
     Ok(Self::new(&name, variant, names))
   }
 
-  pub fn write(&self, curs: &mut Cursor<Vec<u8>>, _names: &Names) -> Result<()> {
-    write_u32(curs, self.name_idx as u32)?;
+  pub fn write(&self, curs: &mut Cursor<Vec<u8>>, names: &Names) -> Result<()> {
+    let index = names.serialized_index(self.name_idx).with_context(|| {
+      format!(
+        "Name {} is not in the name map",
+        names.names[self.name_idx].name
+      )
+    })?;
+    write_u32(curs, index as u32)?;
     write_u32(curs, self.variant)?;
     Ok(())
   }
@@ -138,7 +241,7 @@ impl Names {
   }
 
   pub fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
-    for name in self.names.iter() {
+    for name in self.names.iter().filter(|name| !name.transient) {
       name.write(curs)?;
     }
     Ok(())
@@ -147,12 +250,30 @@ impl Names {
   pub fn byte_size(&self) -> usize {
     // Size of each is 8 + (len(name) + 1)
     let mut size = 0;
-    for name in self.names.iter() {
+    for name in self.names.iter().filter(|name| !name.transient) {
       size += 8 + name.name.len() + 1;
     }
     size
   }
 
+  /// Number of names written to the name map
+  pub fn serialized_count(&self) -> usize {
+    self.names.iter().filter(|name| !name.transient).count()
+  }
+
+  /// Index of the name at `pos` in the written name map. None if the name is
+  /// transient.
+  pub fn serialized_index(&self, pos: usize) -> Option<usize> {
+    if self.names[pos].transient {
+      return None;
+    }
+    let transient_before = self.names[..pos]
+      .iter()
+      .filter(|name| name.transient)
+      .count();
+    Some(pos - transient_before)
+  }
+
   pub fn get_name_obj(&self, name: &str) -> Option<&Name> {
     for own_name in self.names.iter() {
       if own_name.name == name {
@@ -174,22 +295,54 @@ impl Names {
   }
 
   pub fn add(&mut self, name: &str) -> bool {
-    // No-op if name already exists
+    // No-op if name already exists, but a transient name has to be written now
+    if let Some(own_name) = self.names.iter_mut().find(|own_name| own_name.name == name) {
+      let was_transient = own_name.transient;
+      own_name.transient = false;
+      return was_transient;
+    }
+
+    let index = self.names.len() as u32;
+    self.names.push(Name::new(index, name));
+    true
+  }
+
+  /// Adds a name that can be referenced in memory, but isn't written to the
+  /// name map unless it's added with `add` later.
+  pub fn add_transient(&mut self, name: &str) -> bool {
     if self.get_name_obj(name).is_some() {
       return false;
     }
 
     let index = self.names.len() as u32;
-    let name_obj = Name {
-      index,
-      name: name.to_string(),
-      non_case_preserving_hash: 0,
-      case_preserving_hash: 0,
-    };
-    self.names.push(name_obj);
+    self.names.push(Name {
+      transient: true,
+      ..Name::new(index, name)
+    });
     true
   }
 
+  /// Returns the names whose stored hashes don't match the calculated ones,
+  /// which should be empty for a cooked asset.
+  pub fn mismatched_hashes(&self) -> Vec<String> {
+    self
+      .names
+      .iter()
+      .filter(|name| !name.has_expected_hashes())
+      .map(|name| name.name.clone())
+      .collect()
+  }
+
+  /// Recalculates the hashes of all names. Returns the names whose stored
+  /// hashes didn't match, which should be empty for a cooked asset.
+  pub fn rehash_all(&mut self) -> Vec<String> {
+    let mismatched = self.mismatched_hashes();
+    for name in self.names.iter_mut() {
+      name.rehash();
+    }
+    mismatched
+  }
+
   /// Checks that name is in the names map and returns its position. Otherwise
   /// panics.
   ///
@@ -205,3 +358,34 @@ impl Names {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // None is followed by F4 03 C5 0D in the name map of cooked assets. The
+  // case preserving hashes are the low bits of the standard CRC-32 of the
+  // name as UTF-32.
+  #[test]
+  fn hashes_match_the_engine() {
+    let hashes = |name| {
+      (
+        Name::non_case_preserving_hash(name),
+        Name::case_preserving_hash(name),
+      )
+    };
+    assert_eq!(hashes("None"), (0x03F4, 0x0DC5));
+    assert_eq!(hashes("Class"), (0x7774, 0x9178));
+    assert_eq!(hashes("/Script/CoreUObject"), (0x49F8, 0x3E2D));
+    assert_eq!(hashes("Default__Object"), (0x10C4, 0xE6BA));
+    // Only the case preserving hash depends on case
+    assert_eq!(hashes("NONE").0, 0x03F4);
+    assert_ne!(hashes("NONE").1, 0x0DC5);
+
+    let mut names = Names { names: vec![] };
+    names.add("None");
+    let mut curs = Cursor::new(vec![]);
+    names.write(&mut curs).unwrap();
+    assert_eq!(curs.into_inner(), b"\x05\0\0\0None\0\xF4\x03\xC5\x0D");
+  }
+}
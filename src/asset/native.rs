@@ -0,0 +1,160 @@
+use crate::asset::*;
+use anyhow::*;
+use std::any::Any;
+use std::fmt::Debug;
+use std::io::Cursor;
+use std::sync::{Arc, RwLock};
+
+pub mod curve;
+pub mod data_table;
+pub mod string_table;
+pub mod texture;
+
+pub use curve::*;
+pub use data_table::*;
+pub use string_table::*;
+pub use texture::*;
+
+/// Class specific data serialized after an export's properties
+pub trait NativeData: Debug + Any + Send + Sync {
+  fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()>;
+  fn byte_size(&self) -> usize;
+  fn as_any(&self) -> &dyn Any;
+  fn as_any_mut(&mut self) -> &mut dyn Any;
+
+  /// The bulk data records in the native data
+  fn bulk_data(&self) -> Vec<&ByteBulkData> {
+    vec![]
+  }
+
+  fn bulk_data_mut(&mut self) -> Vec<&mut ByteBulkData> {
+    vec![]
+  }
+
+  /// Called when offsets are recalculated with where the native data now
+  /// starts in the package, for native data that stores absolute offsets
+  fn set_package_offset(&mut self, _offset: u64) {}
+}
+
+/// Reads the native data of exports of one class. `data` is everything after
+/// the export's properties and object guid.
+pub trait NativeSerializer: Send + Sync {
+  fn deserialize(
+    &self,
+    data: &[u8],
+    properties: &Properties,
+    ctx: PropertyContext,
+  ) -> Result<Box<dyn NativeData>>;
+}
+
+static NATIVE_SERIALIZERS: RwLock<Vec<(String, Arc<dyn NativeSerializer>)>> =
+  RwLock::new(Vec::new());
+
+/// Registers the serializer for exports with the class `class_name`,
+/// replacing any serializer already registered for it.
+pub fn register_native_serializer(class_name: &str, serializer: Arc<dyn NativeSerializer>) {
+  let mut serializers = NATIVE_SERIALIZERS
+    .write()
+    .expect("Native serializer registry was poisoned");
+  serializers.retain(|(name, _)| name != class_name);
+  serializers.push((class_name.to_string(), serializer));
+}
+
+/// Gets the serializer for exports with the class `class_name`. Registered
+/// serializers take precedence over the built in ones.
+pub fn native_serializer_for(class_name: &str) -> Option<Arc<dyn NativeSerializer>> {
+  let registered = NATIVE_SERIALIZERS
+    .read()
+    .expect("Native serializer registry was poisoned")
+    .iter()
+    .find(|(name, _)| name == class_name)
+    .map(|(_, serializer)| serializer.clone());
+  registered.or_else(|| builtin_native_serializer(class_name))
+}
+
+fn builtin_native_serializer(class_name: &str) -> Option<Arc<dyn NativeSerializer>> {
+  match class_name {
+    "CurveTable" => Some(Arc::new(CurveTableSerializer)),
+    "DataTable" => Some(Arc::new(DataTableSerializer)),
+    "StringTable" => Some(Arc::new(StringTableSerializer)),
+    "Texture2D" | "TextureCube" => Some(Arc::new(TextureSerializer)),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::asset::property::TestPackage;
+  use std::io::Write;
+
+  #[derive(Debug)]
+  struct Raw(Vec<u8>);
+
+  impl NativeData for Raw {
+    fn serialize(&self, curs: &mut Cursor<Vec<u8>>, _ctx: PropertyContext) -> Result<()> {
+      curs.write_all(&self.0)?;
+      Ok(())
+    }
+
+    fn byte_size(&self) -> usize {
+      self.0.len()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+      self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+      self
+    }
+  }
+
+  struct RawSerializer;
+
+  impl NativeSerializer for RawSerializer {
+    fn deserialize(
+      &self,
+      data: &[u8],
+      _properties: &Properties,
+      _ctx: PropertyContext,
+    ) -> Result<Box<dyn NativeData>> {
+      Ok(Box::new(Raw(data.to_vec())))
+    }
+  }
+
+  #[test]
+  fn registered_serializers_are_found_by_class() {
+    assert!(native_serializer_for("NativeTestRaw").is_none());
+    register_native_serializer("NativeTestRaw", Arc::new(RawSerializer));
+
+    let package = TestPackage::new(&[]);
+    let serializer = native_serializer_for("NativeTestRaw").unwrap();
+    let native = package.native_round_trip(serializer.as_ref(), &Raw(vec![1, 2, 3]));
+    assert_eq!(native.as_any().downcast_ref::<Raw>().unwrap().0, [1, 2, 3]);
+  }
+
+  #[test]
+  fn registered_serializers_replace_the_builtin_ones() {
+    // Too short for the builtin texture serializer
+    let data = [0xFF; 3];
+    let package = TestPackage::new(&[]);
+    let properties = package.properties();
+    let builtin = native_serializer_for("TextureCube").unwrap();
+    assert!(builtin
+      .deserialize(&data, &properties, package.ctx())
+      .is_err());
+
+    register_native_serializer("TextureCube", Arc::new(RawSerializer));
+    let registered = native_serializer_for("TextureCube").unwrap();
+    let native = registered
+      .deserialize(&data, &properties, package.ctx())
+      .unwrap();
+    assert!(native.as_any().is::<Raw>());
+    // Only the registered class is replaced
+    let texture = native_serializer_for("Texture2D").unwrap();
+    assert!(texture
+      .deserialize(&data, &properties, package.ctx())
+      .is_err());
+  }
+}
@@ -0,0 +1,769 @@
+use crate::asset::property::meta::*;
+use crate::asset::property::prop_type::*;
+use crate::asset::struct_pattern::*;
+use crate::asset::*;
+use crate::reader::*;
+use crate::util::*;
+use anyhow::*;
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::any::Any;
+use std::io::Cursor;
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+pub enum RichCurveInterpMode {
+  #[strum(serialize = "RCIM_Linear")]
+  Linear,
+  #[strum(serialize = "RCIM_Constant")]
+  Constant,
+  #[strum(serialize = "RCIM_Cubic")]
+  Cubic,
+  #[strum(serialize = "RCIM_None")]
+  None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+pub enum RichCurveTangentMode {
+  #[strum(serialize = "RCTM_Auto")]
+  Auto,
+  #[strum(serialize = "RCTM_User")]
+  User,
+  #[strum(serialize = "RCTM_Break")]
+  Break,
+  #[strum(serialize = "RCTM_None")]
+  None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+pub enum RichCurveTangentWeightMode {
+  #[strum(serialize = "RCTWM_WeightedNone")]
+  WeightedNone,
+  #[strum(serialize = "RCTWM_WeightedArrive")]
+  WeightedArrive,
+  #[strum(serialize = "RCTWM_WeightedLeave")]
+  WeightedLeave,
+  #[strum(serialize = "RCTWM_WeightedBoth")]
+  WeightedBoth,
+}
+
+/// What a curve evaluates to before its first key and after its last key
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
+pub enum RichCurveExtrapolation {
+  #[strum(serialize = "RCCE_Cycle")]
+  Cycle,
+  #[strum(serialize = "RCCE_CycleWithOffset")]
+  CycleWithOffset,
+  #[strum(serialize = "RCCE_Oscillate")]
+  Oscillate,
+  #[strum(serialize = "RCCE_Linear")]
+  Linear,
+  #[strum(serialize = "RCCE_Constant")]
+  Constant,
+  #[strum(serialize = "RCCE_None")]
+  None,
+}
+
+const INTERP_MODES: [RichCurveInterpMode; 4] = [
+  RichCurveInterpMode::Linear,
+  RichCurveInterpMode::Constant,
+  RichCurveInterpMode::Cubic,
+  RichCurveInterpMode::None,
+];
+const TANGENT_MODES: [RichCurveTangentMode; 4] = [
+  RichCurveTangentMode::Auto,
+  RichCurveTangentMode::User,
+  RichCurveTangentMode::Break,
+  RichCurveTangentMode::None,
+];
+const TANGENT_WEIGHT_MODES: [RichCurveTangentWeightMode; 4] = [
+  RichCurveTangentWeightMode::WeightedNone,
+  RichCurveTangentWeightMode::WeightedArrive,
+  RichCurveTangentWeightMode::WeightedLeave,
+  RichCurveTangentWeightMode::WeightedBoth,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RichCurveKey {
+  pub interp_mode: RichCurveInterpMode,
+  pub tangent_mode: RichCurveTangentMode,
+  pub tangent_weight_mode: RichCurveTangentWeightMode,
+  pub time: f32,
+  pub value: f32,
+  pub arrive_tangent: f32,
+  pub arrive_tangent_weight: f32,
+  pub leave_tangent: f32,
+  pub leave_tangent_weight: f32,
+}
+
+/// A float curve made of cubic, linear, or constant segments between keys,
+/// stored as the tagged properties of an `FRichCurve`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RichCurve {
+  pub keys: Vec<RichCurveKey>,
+  // None when the curve has no default value (FLT_MAX in the engine)
+  pub default_value: Option<f32>,
+  pub pre_infinity_extrap: RichCurveExtrapolation,
+  pub post_infinity_extrap: RichCurveExtrapolation,
+}
+
+impl RichCurveKey {
+  pub fn new(time: f32, value: f32) -> Self {
+    Self {
+      interp_mode: RichCurveInterpMode::Cubic,
+      tangent_mode: RichCurveTangentMode::Auto,
+      tangent_weight_mode: RichCurveTangentWeightMode::WeightedNone,
+      time,
+      value,
+      arrive_tangent: 0.0,
+      arrive_tangent_weight: 0.0,
+      leave_tangent: 0.0,
+      leave_tangent_weight: 0.0,
+    }
+  }
+
+  /// Reads a key from the `RichCurveKey` struct pattern
+  pub fn from_struct_value(value: &StructValue) -> Result<Self> {
+    let entries = match value {
+      StructValue::BinaryProperties { entries } => entries,
+      _ => bail!("RichCurveKey should use the BinaryProperties struct pattern"),
+    };
+    let get = |name: &str| {
+      entries
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+        .ok_or_else(|| anyhow!("RichCurveKey has no {}", name))
+    };
+    let get_float = |name: &str| match get(name)? {
+      StructValue::Floating { value, .. } => Ok(*value as f32),
+      _ => bail!("RichCurveKey {} should be Floating", name),
+    };
+    let get_enum = |name: &str| match get(name)? {
+      StructValue::Enum { value, .. } => Ok(*value as usize),
+      _ => bail!("RichCurveKey {} should be an Enum", name),
+    };
+    let interp_mode = get_enum("Interp Mode")?;
+    let tangent_mode = get_enum("Tangent Mode")?;
+    let tangent_weight_mode = get_enum("Tangent Weight Mode")?;
+    Ok(Self {
+      interp_mode: *INTERP_MODES
+        .get(interp_mode)
+        .ok_or_else(|| anyhow!("Invalid interp mode {}", interp_mode))?,
+      tangent_mode: *TANGENT_MODES
+        .get(tangent_mode)
+        .ok_or_else(|| anyhow!("Invalid tangent mode {}", tangent_mode))?,
+      tangent_weight_mode: *TANGENT_WEIGHT_MODES
+        .get(tangent_weight_mode)
+        .ok_or_else(|| anyhow!("Invalid tangent weight mode {}", tangent_weight_mode))?,
+      time: get_float("Time")?,
+      value: get_float("Value")?,
+      arrive_tangent: get_float("Arrive Tangent")?,
+      arrive_tangent_weight: get_float("Arrive Tangent Weight")?,
+      leave_tangent: get_float("Leave Tangent")?,
+      leave_tangent_weight: get_float("Leave Tangent Weight")?,
+    })
+  }
+
+  /// Converts the key to the `RichCurveKey` struct pattern
+  pub fn to_struct_value(&self) -> StructValue {
+    let enum_value = |variants: &[&str], value: usize| StructValue::Enum {
+      variants: variants.iter().map(|v| v.to_string()).collect(),
+      value: value as u8,
+    };
+    let float_value = |value: f32| StructValue::Floating {
+      size: 4,
+      value: value as f64,
+    };
+    let interp_mode = INTERP_MODES.iter().position(|m| *m == self.interp_mode);
+    let tangent_mode = TANGENT_MODES.iter().position(|m| *m == self.tangent_mode);
+    let tangent_weight_mode = TANGENT_WEIGHT_MODES
+      .iter()
+      .position(|m| *m == self.tangent_weight_mode);
+    StructValue::BinaryProperties {
+      entries: vec![
+        (
+          "Interp Mode".to_string(),
+          enum_value(
+            &["Linear", "Constant", "Cubic", "None"],
+            interp_mode.unwrap_or(0),
+          ),
+        ),
+        (
+          "Tangent Mode".to_string(),
+          enum_value(
+            &["Auto", "User", "Break", "None"],
+            tangent_mode.unwrap_or(0),
+          ),
+        ),
+        (
+          "Tangent Weight Mode".to_string(),
+          enum_value(
+            &["None", "Arrive", "Leave", "Both"],
+            tangent_weight_mode.unwrap_or(0),
+          ),
+        ),
+        ("Time".to_string(), float_value(self.time)),
+        ("Value".to_string(), float_value(self.value)),
+        (
+          "Arrive Tangent".to_string(),
+          float_value(self.arrive_tangent),
+        ),
+        (
+          "Arrive Tangent Weight".to_string(),
+          float_value(self.arrive_tangent_weight),
+        ),
+        ("Leave Tangent".to_string(), float_value(self.leave_tangent)),
+        (
+          "Leave Tangent Weight".to_string(),
+          float_value(self.leave_tangent_weight),
+        ),
+      ],
+    }
+  }
+}
+
+impl Default for RichCurve {
+  fn default() -> Self {
+    Self {
+      keys: vec![],
+      default_value: None,
+      pre_infinity_extrap: RichCurveExtrapolation::Constant,
+      post_infinity_extrap: RichCurveExtrapolation::Constant,
+    }
+  }
+}
+
+impl RichCurve {
+  /// Reads the curve from the tagged properties of an `FRichCurve`. Missing
+  /// properties have their default values.
+  pub fn from_properties(properties: &[Property], names: &Names) -> Result<Self> {
+    let mut curve = Self::default();
+    for property in properties.iter() {
+      let name = property.meta.name.to_string(names);
+      match (name.as_str(), &property.value) {
+        ("DefaultValue", Value::Float(value)) => curve.default_value = Some(*value),
+        ("PreInfinityExtrap", Value::Enum(value)) => {
+          curve.pre_infinity_extrap = read_extrapolation(value, names)?
+        }
+        ("PostInfinityExtrap", Value::Enum(value)) => {
+          curve.post_infinity_extrap = read_extrapolation(value, names)?
+        }
+        ("Keys", Value::Array { values, .. }) => {
+          for (i, (_, value)) in values.iter().enumerate() {
+            let key = match value {
+              Value::Struct { value } => RichCurveKey::from_struct_value(value),
+              _ => Err(anyhow!("Expected a struct")),
+            }
+            .with_context(|| format!("Keys[{}]", i))?;
+            curve.keys.push(key);
+          }
+        }
+        _ => {}
+      }
+    }
+    if curve.default_value == Some(f32::MAX) {
+      curve.default_value = None;
+    }
+    Ok(curve)
+  }
+
+  /// Writes the curve into the tagged properties of an `FRichCurve`,
+  /// replacing the curve's properties and keeping any others. Names that are
+  /// needed are added to `names`.
+  pub fn write_to_properties(&self, properties: &mut Vec<Property>, names: &mut Names) {
+    match self.default_value {
+      Some(value) => set_property(
+        properties,
+        names,
+        "DefaultValue",
+        PropType::FloatProperty,
+        Tag::Simple(PropType::FloatProperty),
+        Value::Float(value),
+      ),
+      None => remove_property(properties, names, "DefaultValue"),
+    }
+
+    let extrapolations = [
+      ("PreInfinityExtrap", self.pre_infinity_extrap),
+      ("PostInfinityExtrap", self.post_infinity_extrap),
+    ];
+    for (name, extrap) in extrapolations.iter() {
+      let exists = find_property(properties, names, name).is_some();
+      if !exists && *extrap == RichCurveExtrapolation::Constant {
+        continue;
+      }
+      let tag = Tag::Enum(NameVariant::parse_and_add("ERichCurveExtrapolation", names));
+      let value = Value::Enum(NameVariant::parse_and_add(&extrap.to_string(), names));
+      set_property(properties, names, name, PropType::ByteProperty, tag, value);
+    }
+
+    // Reuse the inner tag of the existing keys so its guid is kept
+    let meta_tag =
+      find_property(properties, names, "Keys").and_then(|i| match &properties[i].value {
+        Value::Array { meta_tag, .. } => meta_tag.clone(),
+        _ => None,
+      });
+    let meta_tag = meta_tag.unwrap_or_else(|| {
+      let meta = Meta::new(
+        NameVariant::parse_and_add("Keys", names),
+        PropType::StructProperty,
+        0,
+      );
+      let tag = Tag::Struct {
+        type_name: NameVariant::parse_and_add("RichCurveKey", names),
+        guid: [0; 16],
+      };
+      (meta, tag)
+    });
+    let values = self
+      .keys
+      .iter()
+      .map(|key| {
+        let value = Value::Struct {
+          value: key.to_struct_value(),
+        };
+        (meta_tag.1.clone(), value)
+      })
+      .collect();
+    let value = Value::Array {
+      meta_tag: Some(meta_tag),
+      values,
+    };
+    let tag = Tag::Array {
+      inner_type: PropType::StructProperty,
+    };
+    set_property(
+      properties,
+      names,
+      "Keys",
+      PropType::ArrayProperty,
+      tag,
+      value,
+    );
+  }
+
+  /// Evaluates the curve like `FRichCurve::Eval`. Curves without keys
+  /// evaluate to the default value, or `default` if they don't have one.
+  pub fn eval(&self, time: f32, default: f32) -> f32 {
+    let (time, cycle_value_offset) = self.remap_time(time);
+    let keys = &self.keys;
+    let value = match (keys.first(), keys.last()) {
+      (Some(first), Some(last)) => {
+        if keys.len() < 2 || time <= first.time {
+          if self.pre_infinity_extrap == RichCurveExtrapolation::Linear && keys.len() > 1 {
+            linear_extrapolate(first, &keys[1], time)
+          } else {
+            first.value
+          }
+        } else if time < last.time {
+          let next = keys.iter().position(|key| key.time > time).unwrap();
+          eval_for_two_keys(&keys[next - 1], &keys[next], time)
+        } else if self.post_infinity_extrap == RichCurveExtrapolation::Linear {
+          linear_extrapolate(last, &keys[keys.len() - 2], time)
+        } else {
+          last.value
+        }
+      }
+      _ => self.default_value.unwrap_or(default),
+    };
+    value + cycle_value_offset
+  }
+
+  /// Maps a time outside of the keys into their range for the cycling
+  /// extrapolation modes. Also returns the value offset for
+  /// `CycleWithOffset`.
+  fn remap_time(&self, time: f32) -> (f32, f32) {
+    let keys = &self.keys;
+    if keys.len() < 2 {
+      return (time, 0.0);
+    }
+    let first = &keys[0];
+    let last = &keys[keys.len() - 1];
+    let (extrap, value_delta) = if time <= first.time {
+      (self.pre_infinity_extrap, first.value - last.value)
+    } else if time >= last.time {
+      (self.post_infinity_extrap, last.value - first.value)
+    } else {
+      return (time, 0.0);
+    };
+    match extrap {
+      RichCurveExtrapolation::Linear | RichCurveExtrapolation::Constant => (time, 0.0),
+      _ => {
+        let (mut time, cycle_count) = cycle_time(first.time, last.time, time);
+        match extrap {
+          RichCurveExtrapolation::CycleWithOffset => (time, value_delta * cycle_count as f32),
+          RichCurveExtrapolation::Oscillate => {
+            if cycle_count % 2 == 1 {
+              time = first.time + (last.time - time);
+            }
+            (time, 0.0)
+          }
+          _ => (time, 0.0),
+        }
+      }
+    }
+  }
+}
+
+fn read_extrapolation(value: &NameVariant, names: &Names) -> Result<RichCurveExtrapolation> {
+  let name = value.to_string(names);
+  RichCurveExtrapolation::from_str(&name)
+    .map_err(|_| anyhow!("Unknown curve extrapolation {}", name))
+}
+
+fn find_property(properties: &[Property], names: &Names, name: &str) -> Option<usize> {
+  properties
+    .iter()
+    .position(|property| property.meta.name.to_string(names) == name)
+}
+
+/// Replaces the tag and value of a property, adding it if it doesn't exist
+fn set_property(
+  properties: &mut Vec<Property>,
+  names: &mut Names,
+  name: &str,
+  typ: PropType,
+  tag: Tag,
+  value: Value,
+) {
+  // The type is written as a name too
+  NameVariant::parse_and_add(&typ.to_string(), names);
+  match find_property(properties, names, name) {
+    Some(i) => {
+      properties[i].tag = tag;
+      properties[i].value = value;
+    }
+    None => {
+      let meta = Meta::new(NameVariant::parse_and_add(name, names), typ, 0);
+      properties.push(Property { meta, tag, value });
+    }
+  }
+}
+
+fn remove_property(properties: &mut Vec<Property>, names: &Names, name: &str) {
+  properties.retain(|property| property.meta.name.to_string(names) != name);
+}
+
+/// Wraps a time outside of `[min_time, max_time]` back into it, returning the
+/// new time and how many cycles away it was.
+fn cycle_time(min_time: f32, max_time: f32, time: f32) -> (f32, i32) {
+  let duration = max_time - min_time;
+  if duration <= 0.0 {
+    return (time, 0);
+  }
+  let mut new_time = time;
+  let mut cycle_count = 0;
+  if time > max_time {
+    cycle_count = ((max_time - time) / duration).floor() as i32;
+    new_time = time + duration * cycle_count as f32;
+  } else if time < min_time {
+    cycle_count = ((time - min_time) / duration).floor() as i32;
+    new_time = time - duration * cycle_count as f32;
+  }
+  if new_time == max_time && time < min_time {
+    new_time = min_time;
+  }
+  if new_time == min_time && time > max_time {
+    new_time = max_time;
+  }
+  (new_time, cycle_count.abs())
+}
+
+/// Continues the line through `key` and `other` past `key`
+fn linear_extrapolate(key: &RichCurveKey, other: &RichCurveKey, time: f32) -> f32 {
+  let dt = other.time - key.time;
+  if dt.abs() < 1e-8 {
+    key.value
+  } else {
+    let slope = (other.value - key.value) / dt;
+    slope * (time - key.time) + key.value
+  }
+}
+
+fn bezier_interp(p0: f32, p1: f32, p2: f32, p3: f32, alpha: f32) -> f32 {
+  let p01 = p0 + (p1 - p0) * alpha;
+  let p12 = p1 + (p2 - p1) * alpha;
+  let p23 = p2 + (p3 - p2) * alpha;
+  let p012 = p01 + (p12 - p01) * alpha;
+  let p123 = p12 + (p23 - p12) * alpha;
+  p012 + (p123 - p012) * alpha
+}
+
+fn eval_for_two_keys(key1: &RichCurveKey, key2: &RichCurveKey, time: f32) -> f32 {
+  let diff = key2.time - key1.time;
+  if diff <= 0.0 || key1.interp_mode == RichCurveInterpMode::Constant {
+    return key1.value;
+  }
+  let alpha = (time - key1.time) / diff;
+  if key1.interp_mode == RichCurveInterpMode::Linear {
+    return key1.value + (key2.value - key1.value) * alpha;
+  }
+
+  use RichCurveTangentWeightMode::*;
+  let leave_weighted = matches!(key1.tangent_weight_mode, WeightedLeave | WeightedBoth);
+  let arrive_weighted = matches!(key2.tangent_weight_mode, WeightedArrive | WeightedBoth);
+  if !leave_weighted && !arrive_weighted {
+    let p1 = key1.value + key1.leave_tangent * diff / 3.0;
+    let p2 = key2.value - key2.arrive_tangent * diff / 3.0;
+    return bezier_interp(key1.value, p1, p2, key2.value, alpha);
+  }
+
+  // Weighted tangents move the control points in time too, so find the
+  // bezier parameter that lands on `time` first
+  let leave_weight = if leave_weighted {
+    key1.leave_tangent_weight
+  } else {
+    diff * (1.0 + key1.leave_tangent * key1.leave_tangent).sqrt() / 3.0
+  };
+  let arrive_weight = if arrive_weighted {
+    key2.arrive_tangent_weight
+  } else {
+    diff * (1.0 + key2.arrive_tangent * key2.arrive_tangent).sqrt() / 3.0
+  };
+  let leave_angle = key1.leave_tangent.atan();
+  let arrive_angle = key2.arrive_tangent.atan();
+  let x1 = leave_angle.cos() * leave_weight / diff;
+  let y1 = key1.value + leave_angle.sin() * leave_weight;
+  let x2 = 1.0 - arrive_angle.cos() * arrive_weight / diff;
+  let y2 = key2.value - arrive_angle.sin() * arrive_weight;
+
+  let (mut low, mut high) = (0.0f32, 1.0f32);
+  for _ in 0..32 {
+    let mid = (low + high) / 2.0;
+    if bezier_interp(0.0, x1, x2, 1.0, mid) < alpha {
+      low = mid;
+    } else {
+      high = mid;
+    }
+  }
+  bezier_interp(key1.value, y1, y2, key2.value, (low + high) / 2.0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CurveTableMode {
+  Empty,
+  SimpleCurves,
+  RichCurves,
+}
+
+/// Rows of a CurveTable, stored as a `TMap<FName, FRealCurve*>` of tagged
+/// `FRichCurve` or `FSimpleCurve` properties
+#[derive(Debug)]
+pub struct CurveTable {
+  pub mode: CurveTableMode,
+  pub rows: Vec<(NameVariant, StructValue)>,
+}
+
+pub struct CurveTableSerializer;
+
+impl NativeSerializer for CurveTableSerializer {
+  fn deserialize(
+    &self,
+    data: &[u8],
+    _properties: &Properties,
+    ctx: PropertyContext,
+  ) -> Result<Box<dyn NativeData>> {
+    let mut rdr = ByteReader::new(data.to_vec());
+    let count = read_u32(&mut rdr)?;
+    let mode = match rdr.read_u8()? {
+      0 => CurveTableMode::Empty,
+      1 => CurveTableMode::SimpleCurves,
+      2 => CurveTableMode::RichCurves,
+      mode => bail!("Invalid curve table mode {}", mode),
+    };
+    let row_struct = match mode {
+      CurveTableMode::SimpleCurves => "SimpleCurve",
+      _ => "RichCurve",
+    };
+    let mut rows = vec![];
+    for i in 0..count {
+      let name = NameVariant::read(&mut rdr, ctx.names).with_context(|| format!("Row {}", i))?;
+      let value = ctx
+        .patterns
+        .deserialize(&mut rdr, row_struct, ctx)
+        .with_context(|| format!("Row {}", name.to_string(ctx.names)))?;
+      rows.push((name, value));
+    }
+    if rdr.remaining_bytes() != 0 {
+      bail!(
+        "{:#X} bytes left after CurveTable rows",
+        rdr.remaining_bytes()
+      );
+    }
+    Ok(Box::new(CurveTable { mode, rows }))
+  }
+}
+
+impl NativeData for CurveTable {
+  fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+    write_u32(curs, self.rows.len() as u32)?;
+    curs.write_u8(self.mode as u8)?;
+    for (name, value) in self.rows.iter() {
+      name.write(curs, ctx.names)?;
+      value
+        .serialize(curs, ctx)
+        .with_context(|| format!("Row {}", name.to_string(ctx.names)))?;
+    }
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    5 + self
+      .rows
+      .iter()
+      .map(|(_, value)| 8 + value.byte_size())
+      .sum::<usize>()
+  }
+
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+}
+
+impl CurveTable {
+  /// Reads the curve of a row. Only tables of rich curves are supported.
+  pub fn curve(&self, row: &NameVariant, names: &Names) -> Result<RichCurve> {
+    if self.mode != CurveTableMode::RichCurves {
+      bail!("Only rich curve tables are supported");
+    }
+    match self.rows.iter().find(|(name, _)| name == row) {
+      Some((_, StructValue::PropertyList { properties, .. })) => {
+        RichCurve::from_properties(properties, names)
+      }
+      Some(_) => bail!("Row {} is not a property list", row.to_string(names)),
+      None => bail!("No row {}", row.to_string(names)),
+    }
+  }
+
+  /// Replaces the curve of a row, adding the row if it doesn't exist
+  pub fn set_curve(
+    &mut self,
+    row: NameVariant,
+    curve: &RichCurve,
+    names: &mut Names,
+  ) -> Result<()> {
+    match self.mode {
+      CurveTableMode::RichCurves => {}
+      CurveTableMode::Empty => self.mode = CurveTableMode::RichCurves,
+      CurveTableMode::SimpleCurves => bail!("Only rich curve tables are supported"),
+    }
+    let index = match self.rows.iter().position(|(name, _)| *name == row) {
+      Some(index) => index,
+      None => {
+        let value = StructValue::PropertyList {
+          properties: vec![],
+          has_none: true,
+        };
+        self.rows.push((row, value));
+        self.rows.len() - 1
+      }
+    };
+    match &mut self.rows[index].1 {
+      StructValue::PropertyList { properties, .. } => {
+        curve.write_to_properties(properties, names);
+        Ok(())
+      }
+      _ => bail!("Row is not a property list"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn key(time: f32, value: f32, interp_mode: RichCurveInterpMode) -> RichCurveKey {
+    RichCurveKey {
+      interp_mode,
+      ..RichCurveKey::new(time, value)
+    }
+  }
+
+  /// A cubic, a linear and a constant segment
+  fn curve(extrap: RichCurveExtrapolation) -> RichCurve {
+    let mut cubic = key(0.0, 0.0, RichCurveInterpMode::Cubic);
+    cubic.leave_tangent = 3.0;
+    RichCurve {
+      keys: vec![
+        cubic,
+        key(1.0, 1.0, RichCurveInterpMode::Linear),
+        key(3.0, 5.0, RichCurveInterpMode::Constant),
+        key(4.0, -1.0, RichCurveInterpMode::Constant),
+      ],
+      default_value: None,
+      pre_infinity_extrap: extrap,
+      post_infinity_extrap: extrap,
+    }
+  }
+
+  fn assert_near(actual: f32, expected: f32) {
+    assert!(
+      (actual - expected).abs() < 1e-4,
+      "{} is not {}",
+      actual,
+      expected
+    );
+  }
+
+  // Expected values are from FRichCurve::Eval on the same keys
+  #[test]
+  fn eval_interpolates_between_keys() {
+    let curve = curve(RichCurveExtrapolation::Constant);
+    assert_near(curve.eval(0.5, 0.0), 0.875);
+    assert_near(curve.eval(0.25, 0.0), 0.578_125);
+    assert_near(curve.eval(1.0, 0.0), 1.0);
+    assert_near(curve.eval(2.0, 0.0), 3.0);
+    assert_near(curve.eval(2.5, 0.0), 4.0);
+    assert_near(curve.eval(3.0, 0.0), 5.0);
+    assert_near(curve.eval(3.99, 0.0), 5.0);
+    assert_near(curve.eval(4.0, 0.0), -1.0);
+  }
+
+  #[test]
+  fn eval_outside_of_the_keys() {
+    let constant = curve(RichCurveExtrapolation::Constant);
+    assert_near(constant.eval(-1.0, 0.0), 0.0);
+    assert_near(constant.eval(10.0, 0.0), -1.0);
+
+    let linear = curve(RichCurveExtrapolation::Linear);
+    assert_near(linear.eval(-2.0, 0.0), -2.0);
+    assert_near(linear.eval(5.0, 0.0), -7.0);
+
+    let cycle = curve(RichCurveExtrapolation::CycleWithOffset);
+    assert_near(cycle.eval(5.0, 0.0), 0.0);
+    assert_near(cycle.eval(-2.0, 0.0), 4.0);
+
+    let oscillate = curve(RichCurveExtrapolation::Oscillate);
+    assert_near(oscillate.eval(7.0, 0.0), 1.0);
+
+    let empty = RichCurve {
+      keys: vec![],
+      ..curve(RichCurveExtrapolation::Constant)
+    };
+    assert_near(empty.eval(1.0, 2.0), 2.0);
+  }
+
+  #[test]
+  fn weighted_tangents_match_their_unweighted_length() {
+    // A tangent weight of `dt * sqrt(1 + tangent²) / 3` is the length the
+    // unweighted cubic uses, so both evaluate the same
+    let mut from = key(0.0, 0.0, RichCurveInterpMode::Cubic);
+    from.leave_tangent = 3.0;
+    from.leave_tangent_weight = 10f32.sqrt() / 3.0;
+    from.tangent_weight_mode = RichCurveTangentWeightMode::WeightedLeave;
+    let to = key(1.0, 1.0, RichCurveInterpMode::Cubic);
+    assert_near(eval_for_two_keys(&from, &to, 0.5), 0.875);
+    assert_near(eval_for_two_keys(&from, &to, 1.0), 1.0);
+
+    // Keys at the same time don't interpolate
+    assert_near(
+      eval_for_two_keys(&from, &key(0.0, 1.0, RichCurveInterpMode::Cubic), 0.0),
+      0.0,
+    );
+  }
+}
@@ -0,0 +1,245 @@
+use crate::asset::struct_pattern::*;
+use crate::asset::*;
+use crate::reader::*;
+use crate::util::*;
+use anyhow::*;
+use std::any::Any;
+use std::io::Cursor;
+
+/// Rows of a DataTable, stored as a `TMap<FName, RowStruct>`
+#[derive(Debug)]
+pub struct DataTable {
+  pub row_struct: String,
+  pub rows: Vec<(NameVariant, StructValue)>,
+}
+
+pub struct DataTableSerializer;
+
+impl NativeSerializer for DataTableSerializer {
+  fn deserialize(
+    &self,
+    data: &[u8],
+    properties: &Properties,
+    ctx: PropertyContext,
+  ) -> Result<Box<dyn NativeData>> {
+    let row_struct = properties
+      .properties
+      .iter()
+      .find(|prop| prop.meta.name.to_string(ctx.names) == "RowStruct")
+      .and_then(|prop| match &prop.value {
+        Value::Object(Reference::Import { name, .. }) => Some(name.to_string(ctx.names)),
+        _ => None,
+      })
+      .ok_or_else(|| anyhow!("DataTable has no imported RowStruct"))?;
+
+    let mut rdr = ByteReader::new(data.to_vec());
+    let count = read_u32(&mut rdr)?;
+    let mut rows = vec![];
+    for i in 0..count {
+      let name = NameVariant::read(&mut rdr, ctx.names).with_context(|| format!("Row {}", i))?;
+      let value = ctx
+        .patterns
+        .deserialize(&mut rdr, &row_struct, ctx)
+        .with_context(|| format!("Row {}", name.to_string(ctx.names)))?;
+      rows.push((name, value));
+    }
+    if rdr.remaining_bytes() != 0 {
+      bail!(
+        "{:#X} bytes left after DataTable rows",
+        rdr.remaining_bytes()
+      );
+    }
+    Ok(Box::new(DataTable { row_struct, rows }))
+  }
+}
+
+impl NativeData for DataTable {
+  fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+    write_u32(curs, self.rows.len() as u32)?;
+    for (name, value) in self.rows.iter() {
+      name.write(curs, ctx.names)?;
+      value
+        .serialize(curs, ctx)
+        .with_context(|| format!("Row {}", name.to_string(ctx.names)))?;
+    }
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    4 + self
+      .rows
+      .iter()
+      .map(|(_, value)| 8 + value.byte_size())
+      .sum::<usize>()
+  }
+
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+}
+
+impl DataTable {
+  pub fn row(&self, name: &NameVariant) -> Option<&StructValue> {
+    self
+      .rows
+      .iter()
+      .find(|(row_name, _)| row_name == name)
+      .map(|(_, value)| value)
+  }
+
+  pub fn row_mut(&mut self, name: &NameVariant) -> Option<&mut StructValue> {
+    self
+      .rows
+      .iter_mut()
+      .find(|(row_name, _)| row_name == name)
+      .map(|(_, value)| value)
+  }
+
+  /// Adds a row, replacing the value of an existing row with the same name
+  pub fn add_row(&mut self, name: NameVariant, value: StructValue) {
+    match self.row_mut(&name) {
+      Some(row) => *row = value,
+      None => self.rows.push((name, value)),
+    }
+  }
+
+  /// Removes a row, returning its value if it existed
+  pub fn remove_row(&mut self, name: &NameVariant) -> Option<StructValue> {
+    let index = self
+      .rows
+      .iter()
+      .position(|(row_name, _)| row_name == name)?;
+    Some(self.rows.remove(index).1)
+  }
+
+  /// Renames a row. Returns false if there is no row named `from` or there
+  /// already is a row named `to`.
+  pub fn rename_row(&mut self, from: &NameVariant, to: NameVariant) -> bool {
+    if self.row(&to).is_some() {
+      return false;
+    }
+    match self.rows.iter_mut().find(|(row_name, _)| row_name == from) {
+      Some((row_name, _)) => {
+        *row_name = to;
+        true
+      }
+      None => false,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::asset::property::meta::*;
+  use crate::asset::property::TestPackage;
+
+  fn row(package: &TestPackage, damage: i32) -> StructValue {
+    StructValue::PropertyList {
+      properties: vec![Property {
+        meta: Meta::new(
+          NameVariant::new("Damage", 0, &package.names),
+          PropType::IntProperty,
+          4,
+        ),
+        tag: Tag::Simple(PropType::IntProperty),
+        value: Value::Int(damage),
+      }],
+      has_none: true,
+    }
+  }
+
+  fn damage(table: &DataTable, name: &NameVariant) -> Option<i32> {
+    match table.row(name)? {
+      StructValue::PropertyList { properties, .. } => match properties[0].value {
+        Value::Int(damage) => Some(damage),
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+
+  #[test]
+  fn rows_are_read_with_the_row_struct() {
+    let package = TestPackage::new(&[
+      "RowStruct",
+      "ObjectProperty",
+      "ScriptStruct",
+      "WeaponRow",
+      "Pickaxe",
+      "Drill",
+      "Damage",
+      "IntProperty",
+    ]);
+    let name = |name| NameVariant::new(name, 0, &package.names);
+    let table = DataTable {
+      row_struct: "WeaponRow".to_string(),
+      rows: vec![
+        (name("Pickaxe"), row(&package, 40)),
+        (name("Drill"), row(&package, 12)),
+      ],
+    };
+    let mut curs = Cursor::new(vec![]);
+    table.serialize(&mut curs, package.ctx()).unwrap();
+    let bytes = curs.into_inner();
+    assert_eq!(table.byte_size(), bytes.len());
+
+    // Rows can't be read without knowing their struct
+    let err = DataTableSerializer
+      .deserialize(&bytes, &package.properties(), package.ctx())
+      .unwrap_err();
+    assert_eq!(err.to_string(), "DataTable has no imported RowStruct");
+
+    let mut properties = package.properties();
+    properties.properties.push(Property {
+      meta: Meta::new(name("RowStruct"), PropType::ObjectProperty, 4),
+      tag: Tag::Simple(PropType::ObjectProperty),
+      value: Value::Object(Reference::import(name("ScriptStruct"), name("WeaponRow"))),
+    });
+    let native = DataTableSerializer
+      .deserialize(&bytes, &properties, package.ctx())
+      .unwrap();
+    let read = native.as_any().downcast_ref::<DataTable>().unwrap();
+    assert_eq!(read.row_struct, "WeaponRow");
+    assert_eq!(damage(read, &name("Pickaxe")), Some(40));
+    assert_eq!(damage(read, &name("Drill")), Some(12));
+
+    // Trailing bytes mean the rows weren't understood
+    let mut extra = bytes.clone();
+    extra.push(0);
+    assert!(DataTableSerializer
+      .deserialize(&extra, &properties, package.ctx())
+      .is_err());
+  }
+
+  #[test]
+  fn rows_are_added_removed_and_renamed() {
+    let package = TestPackage::new(&["Pickaxe", "Drill", "Shotgun", "Damage"]);
+    let name = |name| NameVariant::new(name, 0, &package.names);
+    let mut table = DataTable {
+      row_struct: "WeaponRow".to_string(),
+      rows: vec![],
+    };
+
+    table.add_row(name("Pickaxe"), row(&package, 40));
+    table.add_row(name("Drill"), row(&package, 12));
+    table.add_row(name("Pickaxe"), row(&package, 45));
+    assert_eq!(table.rows.len(), 2);
+    assert_eq!(damage(&table, &name("Pickaxe")), Some(45));
+
+    assert!(!table.rename_row(&name("Drill"), name("Pickaxe")));
+    assert!(!table.rename_row(&name("Shotgun"), name("Drill")));
+    assert!(table.rename_row(&name("Drill"), name("Shotgun")));
+    assert_eq!(damage(&table, &name("Drill")), None);
+    assert_eq!(damage(&table, &name("Shotgun")), Some(12));
+
+    assert!(table.remove_row(&name("Pickaxe")).is_some());
+    assert!(table.remove_row(&name("Pickaxe")).is_none());
+    assert_eq!(table.rows.len(), 1);
+    assert_eq!(table.rows[0].0, name("Shotgun"));
+  }
+}
@@ -0,0 +1,261 @@
+use crate::asset::property::text::*;
+use crate::asset::*;
+use crate::reader::*;
+use crate::util::*;
+use anyhow::*;
+use std::any::Any;
+use std::io::Cursor;
+
+/// Metadata of a StringTable entry, by metadata id
+pub type EntryMetadata = Vec<(NameVariant, Option<String>)>;
+
+/// Localizable strings of a StringTable, looked up by key. Texts reference
+/// entries with a `StringTableEntry` history. Strings are None when they
+/// were written as null strings instead of empty ones.
+#[derive(Debug)]
+pub struct StringTable {
+  pub namespace: Option<String>,
+  pub entries: Vec<(Option<String>, Option<String>)>, // key to source string
+  pub metadata: Vec<(Option<String>, EntryMetadata)>, // key to metadata
+}
+
+pub struct StringTableSerializer;
+
+impl NativeSerializer for StringTableSerializer {
+  fn deserialize(
+    &self,
+    data: &[u8],
+    _properties: &Properties,
+    ctx: PropertyContext,
+  ) -> Result<Box<dyn NativeData>> {
+    let mut rdr = ByteReader::new(data.to_vec());
+    let namespace = read_fstring(&mut rdr).with_context(|| "Namespace")?;
+
+    let entry_count = read_u32(&mut rdr)?;
+    let mut entries = vec![];
+    for i in 0..entry_count {
+      let key = read_fstring(&mut rdr).with_context(|| format!("Entry {} key", i))?;
+      let source = read_fstring(&mut rdr).with_context(|| format!("Entry {:?}", key))?;
+      entries.push((key, source));
+    }
+
+    let metadata_count = read_u32(&mut rdr)?;
+    let mut metadata = vec![];
+    for i in 0..metadata_count {
+      let key = read_fstring(&mut rdr).with_context(|| format!("Metadata {} key", i))?;
+      let count = read_u32(&mut rdr)?;
+      let mut key_metadata = vec![];
+      for _ in 0..count {
+        let id = NameVariant::read(&mut rdr, ctx.names)
+          .with_context(|| format!("Metadata of {:?}", key))?;
+        let value = read_fstring(&mut rdr).with_context(|| format!("Metadata of {:?}", key))?;
+        key_metadata.push((id, value));
+      }
+      metadata.push((key, key_metadata));
+    }
+
+    if rdr.remaining_bytes() != 0 {
+      bail!(
+        "{:#X} bytes left after StringTable entries",
+        rdr.remaining_bytes()
+      );
+    }
+    Ok(Box::new(StringTable {
+      namespace,
+      entries,
+      metadata,
+    }))
+  }
+}
+
+impl NativeData for StringTable {
+  fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+    write_fstring(curs, &self.namespace)?;
+    write_u32(curs, self.entries.len() as u32)?;
+    for (key, source) in self.entries.iter() {
+      write_fstring(curs, key)?;
+      write_fstring(curs, source)?;
+    }
+    write_u32(curs, self.metadata.len() as u32)?;
+    for (key, key_metadata) in self.metadata.iter() {
+      write_fstring(curs, key)?;
+      write_u32(curs, key_metadata.len() as u32)?;
+      for (id, value) in key_metadata.iter() {
+        id.write(curs, ctx.names)?;
+        write_fstring(curs, value)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    let entries_size = self
+      .entries
+      .iter()
+      .map(|(key, source)| fstring_size(key) + fstring_size(source))
+      .sum::<usize>();
+    let metadata_size = self
+      .metadata
+      .iter()
+      .map(|(key, key_metadata)| {
+        fstring_size(key)
+          + 4
+          + key_metadata
+            .iter()
+            .map(|(_, value)| 8 + fstring_size(value))
+            .sum::<usize>()
+      })
+      .sum::<usize>();
+    fstring_size(&self.namespace) + 4 + entries_size + 4 + metadata_size
+  }
+
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+}
+
+/// Compares a key read from the table with one to look up, null keys are
+/// the same as empty ones
+fn is_key(read: &Option<String>, key: &str) -> bool {
+  read.as_deref().unwrap_or_default() == key
+}
+
+impl StringTable {
+  /// Gets the source string of an entry
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self
+      .entries
+      .iter()
+      .find(|(k, _)| is_key(k, key))
+      .map(|(_, source)| source.as_deref().unwrap_or_default())
+  }
+
+  /// Sets the source string of an entry, adding the entry if it doesn't exist
+  pub fn set(&mut self, key: &str, source: &str) {
+    match self.entries.iter_mut().find(|(k, _)| is_key(k, key)) {
+      Some((_, s)) => *s = new_fstring(source),
+      None => self.entries.push((new_fstring(key), new_fstring(source))),
+    }
+  }
+
+  /// Removes an entry and its metadata, returning its source string if it
+  /// existed
+  pub fn remove(&mut self, key: &str) -> Option<String> {
+    self.metadata.retain(|(k, _)| !is_key(k, key));
+    let index = self.entries.iter().position(|(k, _)| is_key(k, key))?;
+    Some(self.entries.remove(index).1.unwrap_or_default())
+  }
+
+  pub fn metadata(&self, key: &str) -> Option<&EntryMetadata> {
+    self
+      .metadata
+      .iter()
+      .find(|(k, _)| is_key(k, key))
+      .map(|(_, key_metadata)| key_metadata)
+  }
+
+  /// Looks up the source string of a text that references this table.
+  /// `table_id` is the object path of this table, like
+  /// `/Game/Text/ST_Items.ST_Items`, and `names` are the names of the asset
+  /// the text is in.
+  pub fn resolve(&self, table_id: &str, text: &Text, names: &Names) -> Option<&str> {
+    match &text.history {
+      TextHistory::StringTableEntry {
+        table_id: text_table_id,
+        key,
+      } if text_table_id.to_string(names) == table_id => self.get(key.as_deref().unwrap_or("")),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::asset::property::TestPackage;
+
+  fn string_table(package: &TestPackage) -> StringTable {
+    let string = |s: &str| Some(s.to_string());
+    StringTable {
+      namespace: string("Items"),
+      entries: vec![
+        (string("Sword"), string("Sword")),
+        (string("Empty"), string("")),
+        (string("Null"), None),
+        (string("Unicode"), string("Épée")),
+      ],
+      metadata: vec![(
+        string("Sword"),
+        vec![(
+          NameVariant::new("Comment", 0, &package.names),
+          string("Shown in the shop"),
+        )],
+      )],
+    }
+  }
+
+  #[test]
+  fn string_table_round_trips() {
+    let package = TestPackage::new(&["Comment"]);
+    let native = package.native_round_trip(&StringTableSerializer, &string_table(&package));
+    let table = native.as_any().downcast_ref::<StringTable>().unwrap();
+    assert_eq!(table.get("Unicode"), Some("Épée"));
+    assert_eq!(table.get("Empty"), Some(""));
+    assert_eq!(table.get("Null"), Some(""));
+    assert_eq!(table.metadata("Sword").map(|m| m.len()), Some(1));
+  }
+
+  #[test]
+  fn resolve_checks_the_table_id() {
+    let table_id = "/Game/ST_Items.ST_Items";
+    let package = TestPackage::new(&["Comment", table_id, "/Game/ST_Other.ST_Other"]);
+    let table = string_table(&package);
+    let text = |table_id: &str| Text {
+      flags: 0,
+      history: TextHistory::StringTableEntry {
+        table_id: NameVariant::new(table_id, 0, &package.names),
+        key: Some("Sword".to_string()),
+      },
+    };
+    assert_eq!(
+      table.resolve(table_id, &text(table_id), &package.names),
+      Some("Sword")
+    );
+    assert_eq!(
+      table.resolve(table_id, &text("/Game/ST_Other.ST_Other"), &package.names),
+      None
+    );
+  }
+
+  #[test]
+  fn empty_and_null_strings_are_kept_apart() {
+    let package = TestPackage::new(&[]);
+    let mut table = StringTable {
+      namespace: None,
+      entries: vec![(Some("Key".to_string()), Some(String::new()))],
+      metadata: vec![],
+    };
+    let mut curs = Cursor::new(vec![]);
+    table.serialize(&mut curs, package.ctx()).unwrap();
+    #[rustfmt::skip]
+    let expected = [
+      0, 0, 0, 0, // Null namespace
+      1, 0, 0, 0,
+      4, 0, 0, 0, b'K', b'e', b'y', 0,
+      1, 0, 0, 0, 0, // Empty source string
+      0, 0, 0, 0,
+    ];
+    assert_eq!(curs.into_inner(), expected);
+
+    // New empty strings are null like the engine writes them
+    table.set("Key", "");
+    table.set("", "Value");
+    assert_eq!(table.entries[0].1, None);
+    assert_eq!(table.entries[1].0, None);
+    assert_eq!(table.get(""), Some("Value"));
+  }
+}
@@ -0,0 +1,377 @@
+use crate::asset::*;
+use crate::reader::*;
+use crate::util::*;
+use anyhow::*;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::any::Any;
+use std::io::Cursor;
+
+const HAS_OPT_DATA: u32 = 1 << 30;
+
+/// Which parts of an object were stripped when it was cooked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StripDataFlags {
+  pub global: u8,
+  pub class: u8,
+}
+
+impl StripDataFlags {
+  fn read(rdr: &mut ByteReader) -> Result<Self> {
+    Ok(Self {
+      global: rdr.read_u8()?,
+      class: rdr.read_u8()?,
+    })
+  }
+
+  fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    curs.write_u8(self.global)?;
+    curs.write_u8(self.class)?;
+    Ok(())
+  }
+
+  pub fn is_editor_data_stripped(&self) -> bool {
+    self.global & 1 != 0
+  }
+}
+
+/// A mip of cooked texture platform data
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureMip {
+  pub bulk_data: ByteBulkData,
+  pub size_x: i32,
+  pub size_y: i32,
+  pub size_z: i32,
+}
+
+impl TextureMip {
+  fn read(rdr: &mut ByteReader) -> Result<Self> {
+    if !read_bool(rdr)? {
+      bail!("Mips that aren't cooked aren't supported");
+    }
+    let bulk_data = ByteBulkData::read(rdr)?;
+    Ok(Self {
+      bulk_data,
+      size_x: rdr.read_i32::<LittleEndian>()?,
+      size_y: rdr.read_i32::<LittleEndian>()?,
+      size_z: rdr.read_i32::<LittleEndian>()?,
+    })
+  }
+
+  fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    write_bool(curs, true)?;
+    self.bulk_data.write(curs)?;
+    curs.write_i32::<LittleEndian>(self.size_x)?;
+    curs.write_i32::<LittleEndian>(self.size_y)?;
+    curs.write_i32::<LittleEndian>(self.size_z)?;
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    4 + self.bulk_data.byte_size() + 12
+  }
+}
+
+/// The cooked data of a texture for one pixel format
+#[derive(Debug, Clone, PartialEq)]
+pub struct TexturePlatformData {
+  pub size_x: i32,
+  pub size_y: i32,
+  // Number of slices, with flags in the top bits
+  pub packed_data: u32,
+  pub pixel_format: Option<String>,
+  // Ext data and number of mips in the tail, present when packed_data says
+  pub opt_data: Option<(u32, u32)>,
+  pub first_mip_to_serialize: i32,
+  pub mips: Vec<TextureMip>,
+}
+
+impl TexturePlatformData {
+  fn read(rdr: &mut ByteReader) -> Result<Self> {
+    let size_x = rdr.read_i32::<LittleEndian>()?;
+    let size_y = rdr.read_i32::<LittleEndian>()?;
+    let packed_data = read_u32(rdr)?;
+    let pixel_format = read_fstring(rdr).with_context(|| "Pixel format")?;
+    let opt_data = if packed_data & HAS_OPT_DATA != 0 {
+      Some((read_u32(rdr)?, read_u32(rdr)?))
+    } else {
+      None
+    };
+    let first_mip_to_serialize = rdr.read_i32::<LittleEndian>()?;
+    let mip_count = read_u32(rdr)?;
+    let mut mips = vec![];
+    for i in 0..mip_count {
+      mips.push(TextureMip::read(rdr).with_context(|| format!("Mip {}", i))?);
+    }
+    if read_bool(rdr)? {
+      bail!("Virtual textures aren't supported");
+    }
+    Ok(Self {
+      size_x,
+      size_y,
+      packed_data,
+      pixel_format,
+      opt_data,
+      first_mip_to_serialize,
+      mips,
+    })
+  }
+
+  fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    curs.write_i32::<LittleEndian>(self.size_x)?;
+    curs.write_i32::<LittleEndian>(self.size_y)?;
+    write_u32(curs, self.packed_data)?;
+    write_fstring(curs, &self.pixel_format)?;
+    if let Some((ext_data, mips_in_tail)) = self.opt_data {
+      write_u32(curs, ext_data)?;
+      write_u32(curs, mips_in_tail)?;
+    }
+    curs.write_i32::<LittleEndian>(self.first_mip_to_serialize)?;
+    write_u32(curs, self.mips.len() as u32)?;
+    for mip in self.mips.iter() {
+      mip.write(curs)?;
+    }
+    write_bool(curs, false)?;
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    let opt_data_size = if self.opt_data.is_some() { 8 } else { 0 };
+    let mips_size = self.mips.iter().map(|mip| mip.byte_size()).sum::<usize>();
+    12 + fstring_size(&self.pixel_format) + opt_data_size + 8 + mips_size + 4
+  }
+}
+
+/// Native data of cooked Texture2D and TextureCube exports, which holds the
+/// bulk data of each mip
+#[derive(Debug)]
+pub struct Texture {
+  pub texture_strip_flags: StripDataFlags,
+  pub class_strip_flags: StripDataFlags,
+  pub platform_data: Vec<(NameVariant, TexturePlatformData)>,
+  // Where this data starts in the package. Each platform data is preceded by
+  // the absolute offset of its end.
+  package_offset: u64,
+}
+
+pub struct TextureSerializer;
+
+impl NativeSerializer for TextureSerializer {
+  fn deserialize(
+    &self,
+    data: &[u8],
+    _properties: &Properties,
+    ctx: PropertyContext,
+  ) -> Result<Box<dyn NativeData>> {
+    let mut rdr = ByteReader::new(data.to_vec());
+    let texture_strip_flags = StripDataFlags::read(&mut rdr)?;
+    if !texture_strip_flags.is_editor_data_stripped() {
+      bail!("Textures with editor data aren't supported");
+    }
+    let class_strip_flags = StripDataFlags::read(&mut rdr)?;
+    if !read_bool(&mut rdr)? {
+      bail!("Only cooked textures are supported");
+    }
+
+    let mut platform_data = vec![];
+    let mut package_offset = None;
+    loop {
+      let pixel_format = NameVariant::read(&mut rdr, ctx.names)?;
+      if pixel_format.to_string(ctx.names) == "None" {
+        break;
+      }
+      let end_offset = rdr.read_i64::<LittleEndian>()?;
+      let data = TexturePlatformData::read(&mut rdr)
+        .with_context(|| format!("{} platform data", pixel_format.to_string(ctx.names)))?;
+
+      // Every end offset has to agree on where this data starts, otherwise
+      // the layout isn't the one expected
+      let start = end_offset - rdr.position() as i64;
+      if start < 0 || package_offset.is_some_and(|offset| offset != start) {
+        bail!(
+          "End offset {:#X} of {} platform data doesn't match its position",
+          end_offset,
+          pixel_format.to_string(ctx.names)
+        );
+      }
+      package_offset = Some(start);
+      platform_data.push((pixel_format, data));
+    }
+
+    if rdr.remaining_bytes() != 0 {
+      bail!(
+        "{:#X} bytes left after texture platform data",
+        rdr.remaining_bytes()
+      );
+    }
+    Ok(Box::new(Texture {
+      texture_strip_flags,
+      class_strip_flags,
+      platform_data,
+      package_offset: package_offset.unwrap_or(0) as u64,
+    }))
+  }
+}
+
+impl NativeData for Texture {
+  fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+    let start = curs.position();
+    self.texture_strip_flags.write(curs)?;
+    self.class_strip_flags.write(curs)?;
+    write_bool(curs, true)?;
+    for (pixel_format, data) in self.platform_data.iter() {
+      pixel_format.write(curs, ctx.names)?;
+      let end = curs.position() + 8 + data.byte_size() as u64 - start;
+      curs.write_u64::<LittleEndian>(self.package_offset + end)?;
+      data.write(curs)?;
+    }
+    NameVariant::new("None", 0, ctx.names)
+      .write(curs, ctx.names)
+      .with_context(|| "Expected None in names")?;
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    let platform_data_size = self
+      .platform_data
+      .iter()
+      .map(|(_, data)| 8 + 8 + data.byte_size())
+      .sum::<usize>();
+    2 + 2 + 4 + platform_data_size + 8
+  }
+
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+
+  fn bulk_data(&self) -> Vec<&ByteBulkData> {
+    self
+      .platform_data
+      .iter()
+      .flat_map(|(_, data)| data.mips.iter().map(|mip| &mip.bulk_data))
+      .collect()
+  }
+
+  fn bulk_data_mut(&mut self) -> Vec<&mut ByteBulkData> {
+    self
+      .platform_data
+      .iter_mut()
+      .flat_map(|(_, data)| data.mips.iter_mut().map(|mip| &mut mip.bulk_data))
+      .collect()
+  }
+
+  fn set_package_offset(&mut self, offset: u64) {
+    self.package_offset = offset;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::asset::property::TestPackage;
+
+  fn mip(flags: BulkDataFlags, inline_payload: Vec<u8>) -> TextureMip {
+    TextureMip {
+      bulk_data: ByteBulkData {
+        header: BulkData {
+          flags,
+          element_count: 0x10,
+          size_on_disk: 0x10,
+          offset_in_file: 0,
+        },
+        inline_payload,
+      },
+      size_x: 4,
+      size_y: 4,
+      size_z: 1,
+    }
+  }
+
+  fn texture(package: &TestPackage, package_offset: u64) -> Texture {
+    let platform_data = |opt_data: Option<(u32, u32)>| TexturePlatformData {
+      size_x: 4,
+      size_y: 4,
+      packed_data: if opt_data.is_some() {
+        1 | HAS_OPT_DATA
+      } else {
+        1
+      },
+      pixel_format: Some("PF_B8G8R8A8".to_string()),
+      opt_data,
+      first_mip_to_serialize: 0,
+      mips: vec![
+        mip(BulkDataFlags::PAYLOAD_IN_SEPERATE_FILE, vec![]),
+        mip(BulkDataFlags::FORCE_INLINE_PAYLOAD, vec![0xFF; 0x10]),
+      ],
+    };
+    Texture {
+      texture_strip_flags: StripDataFlags {
+        global: 1,
+        class: 0,
+      },
+      class_strip_flags: StripDataFlags {
+        global: 1,
+        class: 0,
+      },
+      platform_data: vec![
+        (
+          NameVariant::new("PF_B8G8R8A8", 0, &package.names),
+          platform_data(None),
+        ),
+        (
+          NameVariant::new("PF_DXT5", 0, &package.names),
+          platform_data(Some((0, 2))),
+        ),
+      ],
+      package_offset,
+    }
+  }
+
+  #[test]
+  fn texture_round_trips() {
+    let package = TestPackage::new(&["PF_B8G8R8A8", "PF_DXT5"]);
+    let native = package.native_round_trip(&TextureSerializer, &texture(&package, 0x1234));
+    let texture = native.as_any().downcast_ref::<Texture>().unwrap();
+    assert_eq!(texture.package_offset, 0x1234);
+    assert_eq!(texture.bulk_data().len(), 4);
+    assert_eq!(texture.bulk_data()[1].inline_payload, [0xFF; 0x10]);
+  }
+
+  #[test]
+  fn end_offsets_follow_the_package_offset() {
+    let package = TestPackage::new(&["PF_B8G8R8A8", "PF_DXT5"]);
+    let write = |texture: &Texture| {
+      let mut curs = Cursor::new(vec![]);
+      texture.serialize(&mut curs, package.ctx()).unwrap();
+      curs.into_inner()
+    };
+    let mut texture = texture(&package, 0x1000);
+    let before = write(&texture);
+    texture.set_package_offset(0x1100);
+    let after = write(&texture);
+
+    // The end offset of the first platform data comes after the strip flags,
+    // the cooked bool and the pixel format name
+    let end_offset = |bytes: &[u8]| (&bytes[16..24]).read_i64::<LittleEndian>().unwrap();
+    assert_eq!(end_offset(&after) - end_offset(&before), 0x100);
+    assert_eq!(end_offset(&before) - 0x1000, 24 + 0x80);
+  }
+
+  #[test]
+  fn other_layouts_are_rejected() {
+    let package = TestPackage::new(&["PF_B8G8R8A8", "PF_DXT5"]);
+    let mut curs = Cursor::new(vec![]);
+    texture(&package, 0x1000)
+      .serialize(&mut curs, package.ctx())
+      .unwrap();
+    let mut bytes = curs.into_inner();
+    // An end offset that doesn't agree with the other one
+    bytes[16] ^= 1;
+    assert!(TextureSerializer
+      .deserialize(&bytes, &package.properties(), package.ctx())
+      .is_err());
+  }
+}
@@ -27,3 +27,180 @@ impl<'a> PropertyContext<'a> {
     }
   }
 }
+
+/// A package with only names, for testing serialization without an asset
+#[cfg(test)]
+pub(crate) struct TestPackage {
+  pub summary: FileSummary,
+  pub names: Names,
+  pub imports: Imports,
+  pub exports: Exports,
+  pub patterns: StructPatterns,
+}
+
+#[cfg(test)]
+impl TestPackage {
+  pub fn new(names: &[&str]) -> Self {
+    let engine_version = || EngineVersion {
+      major: 4,
+      minor: 25,
+      patch: 0,
+      changelist: 0,
+      branch: None,
+    };
+    let summary = FileSummary {
+      tag: [0xC1, 0x83, 0x2A, 0x9E],
+      legacy_file_version: -7,
+      legacy_ue3_version: 864,
+      file_version_ue4: ue4_version::UNVERSIONED,
+      file_version_licensee_ue4: 0,
+      custom_versions: vec![],
+      total_header_size: 0,
+      folder_name: "None".to_string(),
+      package_flags: 0,
+      name_count: 0,
+      name_offset: 0,
+      localization_id: None,
+      gatherable_text_data_count: 0,
+      gatherable_text_data_offset: 0,
+      export_count: 0,
+      export_offset: 0,
+      import_count: 0,
+      import_offset: 0,
+      depends_offset: 0,
+      soft_package_references_count: 0,
+      soft_package_references_offset: 0,
+      searchable_names_offset: 0,
+      thumbnail_table_offset: 0,
+      guid: [0; 16],
+      generations: vec![],
+      saved_by_engine_version: engine_version(),
+      compatible_with_engine_version: engine_version(),
+      compression_flags: 0,
+      package_source: 0,
+      additional_packages_to_cook: vec![],
+      num_texture_allocations: 0,
+      asset_registry_data_offset: 0,
+      bulk_data_start_offset: 0,
+      world_tile_info_data_offset: 0,
+      chunk_ids: vec![],
+      preload_dependency_count: 0,
+      preload_dependency_offset: 0,
+    };
+    let mut package_names = Names { names: vec![] };
+    for name in ["None"].iter().chain(names) {
+      package_names.add(name);
+    }
+    let patterns = StructPatterns::from_file(std::path::Path::new(concat!(
+      env!("CARGO_MANIFEST_DIR"),
+      "/data/struct-patterns.json"
+    )))
+    .expect("Failed to load struct patterns");
+    Self {
+      summary,
+      names: package_names,
+      imports: Imports { objects: vec![] },
+      exports: Exports { exports: vec![] },
+      patterns,
+    }
+  }
+
+  pub fn ctx(&self) -> PropertyContext {
+    PropertyContext::new(
+      &self.summary,
+      &self.names,
+      &self.imports,
+      &self.exports,
+      &self.patterns,
+    )
+  }
+
+  /// Writes `value`, reads the bytes back with `read`, and checks that
+  /// writing what was read gives the same bytes and that `size` agrees with
+  /// what was written. Returns what was read.
+  pub fn round_trip<T>(
+    &self,
+    value: &T,
+    read: impl Fn(&mut crate::reader::ByteReader, PropertyContext) -> anyhow::Result<T>,
+    write: impl Fn(&T, &mut std::io::Cursor<Vec<u8>>, PropertyContext) -> anyhow::Result<()>,
+    size: impl Fn(&T) -> usize,
+  ) -> T {
+    let mut curs = std::io::Cursor::new(vec![]);
+    write(value, &mut curs, self.ctx()).expect("Failed to write");
+    let bytes = curs.into_inner();
+    assert_eq!(
+      size(value),
+      bytes.len(),
+      "Size doesn't match what was written"
+    );
+
+    let mut rdr = crate::reader::ByteReader::new(bytes.clone());
+    let read_value = read(&mut rdr, self.ctx()).expect("Failed to read");
+    assert_eq!(rdr.remaining_bytes(), 0, "Not everything was read");
+
+    let mut curs = std::io::Cursor::new(vec![]);
+    write(&read_value, &mut curs, self.ctx()).expect("Failed to write again");
+    assert_eq!(curs.into_inner(), bytes);
+    read_value
+  }
+  /// An asset header with this package's names and nothing else
+  pub fn header(self) -> AssetHeader {
+    let mut rdr = crate::reader::ByteReader::new(vec![]);
+    let assets = AssetRegistry::read(&mut rdr, &self.summary).expect("Empty asset registry");
+    AssetHeader {
+      summary: self.summary,
+      names: self.names,
+      gatherable_text: None,
+      imports: self.imports,
+      exports: self.exports,
+      soft_package_references: None,
+      searchable_names: None,
+      thumbnails: None,
+      assets,
+      dependencies: PreloadDependencies {
+        dependencies: vec![],
+      },
+    }
+  }
+
+  /// Properties of an export with nothing in them
+  pub fn properties(&self) -> Properties {
+    Properties {
+      properties: vec![],
+      ends_with_none: true,
+      unversioned_schema: None,
+      has_guid_flag: false,
+      object_guid: None,
+      native: None,
+      native_error: None,
+      extra: vec![],
+    }
+  }
+
+  /// Like `round_trip`, for native data read by `serializer`
+  pub fn native_round_trip(
+    &self,
+    serializer: &dyn NativeSerializer,
+    native: &dyn NativeData,
+  ) -> Box<dyn NativeData> {
+    let write = |native: &dyn NativeData| {
+      let mut curs = std::io::Cursor::new(vec![]);
+      native
+        .serialize(&mut curs, self.ctx())
+        .expect("Failed to write");
+      let bytes = curs.into_inner();
+      assert_eq!(
+        native.byte_size(),
+        bytes.len(),
+        "Size doesn't match what was written"
+      );
+      bytes
+    };
+    let bytes = write(native);
+    let read_native = serializer
+      .deserialize(&bytes, &self.properties(), self.ctx())
+      .expect("Failed to read");
+    assert_eq!(write(read_native.as_ref()), bytes);
+    read_native
+  }
+}
@@ -35,8 +35,8 @@ fn serialize_array_tag(tag: &Tag, curs: &mut Cursor<Vec<u8>>, ctx: PropertyConte
   }
 }
 
-/// Current version does not attempt to parse non-simple inner values, e.g.
-/// Struct. In this case, it will return a RawData value.
+/// Arrays of types that can't be array elements (ArrayProperty, MapProperty,
+/// SetProperty) are read as RawData.
 ///
 /// # Panics
 /// If `tag` is not Array variant.
@@ -49,43 +49,84 @@ fn deserialize_array(
   if let Tag::Array { inner_type } = tag {
     let loader = Property::get_loader_for(*inner_type).with_context(|| "Array.inner_type")?;
 
-    if loader.simple {
-      let count = rdr.read_u32::<LittleEndian>()?;
-      let inner_tag = Tag::Simple(*inner_type);
-      let mut values = vec![];
-      for i in 0..count {
-        let value = (loader.deserialize_value)(rdr, &inner_tag, max_size, ctx)
-          .with_context(|| format!("Array[{}]", i))?;
-        values.push(value);
+    match inner_type {
+      PropType::ArrayProperty | PropType::MapProperty | PropType::SetProperty => {
+        let data: Vec<u8> = read_bytes(rdr, max_size as usize)?;
+        Ok(Value::RawData { data })
       }
-      Ok(Value::Array {
-        meta_tag: None,
-        values,
-      })
-    } else if *inner_type == PropType::StructProperty {
-      let count = rdr.read_u32::<LittleEndian>()?;
-      let meta = Meta::deserialize(rdr, ctx)?.ok_or(anyhow!("Invalid meta tag for array value"))?;
-      let inner_tag = loader.deserialize_tag(rdr, ctx)?;
-      rdr.read_exact(&mut [0])?;
-      let mut values = vec![];
-      for i in 0..count {
-        let value = (loader.deserialize_value)(rdr, &inner_tag, max_size, ctx)
-          .with_context(|| format!("Array[{}]", i))?;
-        values.push(value);
+      PropType::StructProperty => {
+        let count = rdr.read_u32::<LittleEndian>()?;
+        let mut meta =
+          Meta::deserialize(rdr, ctx)?.ok_or(anyhow!("Invalid meta tag for array value"))?;
+        let inner_tag = loader.deserialize_tag(rdr, ctx)?;
+        meta
+          .deserialize_property_guid(rdr)
+          .with_context(|| "Array property guid")?;
+        let mut values = vec![];
+        for i in 0..count {
+          let value = (loader.deserialize_value)(rdr, &inner_tag, max_size, ctx)
+            .with_context(|| format!("Array[{}]", i))?;
+          values.push((inner_tag.clone(), value));
+        }
+        Ok(Value::Array {
+          meta_tag: Some((meta, inner_tag)),
+          values,
+        })
+      }
+      _ => {
+        let count = rdr.read_u32::<LittleEndian>()?;
+        // Array elements don't have an enum name to tell if ByteProperty
+        // elements are bytes or names, so figure it out from the size.
+        let byte_sized = *inner_type == PropType::ByteProperty && max_size == 4 + count as u64;
+        let mut values = vec![];
+        for i in 0..count {
+          let element = match inner_type {
+            PropType::BoolProperty => (Tag::Bool(rdr.read_u8()? != 0), Value::Bool),
+            PropType::ByteProperty if byte_sized => {
+              (Tag::Simple(*inner_type), Value::Byte(rdr.read_u8()?))
+            }
+            _ => {
+              let inner_tag = Tag::Simple(*inner_type);
+              let value = (loader.deserialize_value)(rdr, &inner_tag, max_size, ctx)
+                .with_context(|| format!("Array[{}]", i))?;
+              (inner_tag, value)
+            }
+          };
+          values.push(element);
+        }
+        Ok(Value::Array {
+          meta_tag: None,
+          values,
+        })
       }
-      Ok(Value::Array {
-        meta_tag: Some((meta, inner_tag)),
-        values,
-      })
-    } else {
-      let data: Vec<u8> = read_bytes(rdr, max_size as usize)?;
-      Ok(Value::RawData { data })
     }
   } else {
     unreachable!()
   }
 }
 
+/// Bool elements are stored as a single byte instead of in their tag.
+fn serialize_element(
+  curs: &mut Cursor<Vec<u8>>,
+  loader: &PropertyLoader,
+  tag: &Tag,
+  value: &Value,
+  ctx: PropertyContext,
+) -> Result<()> {
+  match tag {
+    Tag::Bool(value) => curs.write_u8(if *value { 1 } else { 0 })?,
+    _ => loader.serialize_value(curs, value, tag, ctx)?,
+  }
+  Ok(())
+}
+
+fn element_size(loader: &PropertyLoader, tag: &Tag, value: &Value) -> usize {
+  match tag {
+    Tag::Bool(_) => 1,
+    _ => loader.value_size(value, tag),
+  }
+}
+
 /// # Panics
 /// If `val` is not Array or RawData variant or `tag` is not Array variant.
 fn serialize_array(
@@ -100,18 +141,22 @@ fn serialize_array(
       let len = values.len();
       curs.write_u32::<LittleEndian>(len as u32)?;
 
-      let inner_tag = match meta_tag {
-        None => Tag::Simple(*inner_type),
-        Some((meta, tag)) => {
-          meta.serialize(curs, ctx)?;
-          loader.serialize_tag(curs, tag, ctx)?;
-          curs.write_all(&[0])?;
-          tag.clone()
-        }
-      };
-      for (i, value) in values.iter().enumerate() {
-        loader
-          .serialize_value(curs, value, &inner_tag, ctx)
+      if let Some((meta, tag)) = meta_tag {
+        // The inner tag's size is the size of all the elements
+        let values_size = values
+          .iter()
+          .map(|(tag, value)| element_size(loader, tag, value))
+          .sum::<usize>();
+        let meta = Meta {
+          size: values_size as u32,
+          ..meta.clone()
+        };
+        meta.serialize(curs, ctx)?;
+        loader.serialize_tag(curs, tag, ctx)?;
+        meta.serialize_property_guid(curs)?;
+      }
+      for (i, (tag, value)) in values.iter().enumerate() {
+        serialize_element(curs, loader, tag, value, ctx)
           .with_context(|| format!("Array[{}]", i))?;
       }
       Ok(())
@@ -131,19 +176,15 @@ fn serialize_array(
 fn value_size_array(value: &Value, tag: &Tag) -> usize {
   match (value, tag) {
     (Value::Array { meta_tag, values }, Tag::Array { inner_type }) => {
-      let inner_tag = meta_tag
-        .as_ref()
-        .map(|(_, tag)| tag.clone())
-        .unwrap_or_else(|| Tag::Simple(*inner_type));
       let loader = Property::get_loader_for(*inner_type).expect("Unreachable");
       let meta_tag_size = if let Some((meta, tag)) = meta_tag {
-        meta.byte_size() + loader.tag_size(tag) + 1
+        meta.byte_size() + loader.tag_size(tag) + meta.property_guid_size()
       } else {
         0
       };
       let values_size = values
         .iter()
-        .map(|v| (loader.value_size)(v, &inner_tag))
+        .map(|(tag, value)| element_size(loader, tag, value))
         .sum::<usize>();
       4 + meta_tag_size + values_size
     }
@@ -153,3 +194,62 @@ fn value_size_array(value: &Value, tag: &Tag) -> usize {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Reads an array from `data`, checking that it's written back the same
+  fn read(package: &TestPackage, inner_type: PropType, data: Vec<u8>) -> Vec<(Tag, Value)> {
+    let tag = Tag::Array { inner_type };
+    let max_size = data.len() as u64;
+    let mut rdr = ByteReader::new(data.clone());
+    let value = LOADER_ARRAY
+      .deserialize_value(&mut rdr, &tag, max_size, package.ctx())
+      .unwrap();
+    assert_eq!(LOADER_ARRAY.value_size(&value, &tag), data.len());
+    let mut curs = Cursor::new(vec![]);
+    LOADER_ARRAY
+      .serialize_value(&mut curs, &value, &tag, package.ctx())
+      .unwrap();
+    assert_eq!(curs.into_inner(), data);
+    match value {
+      Value::Array { values, .. } => values,
+      value => panic!("Read {:?}", value),
+    }
+  }
+
+  #[test]
+  fn byte_elements_are_bytes_or_names_by_size() {
+    let package = TestPackage::new(&["EColor::Red"]);
+    let bytes = read(&package, PropType::ByteProperty, vec![2, 0, 0, 0, 7, 9]);
+    assert!(matches!(
+      bytes[..],
+      [(_, Value::Byte(7)), (_, Value::Byte(9))]
+    ));
+
+    // Two elements with 8 bytes each are enum names
+    let names = read(
+      &package,
+      PropType::ByteProperty,
+      vec![2, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0],
+    );
+    match &names[..] {
+      [(_, Value::Enum(first)), (_, Value::Enum(_))] => {
+        assert_eq!(first.to_string(&package.names), "EColor::Red")
+      }
+      values => panic!("Read {:?}", values),
+    }
+  }
+
+  #[test]
+  fn bool_elements_keep_their_value_in_the_tag() {
+    let package = TestPackage::new(&[]);
+    let bools = read(&package, PropType::BoolProperty, vec![3, 0, 0, 0, 1, 0, 1]);
+    let values: Vec<_> = bools.iter().map(|(tag, _)| tag).collect();
+    assert!(matches!(
+      values[..],
+      [Tag::Bool(true), Tag::Bool(false), Tag::Bool(true)]
+    ));
+  }
+}
@@ -0,0 +1,178 @@
+use crate::asset::property::context::*;
+use crate::asset::property::loaders::PropertyLoader;
+use crate::asset::property::prop_type::*;
+use crate::asset::*;
+use crate::loader_simple;
+use crate::reader::*;
+use crate::util::*;
+
+pub const LOADER_DELEGATE: PropertyLoader = loader_simple!(
+  PropType::DelegateProperty,
+  deserialize_delegate,
+  serialize_delegate,
+  |_, _| 12,
+);
+pub const LOADER_MULTICAST_DELEGATE: PropertyLoader = loader_simple!(
+  PropType::MulticastDelegateProperty,
+  deserialize_multicast_delegate,
+  serialize_multicast_delegate,
+  value_size_multicast_delegate,
+);
+pub const LOADER_MULTICAST_INLINE_DELEGATE: PropertyLoader = loader_simple!(
+  PropType::MulticastInlineDelegateProperty,
+  deserialize_multicast_delegate,
+  serialize_multicast_delegate,
+  value_size_multicast_delegate,
+);
+pub const LOADER_MULTICAST_SPARSE_DELEGATE: PropertyLoader = loader_simple!(
+  PropType::MulticastSparseDelegateProperty,
+  deserialize_multicast_delegate,
+  serialize_multicast_delegate,
+  value_size_multicast_delegate,
+);
+
+impl Delegate {
+  fn read(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Self> {
+    let object =
+      Reference::read(rdr, ctx.imports, ctx.exports).with_context(|| "Delegate.object")?;
+    let function_name =
+      NameVariant::read(rdr, ctx.names).with_context(|| "Delegate.function_name")?;
+    Ok(Self {
+      object,
+      function_name,
+    })
+  }
+
+  fn write(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+    self
+      .object
+      .write(curs, ctx.names, ctx.imports, ctx.exports)
+      .with_context(|| "Delegate.object")?;
+    self
+      .function_name
+      .write(curs, ctx.names)
+      .with_context(|| "Delegate.function_name")?;
+    Ok(())
+  }
+}
+
+fn deserialize_delegate(
+  rdr: &mut ByteReader,
+  _: &Tag,
+  _: u64,
+  ctx: PropertyContext,
+) -> Result<Value> {
+  Ok(Value::Delegate(Delegate::read(rdr, ctx)?))
+}
+/// # Panics
+/// If `val` is not Delegate variant
+fn serialize_delegate(
+  val: &Value,
+  _: &Tag,
+  curs: &mut Cursor<Vec<u8>>,
+  ctx: PropertyContext,
+) -> Result<()> {
+  if let Value::Delegate(delegate) = val {
+    delegate.write(curs, ctx)
+  } else {
+    unreachable!()
+  }
+}
+
+fn deserialize_multicast_delegate(
+  rdr: &mut ByteReader,
+  _: &Tag,
+  _: u64,
+  ctx: PropertyContext,
+) -> Result<Value> {
+  let len = read_u32(rdr).with_context(|| "MulticastDelegate.invocation_list")?;
+  let mut invocation_list = vec![];
+  for i in 0..len {
+    let delegate = Delegate::read(rdr, ctx)
+      .with_context(|| format!("MulticastDelegate.invocation_list[{}]", i))?;
+    invocation_list.push(delegate);
+  }
+  Ok(Value::MulticastDelegate { invocation_list })
+}
+/// # Panics
+/// If `val` is not MulticastDelegate variant
+fn serialize_multicast_delegate(
+  val: &Value,
+  _: &Tag,
+  curs: &mut Cursor<Vec<u8>>,
+  ctx: PropertyContext,
+) -> Result<()> {
+  if let Value::MulticastDelegate { invocation_list } = val {
+    write_u32(curs, invocation_list.len() as u32)?;
+    for (i, delegate) in invocation_list.iter().enumerate() {
+      delegate
+        .write(curs, ctx)
+        .with_context(|| format!("MulticastDelegate.invocation_list[{}]", i))?;
+    }
+    Ok(())
+  } else {
+    unreachable!()
+  }
+}
+/// # Panics
+/// If `val` is not MulticastDelegate variant
+fn value_size_multicast_delegate(val: &Value, _: &Tag) -> usize {
+  if let Value::MulticastDelegate { invocation_list } = val {
+    4 + 12 * invocation_list.len()
+  } else {
+    unreachable!()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn invocation_lists_resolve_their_objects() {
+    let mut package = TestPackage::new(&["/Game/BP_Player", "Package", "OnDeath", "OnHit"]);
+    let name = |name| NameVariant::new(name, 0, &package.names);
+    let (package_name, class) = (name("/Game/BP_Player"), name("Package"));
+    package.imports.add(
+      package_name.clone(),
+      class,
+      package_name,
+      Reference::UObject,
+    );
+
+    #[rustfmt::skip]
+    let data = vec![
+      2, 0, 0, 0,
+      0xFF, 0xFF, 0xFF, 0xFF, 3, 0, 0, 0, 0, 0, 0, 0, // BP_Player.OnDeath
+      0, 0, 0, 0, 4, 0, 0, 0, 1, 0, 0, 0, // None.OnHit_1
+    ];
+    let tag = Tag::Simple(PropType::MulticastInlineDelegateProperty);
+    let mut rdr = ByteReader::new(data.clone());
+    let value = LOADER_MULTICAST_INLINE_DELEGATE
+      .deserialize_value(&mut rdr, &tag, data.len() as u64, package.ctx())
+      .unwrap();
+    match &value {
+      Value::MulticastDelegate { invocation_list } => {
+        let names = &package.names;
+        assert!(matches!(
+          &invocation_list[0].object,
+          Reference::Import { name, .. } if name.to_string(names) == "/Game/BP_Player"
+        ));
+        assert_eq!(invocation_list[0].function_name.to_string(names), "OnDeath");
+        assert!(matches!(invocation_list[1].object, Reference::UObject));
+        assert_eq!(invocation_list[1].function_name.to_string(names), "OnHit_1");
+      }
+      value => panic!("Read {:?}", value),
+    }
+
+    assert_eq!(
+      LOADER_MULTICAST_INLINE_DELEGATE.value_size(&value, &tag),
+      data.len()
+    );
+    let mut curs = Cursor::new(vec![]);
+    LOADER_MULTICAST_INLINE_DELEGATE
+      .serialize_value(&mut curs, &value, &tag, package.ctx())
+      .unwrap();
+    assert_eq!(curs.into_inner(), data);
+  }
+}
@@ -6,6 +6,7 @@ use crate::loader;
 use crate::reader::*;
 use crate::util::*;
 use std::io::prelude::*;
+use std::io::SeekFrom;
 
 pub const LOADER_MAP: PropertyLoader = loader!(
   [PropType::MapProperty],
@@ -46,6 +47,11 @@ fn serialize_map_tag(tag: &Tag, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext
   }
 }
 
+/// Maps don't store the types of struct keys and values, so they are read
+/// using the default struct pattern. Other keys and values can be stored
+/// differently than their tag says too (like ByteProperty enum names), so
+/// any map that can't be read to exactly its size is read as RawData instead.
+///
 /// # Panics
 /// If `tag` is not Map variant
 fn deserialize_map(
@@ -59,41 +65,98 @@ fn deserialize_map(
       key_type,
       value_type,
     } => {
-      let key_loader = Property::get_loader_for(*key_type).with_context(|| "Map.key_type")?;
-      let value_loader = Property::get_loader_for(*value_type).with_context(|| "Map.value_type")?;
-
-      if key_loader.simple && value_loader.simple {
-        let key_tag = Tag::Simple(*key_type);
-        let value_tag = Tag::Simple(*value_type);
-
-        // TODO: what to do with this?
-        let num_keys_to_remove = read_u32(rdr).with_context(|| "Map.num_keys_to_remove")?;
-
-        let num_entries = read_u32(rdr).with_context(|| "Map.num_entries")?;
-        let mut entries = vec![];
-        for i in 0..num_entries {
-          let key = key_loader
-            .deserialize_value(rdr, &key_tag, max_size, ctx)
-            .with_context(|| format!("Map.key[{}]", i))?;
-          let value = value_loader
-            .deserialize_value(rdr, &value_tag, max_size, ctx)
-            .with_context(|| format!("Map.value[{}]", i))?;
-          entries.push((key, value));
-        }
-        Ok(Value::Map {
-          num_keys_to_remove,
-          entries,
-        })
-      } else {
+      if !is_map_element(*key_type) || !is_map_element(*value_type) {
         let data: Vec<u8> =
-          read_bytes(rdr, max_size as usize).with_context(|| "Map of complex data")?;
-        Ok(Value::RawData { data })
+          read_bytes(rdr, max_size as usize).with_context(|| "Map of unsupported data")?;
+        return Ok(Value::RawData { data });
+      }
+
+      let start_pos = rdr.position();
+      match deserialize_map_entries(rdr, *key_type, *value_type, max_size, ctx) {
+        Ok(value) if rdr.position() == start_pos + max_size => Ok(value),
+        _ => {
+          rdr.seek(SeekFrom::Start(start_pos))?;
+          let data: Vec<u8> =
+            read_bytes(rdr, max_size as usize).with_context(|| "Map of complex data")?;
+          Ok(Value::RawData { data })
+        }
       }
     }
     _ => unreachable!(),
   }
 }
 
+/// Whether values of `typ` can be read as map keys/values or set elements
+pub fn is_map_element(typ: PropType) -> bool {
+  !matches!(
+    typ,
+    PropType::BoolProperty
+      | PropType::ArrayProperty
+      | PropType::MapProperty
+      | PropType::SetProperty
+  )
+}
+
+fn deserialize_map_entries(
+  rdr: &mut ByteReader,
+  key_type: PropType,
+  value_type: PropType,
+  max_size: u64,
+  ctx: PropertyContext,
+) -> Result<Value> {
+  let num_keys_to_remove = read_u32(rdr).with_context(|| "Map.num_keys_to_remove")?;
+  let mut keys_to_remove = vec![];
+  for i in 0..num_keys_to_remove {
+    let key = deserialize_map_element(rdr, key_type, max_size, ctx)
+      .with_context(|| format!("Map.keys_to_remove[{}]", i))?;
+    keys_to_remove.push(key);
+  }
+
+  let num_entries = read_u32(rdr).with_context(|| "Map.num_entries")?;
+  let mut entries = vec![];
+  for i in 0..num_entries {
+    let key = deserialize_map_element(rdr, key_type, max_size, ctx)
+      .with_context(|| format!("Map.key[{}]", i))?;
+    let value = deserialize_map_element(rdr, value_type, max_size, ctx)
+      .with_context(|| format!("Map.value[{}]", i))?;
+    entries.push((key, value));
+  }
+  Ok(Value::Map {
+    keys_to_remove,
+    entries,
+  })
+}
+
+pub fn deserialize_map_element(
+  rdr: &mut ByteReader,
+  typ: PropType,
+  max_size: u64,
+  ctx: PropertyContext,
+) -> Result<Value> {
+  if typ == PropType::StructProperty {
+    let value = ctx.patterns.deserialize_default(rdr, ctx)?;
+    Ok(Value::Struct { value })
+  } else {
+    let loader = Property::get_loader_for(typ)?;
+    loader.deserialize_value(rdr, &Tag::Simple(typ), max_size, ctx)
+  }
+}
+
+pub fn serialize_map_element(
+  curs: &mut Cursor<Vec<u8>>,
+  typ: PropType,
+  value: &Value,
+  ctx: PropertyContext,
+) -> Result<()> {
+  let loader = Property::get_loader_for(typ)?;
+  loader.serialize_value(curs, value, &Tag::Simple(typ), ctx)
+}
+
+pub fn map_element_size(typ: PropType, value: &Value) -> usize {
+  let loader = Property::get_loader_for(typ).expect("unreachable");
+  loader.value_size(value, &Tag::Simple(typ))
+}
+
 /// # Panics
 /// Panics if `val` and `tag` are not Map or RawData variants
 fn serialize_map(
@@ -105,7 +168,7 @@ fn serialize_map(
   match (val, tag) {
     (
       Value::Map {
-        num_keys_to_remove,
+        keys_to_remove,
         entries,
       },
       Tag::Map {
@@ -113,24 +176,16 @@ fn serialize_map(
         value_type,
       },
     ) => {
-      let key_loader = Property::get_loader_for(*key_type).with_context(|| "Map.key_type")?;
-      let value_loader = Property::get_loader_for(*value_type).with_context(|| "Map.value_type")?;
-
-      if !key_loader.simple || !value_loader.simple {
-        // Enforced by deserialize_map
-        unreachable!();
+      write_u32(curs, keys_to_remove.len() as u32)?;
+      for (i, key) in keys_to_remove.iter().enumerate() {
+        serialize_map_element(curs, *key_type, key, ctx)
+          .with_context(|| format!("Map.keys_to_remove[{}]", i))?;
       }
-      let key_tag = Tag::Simple(*key_type);
-      let value_tag = Tag::Simple(*value_type);
-
-      write_u32(curs, *num_keys_to_remove)?;
       write_u32(curs, entries.len() as u32)?;
       for (i, (key, value)) in entries.iter().enumerate() {
-        key_loader
-          .serialize_value(curs, key, &key_tag, ctx)
+        serialize_map_element(curs, *key_type, key, ctx)
           .with_context(|| format!("Map.key[{}]", i))?;
-        value_loader
-          .serialize_value(curs, value, &value_tag, ctx)
+        serialize_map_element(curs, *value_type, value, ctx)
           .with_context(|| format!("Map.value[{}]", i))?;
       }
       Ok(())
@@ -150,35 +205,128 @@ fn serialize_map(
 fn value_size_map(value: &Value, tag: &Tag) -> usize {
   match (value, tag) {
     (
-      Value::Map { entries, .. },
+      Value::Map {
+        keys_to_remove,
+        entries,
+      },
       Tag::Map {
         key_type,
         value_type,
       },
     ) => {
-      let key_loader = Property::get_loader_for(*key_type)
-        .with_context(|| "Map.key_type")
-        .expect("unreachable");
-      let value_loader = Property::get_loader_for(*value_type)
-        .with_context(|| "Map.value_type")
-        .expect("unreachable");
-
-      if !key_loader.simple || !value_loader.simple {
-        // Enforced by deserialize_map
-        unreachable!();
-      }
-
-      let key_tag = Tag::Simple(*key_type);
-      let value_tag = Tag::Simple(*value_type);
-
+      let keys_to_remove_size: usize = keys_to_remove
+        .iter()
+        .map(|k| map_element_size(*key_type, k))
+        .sum();
       let entries_size: usize = entries
         .iter()
-        .map(|(k, v)| key_loader.value_size(k, &key_tag) + value_loader.value_size(v, &value_tag))
+        .map(|(k, v)| map_element_size(*key_type, k) + map_element_size(*value_type, v))
         .sum();
 
-      4 + 4 + entries_size // num_keys_to_remove + num_entries + entries
+      4 + keys_to_remove_size + 4 + entries_size
     }
     (Value::RawData { data }, _) => data.len(),
     _ => unreachable!(),
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::asset::property::meta::*;
+  use crate::asset::property::struct_pattern::*;
+
+  fn round_trip(package: &TestPackage, tag: &Tag, value: &Value) -> Value {
+    package.round_trip(
+      value,
+      |rdr, ctx| {
+        let max_size = rdr.remaining_bytes() as u64;
+        LOADER_MAP.deserialize_value(rdr, tag, max_size, ctx)
+      },
+      |value, curs, ctx| LOADER_MAP.serialize_value(curs, value, tag, ctx),
+      |value| LOADER_MAP.value_size(value, tag),
+    )
+  }
+
+  fn struct_key(package: &TestPackage, id: i32) -> Value {
+    let name = NameVariant::new("Id", 0, &package.names);
+    Value::Struct {
+      value: StructValue::PropertyList {
+        properties: vec![Property {
+          meta: Meta::new(name, PropType::IntProperty, 4),
+          tag: Tag::Simple(PropType::IntProperty),
+          value: Value::Int(id),
+        }],
+        has_none: true,
+      },
+    }
+  }
+
+  #[test]
+  fn struct_keys_round_trip() {
+    let package = TestPackage::new(&["Id", "IntProperty"]);
+    let tag = Tag::Map {
+      key_type: PropType::StructProperty,
+      value_type: PropType::IntProperty,
+    };
+    let map = Value::Map {
+      keys_to_remove: vec![struct_key(&package, 1)],
+      entries: vec![
+        (struct_key(&package, 2), Value::Int(20)),
+        (struct_key(&package, 3), Value::Int(30)),
+      ],
+    };
+    match round_trip(&package, &tag, &map) {
+      Value::Map {
+        keys_to_remove,
+        entries,
+      } => {
+        assert_eq!(keys_to_remove.len(), 1);
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(entries[1].1, Value::Int(30)));
+      }
+      value => panic!("Read {:?}", value),
+    }
+  }
+
+  #[test]
+  fn maps_with_an_unexpected_layout_are_kept_as_raw_data() {
+    let package = TestPackage::new(&[]);
+    let raw_data = |tag, data| {
+      let data = Value::RawData { data };
+      matches!(round_trip(&package, &tag, &data), Value::RawData { .. })
+    };
+    // ByteProperty keys stored as single bytes instead of enum names
+    assert!(raw_data(
+      Tag::Map {
+        key_type: PropType::ByteProperty,
+        value_type: PropType::IntProperty,
+      },
+      vec![0, 0, 0, 0, 1, 0, 0, 0, 5, 7, 0, 0, 0],
+    ));
+    // Entries that read fine, but don't take up the whole size
+    assert!(raw_data(
+      Tag::Map {
+        key_type: PropType::IntProperty,
+        value_type: PropType::IntProperty,
+      },
+      vec![0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF],
+    ));
+  }
+
+  #[test]
+  fn unreadable_struct_keys_are_kept_as_raw_data() {
+    let package = TestPackage::new(&[]);
+    let tag = Tag::Map {
+      key_type: PropType::StructProperty,
+      value_type: PropType::IntProperty,
+    };
+    let data = Value::RawData {
+      data: vec![0, 0, 0, 0, 1, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF],
+    };
+    assert!(matches!(
+      round_trip(&package, &tag, &data),
+      Value::RawData { .. }
+    ));
+  }
+}
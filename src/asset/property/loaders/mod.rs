@@ -1,12 +1,16 @@
 mod array;
+mod delegate;
 mod map;
 mod other;
+mod set;
 mod simple;
 mod strct;
 
 use array::*;
+use delegate::*;
 use map::*;
 use other::*;
+use set::*;
 use simple::*;
 use strct::*;
 
@@ -18,10 +22,26 @@ use anyhow::*;
 use std::io::Cursor;
 
 pub const LOADERS: &[PropertyLoader] = &[
+  LOADER_INT8,
+  LOADER_INT16,
   LOADER_INT,
+  LOADER_INT64,
+  LOADER_UINT8,
+  LOADER_UINT16,
+  LOADER_UINT32,
+  LOADER_UINT64,
   LOADER_FLOAT,
+  LOADER_DOUBLE,
   LOADER_OBJECT,
   LOADER_SOFTOBJECT,
+  LOADER_SOFTCLASS,
+  LOADER_LAZYOBJECT,
+  LOADER_INTERFACE,
+  LOADER_DELEGATE,
+  LOADER_MULTICAST_DELEGATE,
+  LOADER_MULTICAST_INLINE_DELEGATE,
+  LOADER_MULTICAST_SPARSE_DELEGATE,
+  LOADER_FIELDPATH,
   LOADER_NAME,
   LOADER_STR,
   LOADER_TEXT,
@@ -30,6 +50,7 @@ pub const LOADERS: &[PropertyLoader] = &[
   LOADER_ARRAY,
   LOADER_STRUCT,
   LOADER_MAP,
+  LOADER_SET,
 ];
 
 type TagDeserializer = dyn Fn(&mut ByteReader, PropertyContext) -> Result<Tag>;
@@ -21,7 +21,7 @@ pub const LOADER_ENUM: PropertyLoader = loader!(
   deserialize_enum_tag,
   serialize_enum_value,
   serialize_enum_tag,
-  |_, _| 8,
+  value_size_enum,
   |_| 8,
 );
 
@@ -49,15 +49,23 @@ fn serialize_bool_tag(tag: &Tag, curs: &mut Cursor<Vec<u8>>, _ctx: PropertyConte
   }
 }
 
+/// A ByteProperty whose enum name is None is stored as a single byte.
+/// Everything else (including map keys and values, which don't have an enum
+/// name) is stored as a name.
 fn deserialize_enum_value(
   rdr: &mut ByteReader,
-  _: &Tag,
+  tag: &Tag,
   _: u64,
   ctx: PropertyContext,
 ) -> Result<Value> {
-  Ok(Value::Enum(
-    NameVariant::read(rdr, ctx.names).with_context(|| "Enum/Byte.value")?,
-  ))
+  match tag {
+    Tag::Enum(enum_name) if enum_name.to_string(ctx.names) == "None" => {
+      Ok(Value::Byte(rdr.read_u8().with_context(|| "Byte.value")?))
+    }
+    _ => Ok(Value::Enum(
+      NameVariant::read(rdr, ctx.names).with_context(|| "Enum/Byte.value")?,
+    )),
+  }
 }
 fn deserialize_enum_tag(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Tag> {
   Ok(Tag::Enum(
@@ -65,20 +73,34 @@ fn deserialize_enum_tag(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Ta
   ))
 }
 /// # Panics
-/// If val is not Enum variant.
+/// If val is not Enum or Byte variant.
 fn serialize_enum_value(
   val: &Value,
   _: &Tag,
   curs: &mut Cursor<Vec<u8>>,
   ctx: PropertyContext,
 ) -> Result<()> {
-  if let Value::Enum(val) = val {
-    val
-      .write(curs, ctx.names)
-      .with_context(|| "Enum/Byte.value")?;
-    Ok(())
-  } else {
-    unreachable!()
+  match val {
+    Value::Enum(val) => {
+      val
+        .write(curs, ctx.names)
+        .with_context(|| "Enum/Byte.value")?;
+      Ok(())
+    }
+    Value::Byte(val) => {
+      curs.write_u8(*val).with_context(|| "Byte.value")?;
+      Ok(())
+    }
+    _ => unreachable!(),
+  }
+}
+/// # Panics
+/// If val is not Enum or Byte variant.
+fn value_size_enum(val: &Value, _: &Tag) -> usize {
+  match val {
+    Value::Enum(_) => 8,
+    Value::Byte(_) => 1,
+    _ => unreachable!(),
   }
 }
 /// # Panics
@@ -93,3 +115,62 @@ fn serialize_enum_tag(tag: &Tag, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContex
     unreachable!()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use byteorder::LittleEndian;
+
+  /// A ByteProperty named Level with the given enum name and value
+  fn byte_property(package: &TestPackage, enum_name: &str, value: &[u8]) -> Vec<u8> {
+    let mut curs = Cursor::new(vec![]);
+    let name = |name| NameVariant::new(name, 0, &package.names);
+    name("Level").write(&mut curs, &package.names).unwrap();
+    name("ByteProperty")
+      .write(&mut curs, &package.names)
+      .unwrap();
+    curs.write_u32::<LittleEndian>(value.len() as u32).unwrap();
+    curs.write_u32::<LittleEndian>(0).unwrap();
+    name(enum_name).write(&mut curs, &package.names).unwrap();
+    curs.write_u8(0).unwrap();
+    curs.write_all(value).unwrap();
+    // The properties that follow
+    name("None").write(&mut curs, &package.names).unwrap();
+    curs.into_inner()
+  }
+
+  fn read(package: &TestPackage, data: Vec<u8>) -> Property {
+    let mut rdr = ByteReader::new(data.clone());
+    let property = Property::deserialize(&mut rdr, package.ctx())
+      .unwrap()
+      .unwrap();
+    assert!(Property::deserialize(&mut rdr, package.ctx())
+      .unwrap()
+      .is_none());
+    let mut curs = Cursor::new(vec![]);
+    property.serialize(&mut curs, package.ctx()).unwrap();
+    assert_eq!(curs.into_inner(), data[..data.len() - 8]);
+    property
+  }
+
+  #[test]
+  fn bytes_without_an_enum_are_a_single_byte() {
+    let package = TestPackage::new(&["Level", "ByteProperty", "ELevel", "ELevel::High"]);
+    let byte = read(&package, byte_property(&package, "None", &[7]));
+    assert!(matches!(byte.value, Value::Byte(7)));
+    // The tag, the enum name, the guid flag and the value
+    assert_eq!(byte.byte_size(), 24 + 8 + 1 + 1);
+
+    let mut curs = Cursor::new(vec![]);
+    NameVariant::new("ELevel::High", 0, &package.names)
+      .write(&mut curs, &package.names)
+      .unwrap();
+    let value = curs.into_inner();
+    let enum_value = read(&package, byte_property(&package, "ELevel", &value));
+    match &enum_value.value {
+      Value::Enum(name) => assert_eq!(name.to_string(&package.names), "ELevel::High"),
+      value => panic!("Read {:?}", value),
+    }
+    assert_eq!(enum_value.byte_size(), 24 + 8 + 1 + 8);
+  }
+}
@@ -0,0 +1,189 @@
+use crate::asset::property::context::*;
+use crate::asset::property::loaders::map::*;
+use crate::asset::property::loaders::PropertyLoader;
+use crate::asset::property::prop_type::*;
+use crate::asset::*;
+use crate::loader;
+use crate::reader::*;
+use crate::util::*;
+use std::io::prelude::*;
+use std::io::SeekFrom;
+
+pub const LOADER_SET: PropertyLoader = loader!(
+  [PropType::SetProperty],
+  deserialize_set,
+  deserialize_set_tag,
+  serialize_set,
+  serialize_set_tag,
+  value_size_set,
+  |_| 8,
+);
+
+fn deserialize_set_tag(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Tag> {
+  let inner_type = PropType::deserialize(rdr, ctx).with_context(|| "Set.inner_type")?;
+  Ok(Tag::Set { inner_type })
+}
+
+/// # Panics
+/// If `tag` is not Set variant.
+fn serialize_set_tag(tag: &Tag, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+  if let Tag::Set { inner_type } = tag {
+    inner_type
+      .serialize(curs, ctx)
+      .with_context(|| "Set.inner_type")?;
+    Ok(())
+  } else {
+    unreachable!()
+  }
+}
+
+/// Set elements are stored like map keys, so struct elements are read with
+/// the default struct pattern. Sets that can't be read to exactly their size
+/// are read as RawData instead.
+///
+/// # Panics
+/// If `tag` is not Set variant
+fn deserialize_set(
+  rdr: &mut ByteReader,
+  tag: &Tag,
+  max_size: u64,
+  ctx: PropertyContext,
+) -> Result<Value> {
+  if let Tag::Set { inner_type } = tag {
+    if !is_map_element(*inner_type) {
+      let data: Vec<u8> =
+        read_bytes(rdr, max_size as usize).with_context(|| "Set of unsupported data")?;
+      return Ok(Value::RawData { data });
+    }
+
+    let start_pos = rdr.position();
+    match deserialize_set_elements(rdr, *inner_type, max_size, ctx) {
+      Ok(value) if rdr.position() == start_pos + max_size => Ok(value),
+      _ => {
+        rdr.seek(SeekFrom::Start(start_pos))?;
+        let data: Vec<u8> =
+          read_bytes(rdr, max_size as usize).with_context(|| "Set of complex data")?;
+        Ok(Value::RawData { data })
+      }
+    }
+  } else {
+    unreachable!()
+  }
+}
+
+fn deserialize_set_elements(
+  rdr: &mut ByteReader,
+  inner_type: PropType,
+  max_size: u64,
+  ctx: PropertyContext,
+) -> Result<Value> {
+  let num_elements_to_remove = read_u32(rdr).with_context(|| "Set.num_elements_to_remove")?;
+  let mut elements_to_remove = vec![];
+  for i in 0..num_elements_to_remove {
+    let element = deserialize_map_element(rdr, inner_type, max_size, ctx)
+      .with_context(|| format!("Set.elements_to_remove[{}]", i))?;
+    elements_to_remove.push(element);
+  }
+
+  let num_elements = read_u32(rdr).with_context(|| "Set.num_elements")?;
+  let mut elements = vec![];
+  for i in 0..num_elements {
+    let element = deserialize_map_element(rdr, inner_type, max_size, ctx)
+      .with_context(|| format!("Set[{}]", i))?;
+    elements.push(element);
+  }
+  Ok(Value::Set {
+    elements_to_remove,
+    elements,
+  })
+}
+
+/// # Panics
+/// Panics if `val` and `tag` are not Set or RawData variants
+fn serialize_set(
+  val: &Value,
+  tag: &Tag,
+  curs: &mut Cursor<Vec<u8>>,
+  ctx: PropertyContext,
+) -> Result<()> {
+  match (val, tag) {
+    (
+      Value::Set {
+        elements_to_remove,
+        elements,
+      },
+      Tag::Set { inner_type },
+    ) => {
+      write_u32(curs, elements_to_remove.len() as u32)?;
+      for (i, element) in elements_to_remove.iter().enumerate() {
+        serialize_map_element(curs, *inner_type, element, ctx)
+          .with_context(|| format!("Set.elements_to_remove[{}]", i))?;
+      }
+      write_u32(curs, elements.len() as u32)?;
+      for (i, element) in elements.iter().enumerate() {
+        serialize_map_element(curs, *inner_type, element, ctx)
+          .with_context(|| format!("Set[{}]", i))?;
+      }
+      Ok(())
+    }
+    (Value::RawData { data }, _) => {
+      curs
+        .write_all(data)
+        .with_context(|| "Set of complex data")?;
+      Ok(())
+    }
+    _ => unreachable!(),
+  }
+}
+
+/// # Panics
+/// If `value` is not Set or RawData variant.
+fn value_size_set(value: &Value, tag: &Tag) -> usize {
+  match (value, tag) {
+    (
+      Value::Set {
+        elements_to_remove,
+        elements,
+      },
+      Tag::Set { inner_type },
+    ) => {
+      let elements_size = elements_to_remove
+        .iter()
+        .chain(elements.iter())
+        .map(|element| map_element_size(*inner_type, element))
+        .sum::<usize>();
+      4 + 4 + elements_size // num_elements_to_remove + num_elements + elements
+    }
+    (Value::RawData { data }, _) => data.len(),
+    _ => unreachable!(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn read(tag: &Tag, data: Vec<u8>) -> Value {
+    let package = TestPackage::new(&[]);
+    let max_size = data.len() as u64;
+    let mut rdr = ByteReader::new(data);
+    LOADER_SET
+      .deserialize_value(&mut rdr, tag, max_size, package.ctx())
+      .unwrap()
+  }
+
+  #[test]
+  fn sets_with_an_unexpected_layout_are_kept_as_raw_data() {
+    let tag = Tag::Set {
+      inner_type: PropType::IntProperty,
+    };
+    let set = read(&tag, vec![0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+    assert!(matches!(set, Value::Set { elements, .. } if elements.len() == 1));
+    // Bytes left over after the elements
+    let set = read(&tag, vec![0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0xFF]);
+    assert!(matches!(set, Value::RawData { .. }));
+    // More elements than there are bytes for
+    let set = read(&tag, vec![0, 0, 0, 0, 9, 0, 0, 0, 1, 0, 0, 0]);
+    assert!(matches!(set, Value::RawData { .. }));
+  }
+}
@@ -1,23 +1,104 @@
 use crate::asset::property::context::*;
 use crate::asset::property::loaders::PropertyLoader;
 use crate::asset::property::prop_type::*;
+use crate::asset::property::text::*;
 use crate::asset::*;
 use crate::loader_simple;
 use crate::reader::*;
 use crate::util::*;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
-pub const LOADER_INT: PropertyLoader = loader_simple!(
+/// Creates a simple loader for a numeric property type that is stored as a
+/// single value of `$size` bytes.
+macro_rules! loader_numeric {
+  ( $typ:expr , $variant:ident , $read:expr , $write:expr , $size:expr $(,)? ) => {
+    loader_simple!(
+      $typ,
+      |rdr: &mut ByteReader, _: &Tag, _: u64, _: PropertyContext| {
+        Ok(Value::$variant($read(rdr)?))
+      },
+      |val: &Value, _: &Tag, curs: &mut Cursor<Vec<u8>>, _: PropertyContext| {
+        if let Value::$variant(val) = val {
+          $write(curs, *val)?;
+          Ok(())
+        } else {
+          unreachable!()
+        }
+      },
+      |_, _| $size,
+    )
+  };
+}
+
+pub const LOADER_INT8: PropertyLoader = loader_numeric!(
+  PropType::Int8Property,
+  Int8,
+  ReadBytesExt::read_i8,
+  WriteBytesExt::write_i8,
+  1,
+);
+pub const LOADER_INT16: PropertyLoader = loader_numeric!(
+  PropType::Int16Property,
+  Int16,
+  ReadBytesExt::read_i16::<LittleEndian>,
+  WriteBytesExt::write_i16::<LittleEndian>,
+  2,
+);
+pub const LOADER_INT: PropertyLoader = loader_numeric!(
   PropType::IntProperty,
-  deserialize_int,
-  serialize_int,
-  |_, _| 4,
+  Int,
+  ReadBytesExt::read_i32::<LittleEndian>,
+  WriteBytesExt::write_i32::<LittleEndian>,
+  4,
+);
+pub const LOADER_INT64: PropertyLoader = loader_numeric!(
+  PropType::Int64Property,
+  Int64,
+  ReadBytesExt::read_i64::<LittleEndian>,
+  WriteBytesExt::write_i64::<LittleEndian>,
+  8,
+);
+pub const LOADER_UINT8: PropertyLoader = loader_numeric!(
+  PropType::UInt8Property,
+  UInt8,
+  ReadBytesExt::read_u8,
+  WriteBytesExt::write_u8,
+  1,
+);
+pub const LOADER_UINT16: PropertyLoader = loader_numeric!(
+  PropType::UInt16Property,
+  UInt16,
+  ReadBytesExt::read_u16::<LittleEndian>,
+  WriteBytesExt::write_u16::<LittleEndian>,
+  2,
 );
-pub const LOADER_FLOAT: PropertyLoader = loader_simple!(
+pub const LOADER_UINT32: PropertyLoader = loader_numeric!(
+  PropType::UInt32Property,
+  UInt32,
+  ReadBytesExt::read_u32::<LittleEndian>,
+  WriteBytesExt::write_u32::<LittleEndian>,
+  4,
+);
+pub const LOADER_UINT64: PropertyLoader = loader_numeric!(
+  PropType::UInt64Property,
+  UInt64,
+  ReadBytesExt::read_u64::<LittleEndian>,
+  WriteBytesExt::write_u64::<LittleEndian>,
+  8,
+);
+pub const LOADER_FLOAT: PropertyLoader = loader_numeric!(
   PropType::FloatProperty,
-  deserialize_float,
-  serialize_float,
-  |_, _| 4,
+  Float,
+  ReadBytesExt::read_f32::<LittleEndian>,
+  WriteBytesExt::write_f32::<LittleEndian>,
+  4,
+);
+pub const LOADER_DOUBLE: PropertyLoader = loader_numeric!(
+  PropType::DoubleProperty,
+  Double,
+  ReadBytesExt::read_f64::<LittleEndian>,
+  WriteBytesExt::write_f64::<LittleEndian>,
+  8,
 );
 pub const LOADER_OBJECT: PropertyLoader = loader_simple!(
   PropType::ObjectProperty,
@@ -31,6 +112,36 @@ pub const LOADER_SOFTOBJECT: PropertyLoader = loader_simple!(
   serialize_softobject,
   |_, _| 12,
 );
+pub const LOADER_SOFTCLASS: PropertyLoader = loader_simple!(
+  PropType::SoftClassProperty,
+  deserialize_softobject,
+  serialize_softobject,
+  |_, _| 12,
+);
+pub const LOADER_LAZYOBJECT: PropertyLoader = loader_simple!(
+  PropType::LazyObjectProperty,
+  deserialize_lazyobject,
+  serialize_lazyobject,
+  |_, _| 16,
+);
+pub const LOADER_INTERFACE: PropertyLoader = loader_simple!(
+  PropType::InterfaceProperty,
+  deserialize_interface,
+  serialize_interface,
+  |_, _| 4,
+);
+pub const LOADER_FIELDPATH: PropertyLoader = loader_simple!(
+  PropType::FieldPathProperty,
+  deserialize_fieldpath,
+  serialize_fieldpath,
+  |val, _| {
+    if let Value::FieldPath { path, .. } = val {
+      4 + 8 * path.len() + 4
+    } else {
+      unreachable!()
+    }
+  }
+);
 pub const LOADER_NAME: PropertyLoader = loader_simple!(
   PropType::NameProperty,
   deserialize_name,
@@ -56,114 +167,163 @@ pub const LOADER_TEXT: PropertyLoader = loader_simple!(
   size_of_text,
 );
 
-// TODO: figure out if boiler plate on the numeric types can be reduced further?
-
-fn deserialize_int(
+fn deserialize_object(
   rdr: &mut ByteReader,
   _tag: &Tag,
   _max_size: u64,
-  _ctx: PropertyContext,
+  ctx: PropertyContext,
 ) -> Result<Value> {
-  Ok(Value::Int(rdr.read_i32::<LittleEndian>()?))
+  Ok(Value::Object(Reference::read(
+    rdr,
+    ctx.imports,
+    ctx.exports,
+  )?))
 }
 /// # Panics
-/// If `val` is not Int variant.
-fn serialize_int(
+/// If `val` is not Object variant.
+fn serialize_object(
   val: &Value,
   _tag: &Tag,
   curs: &mut Cursor<Vec<u8>>,
-  _ctx: PropertyContext,
+  ctx: PropertyContext,
 ) -> Result<()> {
-  if let Value::Int(val) = val {
-    curs.write_i32::<LittleEndian>(*val)?;
+  if let Value::Object(dep) = val {
+    dep.write(curs, ctx.names, ctx.imports, ctx.exports)?;
     Ok(())
   } else {
     unreachable!()
   }
 }
 
-fn deserialize_float(
+fn deserialize_softobject(
   rdr: &mut ByteReader,
   _tag: &Tag,
   _max_size: u64,
-  _ctx: PropertyContext,
+  ctx: PropertyContext,
 ) -> Result<Value> {
-  Ok(Value::Float(rdr.read_f32::<LittleEndian>()?))
+  Ok(Value::SoftObject {
+    object_name: NameVariant::read(rdr, ctx.names).with_context(|| "SoftObject.object_name")?,
+    parent: Reference::read(rdr, ctx.imports, ctx.exports).with_context(|| "SoftObject.parent")?,
+  })
 }
 /// # Panics
-/// If `val` is not Float variant.
-fn serialize_float(
+/// If `val` is not SoftObject variant
+fn serialize_softobject(
   val: &Value,
   _tag: &Tag,
   curs: &mut Cursor<Vec<u8>>,
-  _ctx: PropertyContext,
+  ctx: PropertyContext,
+) -> Result<()> {
+  if let Value::SoftObject {
+    object_name,
+    parent,
+  } = val
+  {
+    object_name
+      .write(curs, ctx.names)
+      .with_context(|| "SoftObject.object_name")?;
+    parent
+      .write(curs, ctx.names, ctx.imports, ctx.exports)
+      .with_context(|| "SoftObject.parent")?;
+    Ok(())
+  } else {
+    unreachable!()
+  }
+}
+
+fn deserialize_lazyobject(
+  rdr: &mut ByteReader,
+  _: &Tag,
+  _: u64,
+  _: PropertyContext,
+) -> Result<Value> {
+  let guid = read_bytes(rdr, 16).with_context(|| "LazyObject.guid")?;
+  Ok(Value::LazyObject { guid })
+}
+/// # Panics
+/// If `val` is not LazyObject variant
+fn serialize_lazyobject(
+  val: &Value,
+  _: &Tag,
+  curs: &mut Cursor<Vec<u8>>,
+  _: PropertyContext,
 ) -> Result<()> {
-  if let Value::Float(val) = val {
-    curs.write_f32::<LittleEndian>(*val)?;
+  if let Value::LazyObject { guid } = val {
+    curs.write_all(guid)?;
     Ok(())
   } else {
     unreachable!()
   }
 }
 
-fn deserialize_object(
+fn deserialize_interface(
   rdr: &mut ByteReader,
-  _tag: &Tag,
-  _max_size: u64,
+  _: &Tag,
+  _: u64,
   ctx: PropertyContext,
 ) -> Result<Value> {
-  Ok(Value::Object(Reference::read(
-    rdr,
-    ctx.imports,
-    ctx.exports,
-  )?))
+  Ok(Value::Interface(
+    Reference::read(rdr, ctx.imports, ctx.exports).with_context(|| "Interface")?,
+  ))
 }
 /// # Panics
-/// If `val` is not Object variant.
-fn serialize_object(
+/// If `val` is not Interface variant
+fn serialize_interface(
   val: &Value,
-  _tag: &Tag,
+  _: &Tag,
   curs: &mut Cursor<Vec<u8>>,
   ctx: PropertyContext,
 ) -> Result<()> {
-  if let Value::Object(dep) = val {
-    dep.write(curs, ctx.names, ctx.imports, ctx.exports)?;
+  if let Value::Interface(dep) = val {
+    dep
+      .write(curs, ctx.names, ctx.imports, ctx.exports)
+      .with_context(|| "Interface")?;
     Ok(())
   } else {
     unreachable!()
   }
 }
 
-fn deserialize_softobject(
+fn deserialize_fieldpath(
   rdr: &mut ByteReader,
-  _tag: &Tag,
-  _max_size: u64,
+  _: &Tag,
+  _: u64,
   ctx: PropertyContext,
 ) -> Result<Value> {
-  Ok(Value::SoftObject {
-    object_name: NameVariant::read(rdr, ctx.names).with_context(|| "SoftObject.object_name")?,
-    parent: Reference::read(rdr, ctx.imports, ctx.exports).with_context(|| "SoftObject.parent")?,
+  let len = read_u32(rdr).with_context(|| "FieldPath.path")?;
+  let mut path = vec![];
+  for i in 0..len {
+    path.push(NameVariant::read(rdr, ctx.names).with_context(|| format!("FieldPath.path[{}]", i))?);
+  }
+  let resolved_owner =
+    Reference::read(rdr, ctx.imports, ctx.exports).with_context(|| "FieldPath.resolved_owner")?;
+  Ok(Value::FieldPath {
+    path,
+    resolved_owner,
   })
 }
 /// # Panics
-/// If `val` is not SoftObject variant
-fn serialize_softobject(
+/// If `val` is not FieldPath variant
+fn serialize_fieldpath(
   val: &Value,
-  _tag: &Tag,
+  _: &Tag,
   curs: &mut Cursor<Vec<u8>>,
   ctx: PropertyContext,
 ) -> Result<()> {
-  if let Value::SoftObject {
-    object_name,
-    parent,
+  if let Value::FieldPath {
+    path,
+    resolved_owner,
   } = val
   {
-    object_name
-      .write(curs, ctx.names)
-      .with_context(|| "SoftObject.object_name")?;
-    parent
+    write_u32(curs, path.len() as u32)?;
+    for (i, name) in path.iter().enumerate() {
+      name
+        .write(curs, ctx.names)
+        .with_context(|| format!("FieldPath.path[{}]", i))?;
+    }
+    resolved_owner
       .write(curs, ctx.names, ctx.imports, ctx.exports)
-      .with_context(|| "SoftObject.parent")?;
+      .with_context(|| "FieldPath.resolved_owner")?;
     Ok(())
   } else {
     unreachable!()
@@ -210,11 +370,8 @@ fn serialize_str(
   }
 }
 
-/// Not sure how TextProperty works, just read all the bytes in it for now
-fn deserialize_text(rdr: &mut ByteReader, _: &Tag, len: u64, _: PropertyContext) -> Result<Value> {
-  // let len = max_position - rdr.position();
-  let bytes: Vec<u8> = read_bytes(rdr, len as usize)?;
-  Ok(Value::Text { bytes })
+fn deserialize_text(rdr: &mut ByteReader, _: &Tag, _: u64, ctx: PropertyContext) -> Result<Value> {
+  Ok(Value::Text(Text::deserialize(rdr, ctx)?))
 }
 /// # Panics
 /// Panics if Value is not Text variant
@@ -222,11 +379,10 @@ fn serialize_text(
   val: &Value,
   _: &Tag,
   curs: &mut Cursor<Vec<u8>>,
-  _: PropertyContext,
+  ctx: PropertyContext,
 ) -> Result<()> {
-  if let Value::Text { bytes } = val {
-    curs.write_all(bytes)?;
-    Ok(())
+  if let Value::Text(text) = val {
+    text.serialize(curs, ctx)
   } else {
     unreachable!()
   }
@@ -234,8 +390,8 @@ fn serialize_text(
 /// # Panics
 /// Panics if Value is not Text variant
 fn size_of_text(val: &Value, _: &Tag) -> usize {
-  if let Value::Text { bytes } = val {
-    bytes.len()
+  if let Value::Text(text) = val {
+    text.byte_size()
   } else {
     unreachable!()
   }
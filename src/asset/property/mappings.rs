@@ -0,0 +1,393 @@
+use crate::asset::property::prop_type::*;
+use crate::asset::*;
+use crate::reader::*;
+use crate::util::*;
+use anyhow::*;
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+static MAPPINGS: OnceLock<Mappings> = OnceLock::new();
+
+const USMAP_MAGIC: u16 = 0x30C4;
+
+// EUsmapVersion
+const VERSION_PACKAGE_VERSIONING: u8 = 1;
+const VERSION_LONG_FNAME: u8 = 2;
+const VERSION_LARGE_ENUMS: u8 = 3;
+
+/// The type of a property in a mappings schema
+#[derive(Debug, Clone)]
+pub enum MappedType {
+  Simple(PropType),
+  Enum {
+    inner: Box<MappedType>,
+    enum_name: String,
+  },
+  Struct {
+    struct_name: String,
+  },
+  Array {
+    inner: Box<MappedType>,
+  },
+  Set {
+    inner: Box<MappedType>,
+  },
+  Map {
+    key: Box<MappedType>,
+    value: Box<MappedType>,
+  },
+}
+
+impl MappedType {
+  pub fn prop_type(&self) -> PropType {
+    match self {
+      Self::Simple(typ) => *typ,
+      Self::Enum { .. } => PropType::EnumProperty,
+      Self::Struct { .. } => PropType::StructProperty,
+      Self::Array { .. } => PropType::ArrayProperty,
+      Self::Set { .. } => PropType::SetProperty,
+      Self::Map { .. } => PropType::MapProperty,
+    }
+  }
+
+  fn read(rdr: &mut ByteReader, names: &[String]) -> Result<Self> {
+    let typ = rdr.read_u8()?;
+    let simple = |typ| Ok(Self::Simple(typ));
+    match typ {
+      0 => simple(PropType::ByteProperty),
+      1 => simple(PropType::BoolProperty),
+      2 => simple(PropType::IntProperty),
+      3 => simple(PropType::FloatProperty),
+      4 => simple(PropType::ObjectProperty),
+      5 => simple(PropType::NameProperty),
+      6 => simple(PropType::DelegateProperty),
+      7 => simple(PropType::DoubleProperty),
+      8 => Ok(Self::Array {
+        inner: Box::new(Self::read(rdr, names)?),
+      }),
+      9 => Ok(Self::Struct {
+        struct_name: read_mapped_name(rdr, names)?,
+      }),
+      10 => simple(PropType::StrProperty),
+      11 => simple(PropType::TextProperty),
+      12 => simple(PropType::InterfaceProperty),
+      13 => simple(PropType::MulticastDelegateProperty),
+      14 => simple(PropType::ObjectProperty), // WeakObjectProperty
+      15 => simple(PropType::LazyObjectProperty),
+      16 | 17 => simple(PropType::SoftObjectProperty), // AssetObjectProperty is the old name
+      18 => simple(PropType::UInt64Property),
+      19 => simple(PropType::UInt32Property),
+      20 => simple(PropType::UInt16Property),
+      21 => simple(PropType::Int64Property),
+      22 => simple(PropType::Int16Property),
+      23 => simple(PropType::Int8Property),
+      24 => {
+        let key = Box::new(Self::read(rdr, names)?);
+        let value = Box::new(Self::read(rdr, names)?);
+        Ok(Self::Map { key, value })
+      }
+      25 => Ok(Self::Set {
+        inner: Box::new(Self::read(rdr, names)?),
+      }),
+      26 => {
+        let inner = Box::new(Self::read(rdr, names)?);
+        let enum_name = read_mapped_name(rdr, names)?;
+        Ok(Self::Enum { inner, enum_name })
+      }
+      27 => simple(PropType::FieldPathProperty),
+      _ => bail!("Unknown mapped property type {}", typ),
+    }
+  }
+}
+
+/// A property of a struct in the mappings. Static arrays take up
+/// `array_dim` schema indices, starting at `index`.
+#[derive(Debug, Clone)]
+pub struct PropertySchema {
+  pub index: u16,
+  pub array_dim: u8,
+  pub name: String,
+  pub typ: MappedType,
+}
+
+#[derive(Debug, Clone)]
+pub struct StructSchema {
+  pub name: String,
+  pub super_type: Option<String>,
+  // Number of schema indices used by this struct, not counting the super type
+  pub property_count: u16,
+  pub properties: Vec<PropertySchema>,
+}
+
+/// Property layouts of the engine and game classes, loaded from a .usmap
+/// file. Unversioned properties are stored as indices into these layouts.
+#[derive(Debug)]
+pub struct Mappings {
+  pub enums: HashMap<String, Vec<String>>,
+  pub structs: HashMap<String, StructSchema>,
+}
+
+fn read_mapped_name(rdr: &mut ByteReader, names: &[String]) -> Result<String> {
+  let index = read_u32(rdr)?;
+  names
+    .get(index as usize)
+    .cloned()
+    .with_context(|| format!("Mapped name index {} is out of range", index))
+}
+
+impl Mappings {
+  pub fn read(data: Vec<u8>) -> Result<Self> {
+    let mut rdr = ByteReader::new(data);
+    let magic = rdr.read_u16::<LittleEndian>()?;
+    if magic != USMAP_MAGIC {
+      bail!("Invalid usmap magic {:#06X}", magic);
+    }
+    let version = rdr.read_u8()?;
+    if version > VERSION_LARGE_ENUMS {
+      bail!("Unsupported usmap version {}", version);
+    }
+    if version >= VERSION_PACKAGE_VERSIONING && read_bool(&mut rdr)? {
+      // The package version and custom versions the mappings were made for,
+      // then the changelist
+      rdr.read_i32::<LittleEndian>()?;
+      rdr.read_i32::<LittleEndian>()?;
+      let custom_version_count = read_u32(&mut rdr)?;
+      read_bytes::<_, Vec<u8>>(&mut rdr, custom_version_count as usize * 20)?;
+      read_u32(&mut rdr)?;
+    }
+
+    let compression = rdr.read_u8()?;
+    let compressed_size = read_u32(&mut rdr)?;
+    let decompressed_size = read_u32(&mut rdr)?;
+    if compression != 0 {
+      bail!(
+        "Compressed usmap files (method {}) are not supported",
+        compression
+      );
+    }
+    if compressed_size != decompressed_size || compressed_size as usize != rdr.remaining_bytes() {
+      bail!(
+        "Usmap data size {:#X} doesn't match the rest of the file ({:#X})",
+        compressed_size,
+        rdr.remaining_bytes()
+      );
+    }
+
+    let name_count = read_u32(&mut rdr)?;
+    let mut names = vec![];
+    for i in 0..name_count {
+      let len = if version >= VERSION_LONG_FNAME {
+        rdr.read_u16::<LittleEndian>()? as usize
+      } else {
+        rdr.read_u8()? as usize
+      };
+      let bytes = read_bytes(&mut rdr, len).with_context(|| format!("Mapped name {}", i))?;
+      names.push(String::from_utf8(bytes).with_context(|| format!("Mapped name {}", i))?);
+    }
+
+    let enum_count = read_u32(&mut rdr)?;
+    let mut enums = HashMap::new();
+    for _ in 0..enum_count {
+      let enum_name = read_mapped_name(&mut rdr, &names)?;
+      let entry_count = if version >= VERSION_LARGE_ENUMS {
+        rdr.read_u16::<LittleEndian>()? as usize
+      } else {
+        rdr.read_u8()? as usize
+      };
+      let mut entries = vec![];
+      for _ in 0..entry_count {
+        entries.push(read_mapped_name(&mut rdr, &names)?);
+      }
+      enums.insert(enum_name, entries);
+    }
+
+    let struct_count = read_u32(&mut rdr)?;
+    let mut structs = HashMap::new();
+    for _ in 0..struct_count {
+      let name = read_mapped_name(&mut rdr, &names)?;
+      let super_index = read_u32(&mut rdr)?;
+      let super_type = if super_index == u32::MAX {
+        None
+      } else {
+        Some(
+          names
+            .get(super_index as usize)
+            .cloned()
+            .with_context(|| format!("Super type of {}", name))?,
+        )
+      };
+      let property_count = rdr.read_u16::<LittleEndian>()?;
+      let serializable_count = rdr.read_u16::<LittleEndian>()?;
+      let mut properties = vec![];
+      for _ in 0..serializable_count {
+        let index = rdr.read_u16::<LittleEndian>()?;
+        let array_dim = rdr.read_u8()?;
+        let prop_name = read_mapped_name(&mut rdr, &names)?;
+        let typ = MappedType::read(&mut rdr, &names)
+          .with_context(|| format!("Type of {}.{}", name, prop_name))?;
+        properties.push(PropertySchema {
+          index,
+          array_dim,
+          name: prop_name,
+          typ,
+        });
+      }
+      let schema = StructSchema {
+        name: name.clone(),
+        super_type,
+        property_count,
+        properties,
+      };
+      structs.insert(name, schema);
+    }
+
+    Ok(Self { enums, structs })
+  }
+
+  pub fn from_file(fp: &Path) -> Result<Self> {
+    let data =
+      std::fs::read(fp).with_context(|| format!("Failed to read mappings from {:?}", fp))?;
+    Self::read(data).with_context(|| format!("Failed to parse mappings {:?}", fp))
+  }
+
+  /// Loads mappings from fp into static instance, get it with `Mappings::get()`
+  pub fn load(fp: &Path) -> Result<()> {
+    let instance = Self::from_file(fp)?;
+    MAPPINGS
+      .set(instance)
+      .map_err(|_| anyhow!("Mappings are already loaded"))
+  }
+
+  pub fn get() -> Option<&'static Self> {
+    MAPPINGS.get()
+  }
+
+  pub fn struct_schema(&self, struct_name: &str) -> Result<&StructSchema> {
+    self
+      .structs
+      .get(struct_name)
+      .with_context(|| format!("No mappings for struct {}", struct_name))
+  }
+
+  /// Number of schema indices used by a struct, including its super types
+  pub fn schema_size(&self, struct_name: &str) -> Result<usize> {
+    let schema = self.struct_schema(struct_name)?;
+    let super_size = match &schema.super_type {
+      Some(super_type) => self.schema_size(super_type)?,
+      None => 0,
+    };
+    Ok(super_size + schema.property_count as usize)
+  }
+
+  /// The property at a schema index of a struct, and its static array index
+  pub fn property_at(&self, struct_name: &str, index: usize) -> Result<(&PropertySchema, u32)> {
+    let schema = self.struct_schema(struct_name)?;
+    let super_size = match &schema.super_type {
+      Some(super_type) => self.schema_size(super_type)?,
+      None => 0,
+    };
+    if index < super_size {
+      let super_type = schema.super_type.as_ref().unwrap();
+      return self.property_at(super_type, index);
+    }
+    let local = index - super_size;
+    schema
+      .properties
+      .iter()
+      .find(|prop| {
+        let start = prop.index as usize;
+        start <= local && local < start + prop.array_dim as usize
+      })
+      .map(|prop| (prop, (local - prop.index as usize) as u32))
+      .with_context(|| format!("No property at schema index {} of {}", index, struct_name))
+  }
+
+  /// The schema index of a property of a struct
+  pub fn index_of(&self, struct_name: &str, name: &str, array_index: u32) -> Result<usize> {
+    let schema = self.struct_schema(struct_name)?;
+    let super_size = match &schema.super_type {
+      Some(super_type) => self.schema_size(super_type)?,
+      None => 0,
+    };
+    if let Some(prop) = schema.properties.iter().find(|prop| prop.name == name) {
+      if array_index >= prop.array_dim as u32 {
+        bail!(
+          "Array index {} of {}.{} is out of range (dimension {})",
+          array_index,
+          struct_name,
+          name,
+          prop.array_dim
+        );
+      }
+      return Ok(super_size + prop.index as usize + array_index as usize);
+    }
+    match &schema.super_type {
+      Some(super_type) => self.index_of(super_type, name, array_index),
+      None => bail!("No property {} in mappings of {}", name, struct_name),
+    }
+  }
+
+  /// The name of an enum entry as it is stored in a tagged EnumProperty
+  pub fn enum_value_name(enum_name: &str, entry: &str) -> String {
+    if entry.contains("::") {
+      entry.to_string()
+    } else {
+      format!("{}::{}", enum_name, entry)
+    }
+  }
+
+  /// Adds every name that reading unversioned properties of `struct_name`
+  /// can produce to `names`, as transient names.
+  pub fn add_transient_names(&self, struct_name: &str, names: &mut Names) {
+    names.add_transient("None");
+    let mut visited = HashSet::new();
+    self.add_struct_names(struct_name, names, &mut visited);
+  }
+
+  fn add_struct_names<'a>(
+    &'a self,
+    struct_name: &'a str,
+    names: &mut Names,
+    visited: &mut HashSet<&'a str>,
+  ) {
+    if !visited.insert(struct_name) {
+      return;
+    }
+    names.add_transient(struct_name);
+    if let Some(schema) = self.structs.get(struct_name) {
+      if let Some(super_type) = &schema.super_type {
+        self.add_struct_names(super_type, names, visited);
+      }
+      for prop in schema.properties.iter() {
+        names.add_transient(&prop.name);
+        self.add_type_names(&prop.typ, names, visited);
+      }
+    }
+  }
+
+  fn add_type_names<'a>(
+    &'a self,
+    typ: &'a MappedType,
+    names: &mut Names,
+    visited: &mut HashSet<&'a str>,
+  ) {
+    match typ {
+      MappedType::Simple(_) => {}
+      MappedType::Enum { enum_name, .. } => {
+        names.add_transient(enum_name);
+        for entry in self.enums.get(enum_name).into_iter().flatten() {
+          names.add_transient(&Self::enum_value_name(enum_name, entry));
+        }
+      }
+      MappedType::Struct { struct_name } => self.add_struct_names(struct_name, names, visited),
+      MappedType::Array { inner } | MappedType::Set { inner } => {
+        self.add_type_names(inner, names, visited)
+      }
+      MappedType::Map { key, value } => {
+        self.add_type_names(key, names, visited);
+        self.add_type_names(value, names, visited);
+      }
+    }
+  }
+}
@@ -2,18 +2,42 @@ use crate::asset::property::context::*;
 use crate::asset::property::prop_type::*;
 use crate::asset::*;
 use crate::reader::*;
+use crate::util::*;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::prelude::*;
 
 #[derive(Debug, Clone)]
 pub struct Meta {
   pub name: NameVariant,
   pub typ: PropType,
-  pub size: u64,
+  pub size: u32,
+  // Position in a static array, e.g. `float Damage[3]` is stored as three
+  // properties named Damage with array indices 0, 1, and 2.
+  pub array_index: u32,
+  // Only present when the HasPropertyGuid byte after the tag is set
+  pub property_guid: Option<[u8; 16]>,
+  // Zero mask bit of a property read from unversioned data, written back as
+  // it was unless the value changed
+  pub unversioned_zero: Option<bool>,
 }
 
 impl Meta {
-  pub fn new(name: NameVariant, typ: PropType, size: u64) -> Self {
-    Self { name, typ, size }
+  pub fn new(name: NameVariant, typ: PropType, size: u32) -> Self {
+    Self {
+      name,
+      typ,
+      size,
+      array_index: 0,
+      property_guid: None,
+      unversioned_zero: None,
+    }
+  }
+
+  pub fn with_array_index(self, array_index: u32) -> Self {
+    Self {
+      array_index,
+      ..self
+    }
   }
 
   pub fn deserialize(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Option<Self>> {
@@ -23,18 +47,108 @@ impl Meta {
     }
 
     let typ = PropType::deserialize(rdr, ctx)?;
-    let size = rdr.read_u64::<LittleEndian>()?;
-    Ok(Some(Self { name, typ, size }))
+    let size = rdr.read_u32::<LittleEndian>()?;
+    let array_index = rdr.read_u32::<LittleEndian>()?;
+    Ok(Some(Self {
+      name,
+      typ,
+      size,
+      array_index,
+      property_guid: None,
+      unversioned_zero: None,
+    }))
   }
 
   pub fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
     self.name.write(curs, ctx.names)?;
     self.typ.serialize(curs, ctx)?;
-    curs.write_u64::<LittleEndian>(self.size)?;
+    curs.write_u32::<LittleEndian>(self.size)?;
+    curs.write_u32::<LittleEndian>(self.array_index)?;
     Ok(())
   }
 
   pub fn byte_size(&self) -> usize {
     24
   }
+
+  /// Read the HasPropertyGuid flag and property GUID that follow the tag
+  pub fn deserialize_property_guid(&mut self, rdr: &mut ByteReader) -> Result<()> {
+    let has_property_guid = rdr.read_u8()?;
+    self.property_guid = match has_property_guid {
+      0 => None,
+      1 => Some(read_bytes(rdr, 16)?),
+      _ => bail!("Invalid HasPropertyGuid value {}", has_property_guid),
+    };
+    Ok(())
+  }
+
+  pub fn serialize_property_guid(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    match &self.property_guid {
+      Some(guid) => {
+        curs.write_u8(1)?;
+        curs.write_all(guid)?;
+      }
+      None => curs.write_u8(0)?,
+    }
+    Ok(())
+  }
+
+  pub fn property_guid_size(&self) -> usize {
+    match self.property_guid {
+      Some(_) => 17,
+      None => 1,
+    }
+  }
+
+  /// The property name, including the array index if it is in a static array
+  pub fn indexed_name(&self, names: &Names) -> String {
+    if self.array_index == 0 {
+      self.name.to_string(names)
+    } else {
+      format!("{}[{}]", self.name.to_string(names), self.array_index)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// An IntProperty named Count, with `guid` after its tag
+  fn int_property(package: &TestPackage, guid: &[u8]) -> Vec<u8> {
+    let mut curs = Cursor::new(vec![]);
+    let meta = Meta::new(
+      NameVariant::new("Count", 0, &package.names),
+      PropType::IntProperty,
+      4,
+    );
+    meta.serialize(&mut curs, package.ctx()).unwrap();
+    curs.write_all(guid).unwrap();
+    curs.write_i32::<LittleEndian>(5).unwrap();
+    curs.into_inner()
+  }
+
+  #[test]
+  fn property_guids_follow_the_tag() {
+    let package = TestPackage::new(&["Count", "IntProperty"]);
+    let guid: Vec<u8> = (1..=16).collect();
+    let data = int_property(&package, &[&[1], &guid[..]].concat());
+    let mut rdr = ByteReader::new(data.clone());
+    let property = Property::deserialize(&mut rdr, package.ctx())
+      .unwrap()
+      .unwrap();
+    assert_eq!(
+      property.meta.property_guid.as_ref().map(|g| &g[..]),
+      Some(&guid[..])
+    );
+    assert!(matches!(property.value, Value::Int(5)));
+    assert_eq!(property.byte_size(), data.len());
+    let mut curs = Cursor::new(vec![]);
+    property.serialize(&mut curs, package.ctx()).unwrap();
+    assert_eq!(curs.into_inner(), data);
+
+    let data = int_property(&package, &[2]);
+    let err = Property::deserialize(&mut ByteReader::new(data), package.ctx()).unwrap_err();
+    assert!(format!("{:#}", err).contains("Invalid HasPropertyGuid value 2"));
+  }
 }
@@ -4,17 +4,25 @@ use crate::reader::*;
 use crate::util::*;
 use anyhow::*;
 pub use context::PropertyContext;
+#[cfg(test)]
+pub(crate) use context::TestPackage;
 use std::io::prelude::*;
+use std::io::SeekFrom;
 
 mod context;
 mod loaders;
+pub mod mappings;
 pub mod meta;
 pub mod prop_type;
 pub mod struct_pattern;
+pub mod text;
+mod unversioned;
 
 use loaders::{PropertyLoader, LOADERS};
+use mappings::*;
 use meta::*;
 use prop_type::*;
+use text::*;
 
 /*====================
 // Property Components
@@ -22,25 +30,44 @@ use prop_type::*;
 
 #[derive(Debug, Clone)]
 pub enum Value {
+  Int8(i8),
+  Int16(i16),
   Int(i32),
+  Int64(i64),
+  UInt8(u8),
+  UInt16(u16),
+  UInt32(u32),
+  UInt64(u64),
   Float(f32),
+  Double(f64),
   Object(Reference),
   // TODO: unk1 might be the length of a string that is stored in the SoftObjectProperty
   // Find example of this happening?
   SoftObject {
     object_name: NameVariant,
     parent: Reference,
+  }, // For SoftObjectProperty and SoftClassProperty
+  LazyObject {
+    guid: [u8; 16],
+  },
+  Interface(Reference),
+  Delegate(Delegate),
+  MulticastDelegate {
+    invocation_list: Vec<Delegate>,
+  },
+  FieldPath {
+    path: Vec<NameVariant>,
+    resolved_owner: Reference,
   },
   Name(NameVariant),
   Str(String),
-  Text {
-    bytes: Vec<u8>, // TODO: how to actually represent TextProperty?
-  },
+  Text(Text),
   Bool,
   Enum(NameVariant), // For ByteProperty and EnumProperty, TODO better name?
+  Byte(u8),          // For ByteProperty without an enum
   Array {
     meta_tag: Option<(Meta, Tag)>,
-    values: Vec<Value>,
+    values: Vec<(Tag, Value)>, // Each value with the tag it is serialized with
   },
   Struct {
     value: StructValue,
@@ -49,11 +76,22 @@ pub enum Value {
     data: Vec<u8>,
   },
   Map {
-    num_keys_to_remove: u32, // TODO: this may be the size of a second map, not sure though.
+    keys_to_remove: Vec<Value>,
     entries: Vec<(Value, Value)>, // assoc. list instead of hashmap since value isn't hashable
+  },
+  Set {
+    elements_to_remove: Vec<Value>,
+    elements: Vec<Value>,
   }, // etc.
 }
 
+/// A function bound on an object, used by the delegate properties
+#[derive(Debug, Clone)]
+pub struct Delegate {
+  pub object: Reference,
+  pub function_name: NameVariant,
+}
+
 #[derive(Debug, Clone)]
 pub enum Tag {
   Simple(PropType),
@@ -70,6 +108,9 @@ pub enum Tag {
     key_type: PropType,
     value_type: PropType,
   },
+  Set {
+    inner_type: PropType,
+  },
 }
 
 #[derive(Debug, Clone)]
@@ -88,20 +129,24 @@ impl Property {
   }
 
   pub fn deserialize(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Option<Self>> {
-    if let Some(meta) = Meta::deserialize(rdr, ctx)? {
+    if let Some(mut meta) = Meta::deserialize(rdr, ctx)? {
       let loader = Self::get_loader_for(meta.typ)?;
 
       // println!("Entering tag for {} at {:#X}", meta.typ, rdr.position());
       let tag = loader.deserialize_tag(rdr, ctx)?;
 
-      // Read 0x00 between tag and value
-      rdr.read_exact(&mut [0])?;
+      meta
+        .deserialize_property_guid(rdr)
+        .with_context(|| "Property guid")?;
 
       // println!("Entering value for {} at {:#X}", meta.typ, rdr.position());
 
+      // Always unlimit, so a caller that recovers from an error (like a map
+      // falling back to RawData) doesn't keep a stale limit
       rdr.limit(meta.size as usize);
-      let value = loader.deserialize_value(rdr, &tag, meta.size, ctx)?;
+      let value = loader.deserialize_value(rdr, &tag, meta.size as u64, ctx);
       rdr.unlimit();
+      let value = value?;
 
       // println!("Exiting value for {} at {:#X} \n", meta.typ, rdr.position());
 
@@ -116,11 +161,14 @@ impl Property {
 
     // Compute real value size
     let new_size = loader.value_size(&self.value, &self.tag);
-    let meta = Meta::new(self.meta.name.clone(), self.meta.typ, new_size as u64);
+    let meta = Meta {
+      size: new_size as u32,
+      ..self.meta.clone()
+    };
     meta.serialize(curs, ctx)?;
 
     loader.serialize_tag(curs, &self.tag, ctx)?;
-    curs.write_all(&[0])?;
+    self.meta.serialize_property_guid(curs)?;
     loader.serialize_value(curs, &self.value, &self.tag, ctx)?;
     Ok(())
   }
@@ -130,8 +178,8 @@ impl Property {
     let meta_size = self.meta.byte_size();
     let tag_size = loader.tag_size(&self.tag);
     let value_size = loader.value_size(&self.value, &self.tag);
-    // Include 0x00 byte separator between tag and value
-    meta_size + tag_size + 1 + value_size
+    let guid_size = self.meta.property_guid_size();
+    meta_size + tag_size + guid_size + value_size
   }
 }
 
@@ -140,6 +188,18 @@ impl Property {
 pub struct Properties {
   pub properties: Vec<Property>,
   ends_with_none: bool,
+  // Name of the class in the mappings if the properties are unversioned
+  unversioned_schema: Option<String>,
+  // Objects other than templates have a bool saying if they have an object
+  // guid after their properties
+  has_guid_flag: bool,
+  pub object_guid: Option<[u8; 16]>,
+  // Class specific native data, if a serializer is registered for the class
+  pub native: Option<Box<dyn NativeData>>,
+  // Why the registered serializer couldn't read the native data, which is
+  // then kept as unknown trailing data
+  pub native_error: Option<Error>,
+  // Class specific native data that isn't understood
   extra: Vec<u8>,
 }
 
@@ -155,10 +215,30 @@ impl Properties {
     }
     let mut ends_with_none = false;
     let mut properties = vec![];
+    let mut unversioned_schema = None;
 
     // Read all properties until None prop or past end of this export
     let end_pos = export.export_file_offset + export.serial_size;
-    'proploop: while rdr.position() < end_pos {
+    if ctx.summary.has_unversioned_properties() {
+      let mappings = Mappings::get().ok_or_else(|| {
+        anyhow!(
+          "{} has unversioned properties, but no mappings are loaded",
+          export.object_name.to_string(ctx.names)
+        )
+      })?;
+      let class_name = export.class_name(ctx.names);
+      rdr.limit(export.serial_size as usize);
+      let result = unversioned::deserialize_properties(rdr, &class_name, mappings, ctx);
+      rdr.unlimit();
+      properties = result.with_context(|| {
+        format!(
+          "Unversioned properties of {}",
+          export.object_name.to_string(ctx.names)
+        )
+      })?;
+      unversioned_schema = Some(class_name);
+    }
+    'proploop: while unversioned_schema.is_none() && rdr.position() < end_pos {
       let start_pos = rdr.position();
 
       if let Some(prop) = Property::deserialize(rdr, ctx).with_context(|| {
@@ -186,7 +266,29 @@ impl Properties {
       );
     }
 
-    // Read any bytes that were left over. This happens sometimes, and I'm not sure why.
+    let mut has_guid_flag = false;
+    let mut object_guid = None;
+    let remaining = export.serial_size - num_bytes_read;
+    if (ends_with_none || unversioned_schema.is_some())
+      && !ctx.exports.is_template(export)
+      && remaining >= 4
+    {
+      let guid_start = rdr.position();
+      match read_u32(rdr)? {
+        0 => has_guid_flag = true,
+        1 if remaining >= 20 => {
+          has_guid_flag = true;
+          object_guid = Some(read_bytes(rdr, 16)?);
+        }
+        // Not a guid flag, so it's part of the native data
+        _ => {
+          rdr.seek(SeekFrom::Start(guid_start))?;
+        }
+      }
+    }
+    let num_bytes_read = rdr.position() - start_pos;
+
+    // Anything left over is native data written by the export's class
     let extra = if num_bytes_read < export.serial_size {
       let remaining = export.serial_size as usize - num_bytes_read as usize;
       read_bytes(rdr, remaining).with_context(|| {
@@ -202,16 +304,17 @@ impl Properties {
     Ok(Self {
       properties,
       ends_with_none,
+      unversioned_schema,
+      has_guid_flag,
+      object_guid,
+      native: None,
+      native_error: None,
       extra,
     })
   }
 
   pub fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
-    for property in &self.properties {
-      property
-        .serialize(curs, ctx)
-        .with_context(|| "While serializing struct")?;
-    }
+    self.serialize_properties(curs, ctx)?;
 
     if self.ends_with_none {
       let none: NameVariant = NameVariant::new("None", 0, ctx.names);
@@ -220,15 +323,95 @@ impl Properties {
         .with_context(|| "Expected None in names")?;
     }
 
+    if self.has_guid_flag {
+      match &self.object_guid {
+        Some(guid) => {
+          write_u32(curs, 1)?;
+          curs.write_all(guid)?;
+        }
+        None => write_u32(curs, 0)?,
+      }
+    }
+
+    if let Some(native) = &self.native {
+      native
+        .serialize(curs, ctx)
+        .with_context(|| "While serializing native data")?;
+    }
     curs.write_all(&self.extra[..])?;
 
     Ok(())
   }
 
-  pub fn byte_size(&self) -> usize {
-    let props_size = self.properties.iter().map(|p| p.byte_size()).sum::<usize>();
+  fn serialize_properties(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+    if let Some(schema) = &self.unversioned_schema {
+      let mappings = Mappings::get()
+        .ok_or_else(|| anyhow!("Mappings are needed to write unversioned properties"))?;
+      return unversioned::serialize_properties(curs, &self.properties, schema, mappings, ctx)
+        .with_context(|| format!("Unversioned properties of {}", schema));
+    }
+    for property in &self.properties {
+      property
+        .serialize(curs, ctx)
+        .with_context(|| "While serializing struct")?;
+    }
+    Ok(())
+  }
+
+  /// Whether the properties are stored without tags, as indices into the
+  /// loaded mappings
+  pub fn is_unversioned(&self) -> bool {
+    self.unversioned_schema.is_some()
+  }
+
+  pub fn native_as<T: NativeData>(&self) -> Option<&T> {
+    self
+      .native
+      .as_ref()
+      .and_then(|native| native.as_any().downcast_ref())
+  }
+
+  pub fn native_as_mut<T: NativeData>(&mut self) -> Option<&mut T> {
+    self
+      .native
+      .as_mut()
+      .and_then(|native| native.as_any_mut().downcast_mut())
+  }
+
+  pub(crate) fn take_unknown_trailing_data(&mut self) -> Vec<u8> {
+    std::mem::take(&mut self.extra)
+  }
+
+  pub(crate) fn unknown_trailing_data_mut(&mut self) -> &mut Vec<u8> {
+    &mut self.extra
+  }
+
+  /// Data after the properties (and object guid) that isn't understood. This
+  /// is usually native data of the export's class.
+  pub fn unknown_trailing_data(&self) -> &[u8] {
+    &self.extra
+  }
+
+  pub fn byte_size(&self, ctx: PropertyContext) -> usize {
+    let props_size = if self.unversioned_schema.is_some() {
+      // The header depends on every value, so it's easiest to write it out.
+      // Errors show up again when the properties are actually written.
+      let mut curs = Cursor::new(vec![]);
+      match self.serialize_properties(&mut curs, ctx) {
+        Ok(()) => curs.into_inner().len(),
+        Err(_) => 0,
+      }
+    } else {
+      self.properties.iter().map(|p| p.byte_size()).sum::<usize>()
+    };
     let none_size = if self.ends_with_none { 8 } else { 0 };
+    let guid_size = match (self.has_guid_flag, self.object_guid) {
+      (false, _) => 0,
+      (true, None) => 4,
+      (true, Some(_)) => 20,
+    };
+    let native_size = self.native.as_ref().map(|n| n.byte_size()).unwrap_or(0);
     let extra_size = self.extra.len();
-    props_size + none_size + extra_size
+    props_size + none_size + guid_size + native_size + extra_size
   }
 }
@@ -7,11 +7,26 @@ use strum_macros::{Display, EnumString};
 
 #[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
 pub enum PropType {
+  Int8Property,
+  Int16Property,
   IntProperty,
+  Int64Property,
   UInt8Property,
+  UInt16Property,
+  UInt32Property,
+  UInt64Property,
   FloatProperty,
+  DoubleProperty,
   ObjectProperty,
   SoftObjectProperty,
+  SoftClassProperty,
+  LazyObjectProperty,
+  InterfaceProperty,
+  DelegateProperty,
+  MulticastDelegateProperty,
+  MulticastInlineDelegateProperty,
+  MulticastSparseDelegateProperty,
+  FieldPathProperty,
   NameProperty,
   StrProperty,
   TextProperty,
@@ -22,14 +37,30 @@ pub enum PropType {
   ArrayProperty,
   StructProperty,
   MapProperty,
+  SetProperty,
 }
 
-pub const ALL_PROP_TYPES: [PropType; 14] = [
+pub const ALL_PROP_TYPES: [PropType; 30] = [
+  PropType::Int8Property,
+  PropType::Int16Property,
   PropType::IntProperty,
+  PropType::Int64Property,
   PropType::UInt8Property,
+  PropType::UInt16Property,
+  PropType::UInt32Property,
+  PropType::UInt64Property,
   PropType::FloatProperty,
+  PropType::DoubleProperty,
   PropType::ObjectProperty,
   PropType::SoftObjectProperty,
+  PropType::SoftClassProperty,
+  PropType::LazyObjectProperty,
+  PropType::InterfaceProperty,
+  PropType::DelegateProperty,
+  PropType::MulticastDelegateProperty,
+  PropType::MulticastInlineDelegateProperty,
+  PropType::MulticastSparseDelegateProperty,
+  PropType::FieldPathProperty,
   PropType::NameProperty,
   PropType::StrProperty,
   PropType::TextProperty,
@@ -39,6 +70,7 @@ pub const ALL_PROP_TYPES: [PropType; 14] = [
   PropType::ArrayProperty,
   PropType::StructProperty,
   PropType::MapProperty,
+  PropType::SetProperty,
 ];
 
 impl PropType {
@@ -44,6 +44,20 @@ enum StructPattern {
 }
 
 impl StructPattern {
+  /// Size of a struct read with this pattern, if it has a fixed size
+  fn fixed_size(&self) -> Option<usize> {
+    match self {
+      Self::PropertyList => None,
+      Self::Binary { size } => Some(*size),
+      Self::BinaryProperties { properties } => properties
+        .iter()
+        .map(|entry| entry.pattern.fixed_size())
+        .sum(),
+      Self::Int { size } | Self::UInt { size } | Self::Floating { size } => Some(*size as usize),
+      Self::Enum { .. } => Some(1),
+    }
+  }
+
   fn deserialize(&self, rdr: &mut ByteReader, ctx: PropertyContext) -> Result<StructValue> {
     match self {
       Self::PropertyList => {
@@ -278,6 +292,27 @@ impl StructPatterns {
       .with_context(|| format!("For struct type {} (default = {})", struct_type, is_default))
   }
 
+  /// Size of a struct type that is stored as binary data instead of a
+  /// property list, if it has a pattern.
+  pub fn binary_size(&self, struct_type: &str) -> Option<usize> {
+    self
+      .patterns
+      .get(struct_type)
+      .and_then(|pattern| pattern.fixed_size())
+  }
+
+  /// Deserializes a struct of unknown type with the default pattern
+  pub fn deserialize_default(
+    &self,
+    rdr: &mut ByteReader,
+    ctx: PropertyContext,
+  ) -> Result<StructValue> {
+    self
+      .default
+      .deserialize(rdr, ctx)
+      .with_context(|| "For struct of unknown type")
+  }
+
   pub fn serialize(
     &self,
     value: &StructValue,
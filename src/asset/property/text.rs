@@ -0,0 +1,832 @@
+use crate::asset::property::context::*;
+use crate::asset::*;
+use crate::reader::*;
+use crate::util::*;
+use anyhow::*;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::prelude::*;
+use std::io::Cursor;
+
+/// An FText, the value of a TextProperty.
+#[derive(Debug, Clone)]
+pub struct Text {
+  pub flags: u32,
+  pub history: TextHistory,
+}
+
+/// How the text was created. Strings are `None` when they are stored as null
+/// (0 length) strings.
+#[derive(Debug, Clone)]
+pub enum TextHistory {
+  None {
+    culture_invariant_string: Option<Option<String>>,
+  },
+  Base {
+    namespace: Option<String>,
+    key: Option<String>,
+    source_string: Option<String>,
+  },
+  NamedFormat {
+    source_format: Box<Text>,
+    arguments: Vec<(Option<String>, FormatArgumentValue)>,
+  },
+  OrderedFormat {
+    source_format: Box<Text>,
+    arguments: Vec<FormatArgumentValue>,
+  },
+  ArgumentFormat {
+    source_format: Box<Text>,
+    arguments: Vec<(Option<String>, FormatArgumentData)>,
+  },
+  AsNumber(FormattedNumber),
+  AsPercent(FormattedNumber),
+  AsCurrency {
+    currency_code: Option<String>,
+    number: FormattedNumber,
+  },
+  AsDate {
+    source_date_time: i64,
+    date_style: i8,
+    time_zone: Option<String>,
+    target_culture: Option<String>,
+  },
+  AsTime {
+    source_date_time: i64,
+    time_style: i8,
+    time_zone: Option<String>,
+    target_culture: Option<String>,
+  },
+  AsDateTime {
+    source_date_time: i64,
+    date_style: i8,
+    time_style: i8,
+    time_zone: Option<String>,
+    target_culture: Option<String>,
+  },
+  Transform {
+    source_text: Box<Text>,
+    transform_type: u8,
+  },
+  StringTableEntry {
+    table_id: NameVariant,
+    key: Option<String>,
+  },
+  TextGenerator {
+    generator_type: NameVariant,
+    generator_contents: Option<Vec<u8>>, // Only present if generator_type is not None
+  },
+}
+
+#[derive(Debug, Clone)]
+pub enum FormatArgumentValue {
+  Int(i64),
+  UInt(u64),
+  Float(f32),
+  Double(f64),
+  Text(Text),
+  Gender(u8),
+}
+
+/// An argument of an ArgumentFormat text. Unlike `FormatArgumentValue`, ints
+/// are 32 bits and there are no unsigned ints or doubles.
+#[derive(Debug, Clone)]
+pub enum FormatArgumentData {
+  Int(i32),
+  Float(f32),
+  Text(Text),
+  Gender(u8),
+}
+
+/// Shared by the AsNumber, AsPercent and AsCurrency histories
+#[derive(Debug, Clone)]
+pub struct FormattedNumber {
+  pub source_value: Box<FormatArgumentValue>,
+  pub format_options: Option<NumberFormattingOptions>,
+  pub target_culture: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NumberFormattingOptions {
+  pub always_sign: bool,
+  pub use_grouping: bool,
+  pub rounding_mode: i8,
+  pub minimum_integral_digits: i32,
+  pub maximum_integral_digits: i32,
+  pub minimum_fractional_digits: i32,
+  pub maximum_fractional_digits: i32,
+}
+
+impl Text {
+  /// Creates a Base text with the given source string. Strings are None to
+  /// write them as null strings instead of empty ones.
+  pub fn base(namespace: Option<&str>, key: Option<&str>, source_string: Option<&str>) -> Self {
+    Self {
+      flags: 0,
+      history: TextHistory::Base {
+        namespace: namespace.map(str::to_string),
+        key: key.map(str::to_string),
+        source_string: source_string.map(str::to_string),
+      },
+    }
+  }
+
+  /// Creates an empty text, equivalent to FText::GetEmpty()
+  pub fn empty() -> Self {
+    Self {
+      flags: 0,
+      history: TextHistory::None {
+        culture_invariant_string: None,
+      },
+    }
+  }
+
+  /// The source string of the text, if it has one directly.
+  pub fn source_string(&self) -> Option<&str> {
+    match &self.history {
+      TextHistory::None {
+        culture_invariant_string,
+      } => culture_invariant_string.as_ref()?.as_deref(),
+      TextHistory::Base { source_string, .. } => source_string.as_deref(),
+      _ => None,
+    }
+  }
+
+  pub fn deserialize(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Self> {
+    let flags = read_u32(rdr).with_context(|| "Text.flags")?;
+    let history = TextHistory::deserialize(rdr, ctx).with_context(|| "Text.history")?;
+    Ok(Self { flags, history })
+  }
+
+  pub fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+    write_u32(curs, self.flags)?;
+    self.history.serialize(curs, ctx)
+  }
+
+  pub fn byte_size(&self) -> usize {
+    4 + self.history.byte_size()
+  }
+}
+
+impl TextHistory {
+  pub fn history_type(&self) -> i8 {
+    match self {
+      Self::None { .. } => -1,
+      Self::Base { .. } => 0,
+      Self::NamedFormat { .. } => 1,
+      Self::OrderedFormat { .. } => 2,
+      Self::ArgumentFormat { .. } => 3,
+      Self::AsNumber(_) => 4,
+      Self::AsPercent(_) => 5,
+      Self::AsCurrency { .. } => 6,
+      Self::AsDate { .. } => 7,
+      Self::AsTime { .. } => 8,
+      Self::AsDateTime { .. } => 9,
+      Self::Transform { .. } => 10,
+      Self::StringTableEntry { .. } => 11,
+      Self::TextGenerator { .. } => 12,
+    }
+  }
+
+  fn deserialize(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Self> {
+    let history_type = rdr.read_i8()?;
+    let history = match history_type {
+      -1 => {
+        let culture_invariant_string = if read_bool(rdr)? {
+          Some(read_fstring(rdr)?)
+        } else {
+          None
+        };
+        Self::None {
+          culture_invariant_string,
+        }
+      }
+      0 => Self::Base {
+        namespace: read_fstring(rdr).with_context(|| "Base.namespace")?,
+        key: read_fstring(rdr).with_context(|| "Base.key")?,
+        source_string: read_fstring(rdr).with_context(|| "Base.source_string")?,
+      },
+      1 => Self::NamedFormat {
+        source_format: Box::new(Text::deserialize(rdr, ctx)?),
+        arguments: read_named_arguments(rdr, ctx)?,
+      },
+      2 => {
+        let source_format = Box::new(Text::deserialize(rdr, ctx)?);
+        let count = rdr.read_i32::<LittleEndian>()?;
+        let mut arguments = vec![];
+        for i in 0..count {
+          arguments.push(
+            FormatArgumentValue::deserialize(rdr, ctx)
+              .with_context(|| format!("OrderedFormat.arguments[{}]", i))?,
+          );
+        }
+        Self::OrderedFormat {
+          source_format,
+          arguments,
+        }
+      }
+      3 => Self::ArgumentFormat {
+        source_format: Box::new(Text::deserialize(rdr, ctx)?),
+        arguments: read_named_arguments(rdr, ctx)?,
+      },
+      4 => Self::AsNumber(FormattedNumber::deserialize(rdr, ctx)?),
+      5 => Self::AsPercent(FormattedNumber::deserialize(rdr, ctx)?),
+      6 => Self::AsCurrency {
+        currency_code: read_fstring(rdr).with_context(|| "AsCurrency.currency_code")?,
+        number: FormattedNumber::deserialize(rdr, ctx)?,
+      },
+      7 => Self::AsDate {
+        source_date_time: rdr.read_i64::<LittleEndian>()?,
+        date_style: rdr.read_i8()?,
+        time_zone: read_fstring(rdr)?,
+        target_culture: read_fstring(rdr)?,
+      },
+      8 => Self::AsTime {
+        source_date_time: rdr.read_i64::<LittleEndian>()?,
+        time_style: rdr.read_i8()?,
+        time_zone: read_fstring(rdr)?,
+        target_culture: read_fstring(rdr)?,
+      },
+      9 => Self::AsDateTime {
+        source_date_time: rdr.read_i64::<LittleEndian>()?,
+        date_style: rdr.read_i8()?,
+        time_style: rdr.read_i8()?,
+        time_zone: read_fstring(rdr)?,
+        target_culture: read_fstring(rdr)?,
+      },
+      10 => Self::Transform {
+        source_text: Box::new(Text::deserialize(rdr, ctx)?),
+        transform_type: rdr.read_u8()?,
+      },
+      11 => Self::StringTableEntry {
+        table_id: NameVariant::read(rdr, ctx.names).with_context(|| "StringTableEntry.table_id")?,
+        key: read_fstring(rdr).with_context(|| "StringTableEntry.key")?,
+      },
+      12 => {
+        let generator_type =
+          NameVariant::read(rdr, ctx.names).with_context(|| "TextGenerator.generator_type")?;
+        let generator_contents = if generator_type.to_string(ctx.names) != "None" {
+          let len = read_u32(rdr)? as usize;
+          Some(read_bytes(rdr, len)?)
+        } else {
+          None
+        };
+        Self::TextGenerator {
+          generator_type,
+          generator_contents,
+        }
+      }
+      _ => bail!("Unknown text history type {}", history_type),
+    };
+    Ok(history)
+  }
+
+  fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+    curs.write_i8(self.history_type())?;
+    match self {
+      Self::None {
+        culture_invariant_string,
+      } => {
+        write_bool(curs, culture_invariant_string.is_some())?;
+        if let Some(string) = culture_invariant_string {
+          write_fstring(curs, string)?;
+        }
+      }
+      Self::Base {
+        namespace,
+        key,
+        source_string,
+      } => {
+        write_fstring(curs, namespace)?;
+        write_fstring(curs, key)?;
+        write_fstring(curs, source_string)?;
+      }
+      Self::NamedFormat {
+        source_format,
+        arguments,
+      } => {
+        source_format.serialize(curs, ctx)?;
+        write_named_arguments(curs, arguments, ctx)?;
+      }
+      Self::ArgumentFormat {
+        source_format,
+        arguments,
+      } => {
+        source_format.serialize(curs, ctx)?;
+        write_named_arguments(curs, arguments, ctx)?;
+      }
+      Self::OrderedFormat {
+        source_format,
+        arguments,
+      } => {
+        source_format.serialize(curs, ctx)?;
+        curs.write_i32::<LittleEndian>(arguments.len() as i32)?;
+        for argument in arguments {
+          argument.serialize(curs, ctx)?;
+        }
+      }
+      Self::AsNumber(number) | Self::AsPercent(number) => number.serialize(curs, ctx)?,
+      Self::AsCurrency {
+        currency_code,
+        number,
+      } => {
+        write_fstring(curs, currency_code)?;
+        number.serialize(curs, ctx)?;
+      }
+      Self::AsDate {
+        source_date_time,
+        date_style,
+        time_zone,
+        target_culture,
+      } => {
+        curs.write_i64::<LittleEndian>(*source_date_time)?;
+        curs.write_i8(*date_style)?;
+        write_fstring(curs, time_zone)?;
+        write_fstring(curs, target_culture)?;
+      }
+      Self::AsTime {
+        source_date_time,
+        time_style,
+        time_zone,
+        target_culture,
+      } => {
+        curs.write_i64::<LittleEndian>(*source_date_time)?;
+        curs.write_i8(*time_style)?;
+        write_fstring(curs, time_zone)?;
+        write_fstring(curs, target_culture)?;
+      }
+      Self::AsDateTime {
+        source_date_time,
+        date_style,
+        time_style,
+        time_zone,
+        target_culture,
+      } => {
+        curs.write_i64::<LittleEndian>(*source_date_time)?;
+        curs.write_i8(*date_style)?;
+        curs.write_i8(*time_style)?;
+        write_fstring(curs, time_zone)?;
+        write_fstring(curs, target_culture)?;
+      }
+      Self::Transform {
+        source_text,
+        transform_type,
+      } => {
+        source_text.serialize(curs, ctx)?;
+        curs.write_u8(*transform_type)?;
+      }
+      Self::StringTableEntry { table_id, key } => {
+        table_id.write(curs, ctx.names)?;
+        write_fstring(curs, key)?;
+      }
+      Self::TextGenerator {
+        generator_type,
+        generator_contents,
+      } => {
+        generator_type.write(curs, ctx.names)?;
+        if let Some(contents) = generator_contents {
+          write_u32(curs, contents.len() as u32)?;
+          curs.write_all(contents)?;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    let history_size = match self {
+      Self::None {
+        culture_invariant_string,
+      } => 4 + culture_invariant_string.as_ref().map_or(0, fstring_size),
+      Self::Base {
+        namespace,
+        key,
+        source_string,
+      } => fstring_size(namespace) + fstring_size(key) + fstring_size(source_string),
+      Self::NamedFormat {
+        source_format,
+        arguments,
+      } => source_format.byte_size() + named_arguments_size(arguments),
+      Self::ArgumentFormat {
+        source_format,
+        arguments,
+      } => source_format.byte_size() + named_arguments_size(arguments),
+      Self::OrderedFormat {
+        source_format,
+        arguments,
+      } => {
+        source_format.byte_size() + 4 + arguments.iter().map(|arg| arg.byte_size()).sum::<usize>()
+      }
+      Self::AsNumber(number) | Self::AsPercent(number) => number.byte_size(),
+      Self::AsCurrency {
+        currency_code,
+        number,
+      } => fstring_size(currency_code) + number.byte_size(),
+      Self::AsDate {
+        time_zone,
+        target_culture,
+        ..
+      }
+      | Self::AsTime {
+        time_zone,
+        target_culture,
+        ..
+      } => 8 + 1 + fstring_size(time_zone) + fstring_size(target_culture),
+      Self::AsDateTime {
+        time_zone,
+        target_culture,
+        ..
+      } => 8 + 2 + fstring_size(time_zone) + fstring_size(target_culture),
+      Self::Transform { source_text, .. } => source_text.byte_size() + 1,
+      Self::StringTableEntry { key, .. } => 8 + fstring_size(key),
+      Self::TextGenerator {
+        generator_contents, ..
+      } => {
+        8 + generator_contents
+          .as_ref()
+          .map_or(0, |contents| 4 + contents.len())
+      }
+    };
+    1 + history_size
+  }
+}
+
+/// The values of NamedFormat and ArgumentFormat arguments
+trait FormatArgument: Sized {
+  fn deserialize(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Self>;
+  fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()>;
+  fn byte_size(&self) -> usize;
+}
+
+impl FormatArgument for FormatArgumentValue {
+  fn deserialize(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Self> {
+    let typ = rdr.read_i8()?;
+    let value = match typ {
+      0 => Self::Int(rdr.read_i64::<LittleEndian>()?),
+      1 => Self::UInt(rdr.read_u64::<LittleEndian>()?),
+      2 => Self::Float(rdr.read_f32::<LittleEndian>()?),
+      3 => Self::Double(rdr.read_f64::<LittleEndian>()?),
+      4 => Self::Text(Text::deserialize(rdr, ctx)?),
+      5 => Self::Gender(rdr.read_u8()?),
+      _ => bail!("Unknown format argument type {}", typ),
+    };
+    Ok(value)
+  }
+
+  fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+    match self {
+      Self::Int(value) => {
+        curs.write_i8(0)?;
+        curs.write_i64::<LittleEndian>(*value)?;
+      }
+      Self::UInt(value) => {
+        curs.write_i8(1)?;
+        curs.write_u64::<LittleEndian>(*value)?;
+      }
+      Self::Float(value) => {
+        curs.write_i8(2)?;
+        curs.write_f32::<LittleEndian>(*value)?;
+      }
+      Self::Double(value) => {
+        curs.write_i8(3)?;
+        curs.write_f64::<LittleEndian>(*value)?;
+      }
+      Self::Text(value) => {
+        curs.write_i8(4)?;
+        value.serialize(curs, ctx)?;
+      }
+      Self::Gender(value) => {
+        curs.write_i8(5)?;
+        curs.write_u8(*value)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    let value_size = match self {
+      Self::Int(_) | Self::UInt(_) | Self::Double(_) => 8,
+      Self::Float(_) => 4,
+      Self::Text(value) => value.byte_size(),
+      Self::Gender(_) => 1,
+    };
+    1 + value_size
+  }
+}
+
+impl FormatArgument for FormatArgumentData {
+  fn deserialize(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Self> {
+    let typ = rdr.read_i8()?;
+    let value = match typ {
+      0 => Self::Int(rdr.read_i32::<LittleEndian>()?),
+      2 => Self::Float(rdr.read_f32::<LittleEndian>()?),
+      4 => Self::Text(Text::deserialize(rdr, ctx)?),
+      5 => Self::Gender(rdr.read_u8()?),
+      _ => bail!("Unknown format argument data type {}", typ),
+    };
+    Ok(value)
+  }
+
+  fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+    match self {
+      Self::Int(value) => {
+        curs.write_i8(0)?;
+        curs.write_i32::<LittleEndian>(*value)?;
+      }
+      Self::Float(value) => {
+        curs.write_i8(2)?;
+        curs.write_f32::<LittleEndian>(*value)?;
+      }
+      Self::Text(value) => {
+        curs.write_i8(4)?;
+        value.serialize(curs, ctx)?;
+      }
+      Self::Gender(value) => {
+        curs.write_i8(5)?;
+        curs.write_u8(*value)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn byte_size(&self) -> usize {
+    let value_size = match self {
+      Self::Int(_) | Self::Float(_) => 4,
+      Self::Text(value) => value.byte_size(),
+      Self::Gender(_) => 1,
+    };
+    1 + value_size
+  }
+}
+
+impl FormattedNumber {
+  fn deserialize(rdr: &mut ByteReader, ctx: PropertyContext) -> Result<Self> {
+    let source_value = Box::new(FormatArgumentValue::deserialize(rdr, ctx)?);
+    let format_options = if read_bool(rdr)? {
+      Some(NumberFormattingOptions::deserialize(rdr)?)
+    } else {
+      None
+    };
+    let target_culture = read_fstring(rdr)?;
+    Ok(Self {
+      source_value,
+      format_options,
+      target_culture,
+    })
+  }
+
+  fn serialize(&self, curs: &mut Cursor<Vec<u8>>, ctx: PropertyContext) -> Result<()> {
+    self.source_value.serialize(curs, ctx)?;
+    write_bool(curs, self.format_options.is_some())?;
+    if let Some(options) = &self.format_options {
+      options.serialize(curs)?;
+    }
+    write_fstring(curs, &self.target_culture)
+  }
+
+  fn byte_size(&self) -> usize {
+    let options_size = if self.format_options.is_some() {
+      NumberFormattingOptions::BYTE_SIZE
+    } else {
+      0
+    };
+    self.source_value.byte_size() + 4 + options_size + fstring_size(&self.target_culture)
+  }
+}
+
+impl NumberFormattingOptions {
+  const BYTE_SIZE: usize = 25;
+
+  fn deserialize(rdr: &mut ByteReader) -> Result<Self> {
+    Ok(Self {
+      always_sign: read_bool(rdr)?,
+      use_grouping: read_bool(rdr)?,
+      rounding_mode: rdr.read_i8()?,
+      minimum_integral_digits: rdr.read_i32::<LittleEndian>()?,
+      maximum_integral_digits: rdr.read_i32::<LittleEndian>()?,
+      minimum_fractional_digits: rdr.read_i32::<LittleEndian>()?,
+      maximum_fractional_digits: rdr.read_i32::<LittleEndian>()?,
+    })
+  }
+
+  fn serialize(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    write_bool(curs, self.always_sign)?;
+    write_bool(curs, self.use_grouping)?;
+    curs.write_i8(self.rounding_mode)?;
+    curs.write_i32::<LittleEndian>(self.minimum_integral_digits)?;
+    curs.write_i32::<LittleEndian>(self.maximum_integral_digits)?;
+    curs.write_i32::<LittleEndian>(self.minimum_fractional_digits)?;
+    curs.write_i32::<LittleEndian>(self.maximum_fractional_digits)?;
+    Ok(())
+  }
+}
+
+fn read_named_arguments<A: FormatArgument>(
+  rdr: &mut ByteReader,
+  ctx: PropertyContext,
+) -> Result<Vec<(Option<String>, A)>> {
+  let count = rdr.read_i32::<LittleEndian>()?;
+  let mut arguments = vec![];
+  for i in 0..count {
+    let name = read_fstring(rdr).with_context(|| format!("Format argument[{}].name", i))?;
+    let value =
+      A::deserialize(rdr, ctx).with_context(|| format!("Format argument[{}].value", i))?;
+    arguments.push((name, value));
+  }
+  Ok(arguments)
+}
+
+fn write_named_arguments<A: FormatArgument>(
+  curs: &mut Cursor<Vec<u8>>,
+  arguments: &[(Option<String>, A)],
+  ctx: PropertyContext,
+) -> Result<()> {
+  curs.write_i32::<LittleEndian>(arguments.len() as i32)?;
+  for (name, value) in arguments {
+    write_fstring(curs, name)?;
+    value.serialize(curs, ctx)?;
+  }
+  Ok(())
+}
+
+fn named_arguments_size<A: FormatArgument>(arguments: &[(Option<String>, A)]) -> usize {
+  4 + arguments
+    .iter()
+    .map(|(name, value)| fstring_size(name) + value.byte_size())
+    .sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::asset::property::TestPackage;
+
+  fn round_trip(package: &TestPackage, history: TextHistory) -> Text {
+    let text = Text { flags: 0, history };
+    package.round_trip(&text, Text::deserialize, Text::serialize, Text::byte_size)
+  }
+
+  fn format(source: &str) -> Box<Text> {
+    Box::new(Text::base(None, Some("Key"), Some(source)))
+  }
+
+  #[test]
+  fn argument_format_ints_are_32_bit() {
+    let package = TestPackage::new(&[]);
+    let text = round_trip(
+      &package,
+      TextHistory::ArgumentFormat {
+        source_format: format("{Count}"),
+        arguments: vec![(Some("Count".to_string()), FormatArgumentData::Int(-5))],
+      },
+    );
+    match text.history {
+      TextHistory::ArgumentFormat { arguments, .. } => {
+        assert!(matches!(arguments[..], [(_, FormatArgumentData::Int(-5))]))
+      }
+      history => panic!("Read {:?}", history),
+    }
+
+    let mut curs = Cursor::new(vec![]);
+    FormatArgumentData::Int(-5)
+      .serialize(&mut curs, package.ctx())
+      .unwrap();
+    assert_eq!(curs.into_inner(), [0, 0xFB, 0xFF, 0xFF, 0xFF]);
+  }
+
+  #[test]
+  fn argument_format_round_trips() {
+    let package = TestPackage::new(&[]);
+    round_trip(
+      &package,
+      TextHistory::ArgumentFormat {
+        source_format: format("{A} {B} {C}"),
+        arguments: vec![
+          (Some("A".to_string()), FormatArgumentData::Float(1.5)),
+          (
+            Some("B".to_string()),
+            FormatArgumentData::Text(Text::base(Some(""), Some("Inner"), Some("Text"))),
+          ),
+          (Some("C".to_string()), FormatArgumentData::Gender(1)),
+        ],
+      },
+    );
+  }
+
+  #[test]
+  fn named_and_ordered_formats_round_trip() {
+    let package = TestPackage::new(&[]);
+    round_trip(
+      &package,
+      TextHistory::NamedFormat {
+        source_format: format("{A} {B}"),
+        arguments: vec![
+          (Some("A".to_string()), FormatArgumentValue::Int(i64::MIN)),
+          (Some("B".to_string()), FormatArgumentValue::Double(0.25)),
+        ],
+      },
+    );
+    round_trip(
+      &package,
+      TextHistory::OrderedFormat {
+        source_format: format("{0} {1}"),
+        arguments: vec![
+          FormatArgumentValue::UInt(u64::MAX),
+          FormatArgumentValue::Text(Text::empty()),
+        ],
+      },
+    );
+  }
+
+  #[test]
+  fn number_histories_round_trip() {
+    let package = TestPackage::new(&[]);
+    let number = |format_options| FormattedNumber {
+      source_value: Box::new(FormatArgumentValue::Float(12.5)),
+      format_options,
+      target_culture: Some("en".to_string()),
+    };
+    round_trip(&package, TextHistory::AsNumber(number(None)));
+    round_trip(
+      &package,
+      TextHistory::AsCurrency {
+        currency_code: Some("USD".to_string()),
+        number: number(Some(NumberFormattingOptions {
+          always_sign: true,
+          use_grouping: false,
+          rounding_mode: 2,
+          minimum_integral_digits: 1,
+          maximum_integral_digits: 10,
+          minimum_fractional_digits: 0,
+          maximum_fractional_digits: 3,
+        })),
+      },
+    );
+  }
+
+  #[test]
+  fn other_histories_round_trip() {
+    let package = TestPackage::new(&["/Game/ST_Items.ST_Items"]);
+    round_trip(
+      &package,
+      TextHistory::None {
+        culture_invariant_string: Some(Some("Invariant".to_string())),
+      },
+    );
+    round_trip(
+      &package,
+      TextHistory::AsDateTime {
+        source_date_time: 637_000_000_000_000_000,
+        date_style: 1,
+        time_style: 2,
+        time_zone: None,
+        target_culture: None,
+      },
+    );
+    round_trip(
+      &package,
+      TextHistory::Transform {
+        source_text: format("Upper"),
+        transform_type: 1,
+      },
+    );
+    round_trip(
+      &package,
+      TextHistory::StringTableEntry {
+        table_id: NameVariant::new("/Game/ST_Items.ST_Items", 0, &package.names),
+        key: Some("Item".to_string()),
+      },
+    );
+    round_trip(
+      &package,
+      TextHistory::TextGenerator {
+        generator_type: NameVariant::new("None", 0, &package.names),
+        generator_contents: None,
+      },
+    );
+  }
+
+  #[test]
+  fn base_texts_keep_empty_and_null_strings() {
+    let package = TestPackage::new(&[]);
+    let empty = Text::base(Some(""), Some("Key"), Some("Source"));
+    let null = Text::base(None, Some("Key"), Some("Source"));
+    // An empty string has a length of 1 for its terminator
+    assert_eq!(empty.byte_size(), null.byte_size() + 1);
+    for text in [empty, null].iter() {
+      let read = round_trip(&package, text.history.clone());
+      assert_eq!(read.byte_size(), text.byte_size());
+      match (read.history, &text.history) {
+        (
+          TextHistory::Base { namespace, .. },
+          TextHistory::Base {
+            namespace: expected,
+            ..
+          },
+        ) => {
+          assert_eq!(&namespace, expected)
+        }
+        (history, _) => panic!("Read {:?}", history),
+      }
+    }
+  }
+}
@@ -0,0 +1,858 @@
+use crate::asset::property::mappings::*;
+use crate::asset::property::meta::*;
+use crate::asset::property::prop_type::*;
+use crate::asset::property::struct_pattern::*;
+use crate::asset::*;
+use crate::reader::*;
+use crate::util::*;
+use anyhow::*;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+// Unversioned properties start with a header of fragments, each skipping
+// some schema indices and then listing some values. Values of fragments with
+// zeroes have a bit in the zero mask after the fragments, and aren't
+// serialized if it is set.
+const SKIP_MAX: u16 = 0x7F;
+const VALUE_MAX: u16 = 0x7F;
+const HAS_ZEROES_FLAG: u16 = 0x80;
+const IS_LAST_FLAG: u16 = 0x100;
+const VALUE_NUM_SHIFT: u16 = 9;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Fragment {
+  skip_num: u16,
+  value_num: u16,
+  has_zeroes: bool,
+  is_last: bool,
+}
+
+impl Fragment {
+  fn unpack(packed: u16) -> Self {
+    Self {
+      skip_num: packed & SKIP_MAX,
+      value_num: packed >> VALUE_NUM_SHIFT,
+      has_zeroes: packed & HAS_ZEROES_FLAG != 0,
+      is_last: packed & IS_LAST_FLAG != 0,
+    }
+  }
+
+  fn pack(&self) -> u16 {
+    let mut packed = self.skip_num | (self.value_num << VALUE_NUM_SHIFT);
+    if self.has_zeroes {
+      packed |= HAS_ZEROES_FLAG;
+    }
+    if self.is_last {
+      packed |= IS_LAST_FLAG;
+    }
+    packed
+  }
+}
+
+/// Reads the header, returning the schema index of every serialized property
+/// and whether its value is zero
+fn read_header(rdr: &mut ByteReader) -> Result<Vec<(usize, bool)>> {
+  let mut fragments = vec![];
+  loop {
+    let fragment = Fragment::unpack(rdr.read_u16::<LittleEndian>()?);
+    fragments.push(fragment);
+    if fragment.is_last {
+      break;
+    }
+  }
+
+  let zero_mask_len = fragments
+    .iter()
+    .filter(|fragment| fragment.has_zeroes)
+    .map(|fragment| fragment.value_num as usize)
+    .sum::<usize>();
+  let zero_mask: Vec<u32> = if zero_mask_len == 0 {
+    vec![]
+  } else if zero_mask_len <= 8 {
+    vec![rdr.read_u8()?.into()]
+  } else if zero_mask_len <= 16 {
+    vec![rdr.read_u16::<LittleEndian>()?.into()]
+  } else {
+    let mut words = vec![];
+    for _ in 0..zero_mask_len.div_ceil(32) {
+      words.push(rdr.read_u32::<LittleEndian>()?);
+    }
+    words
+  };
+
+  let mut entries = vec![];
+  let mut schema_index = 0;
+  let mut zero_index = 0;
+  for fragment in fragments {
+    schema_index += fragment.skip_num as usize;
+    for _ in 0..fragment.value_num {
+      let is_zero = if fragment.has_zeroes {
+        let bit = zero_mask[zero_index / 32] & (1 << (zero_index % 32)) != 0;
+        zero_index += 1;
+        bit
+      } else {
+        false
+      };
+      entries.push((schema_index, is_zero));
+      schema_index += 1;
+    }
+  }
+  Ok(entries)
+}
+
+/// Writes the header for properties at the sorted schema indices
+fn write_header(
+  curs: &mut Cursor<Vec<u8>>,
+  entries: &[(usize, bool)],
+  schema_size: usize,
+) -> Result<()> {
+  let mut fragments = vec![Fragment::default()];
+  // Without any values, the engine still writes the skip of the first fragment
+  if entries.is_empty() {
+    fragments[0].skip_num = schema_size.min(SKIP_MAX as usize) as u16;
+  }
+  let mut zero_mask = vec![];
+  // Zero bits of the last fragment, dropped if it has no zeroes
+  let mut fragment_zeroes = vec![];
+
+  let mut next_index = 0;
+  for (schema_index, is_zero) in entries.iter() {
+    for _ in next_index..*schema_index {
+      let last = fragments.last().unwrap();
+      if last.value_num > 0 || last.skip_num == SKIP_MAX {
+        if last.has_zeroes {
+          zero_mask.append(&mut fragment_zeroes);
+        }
+        fragment_zeroes.clear();
+        fragments.push(Fragment::default());
+      }
+      fragments.last_mut().unwrap().skip_num += 1;
+    }
+
+    let last = fragments.last().unwrap();
+    if last.value_num == VALUE_MAX {
+      if last.has_zeroes {
+        zero_mask.append(&mut fragment_zeroes);
+      }
+      fragment_zeroes.clear();
+      fragments.push(Fragment::default());
+    }
+    let last = fragments.last_mut().unwrap();
+    last.value_num += 1;
+    last.has_zeroes |= *is_zero;
+    fragment_zeroes.push(*is_zero);
+    next_index = schema_index + 1;
+  }
+  if fragments.last().unwrap().has_zeroes {
+    zero_mask.append(&mut fragment_zeroes);
+  }
+  fragments.last_mut().unwrap().is_last = true;
+
+  for fragment in fragments.iter() {
+    curs.write_u16::<LittleEndian>(fragment.pack())?;
+  }
+
+  let mut words = vec![0u32; zero_mask.len().div_ceil(32)];
+  for (i, is_zero) in zero_mask.iter().enumerate() {
+    if *is_zero {
+      words[i / 32] |= 1 << (i % 32);
+    }
+  }
+  if zero_mask.len() > 16 {
+    for word in words {
+      curs.write_u32::<LittleEndian>(word)?;
+    }
+  } else if zero_mask.len() > 8 {
+    curs.write_u16::<LittleEndian>(words[0] as u16)?;
+  } else if !zero_mask.is_empty() {
+    curs.write_u8(words[0] as u8)?;
+  }
+  Ok(())
+}
+
+fn name_variant(name: &str, names: &Names) -> Result<NameVariant> {
+  names
+    .get_name_obj(name)
+    .with_context(|| format!("Missing name {} for unversioned property", name))?;
+  Ok(NameVariant::new(name, 0, names))
+}
+
+fn enum_entries<'a>(mappings: &'a Mappings, enum_name: &str) -> Result<&'a Vec<String>> {
+  mappings
+    .enums
+    .get(enum_name)
+    .with_context(|| format!("No mappings for enum {}", enum_name))
+}
+
+fn tag_for(typ: &MappedType, ctx: PropertyContext) -> Result<Tag> {
+  Ok(match typ {
+    MappedType::Simple(PropType::BoolProperty) => Tag::Bool(false),
+    MappedType::Simple(PropType::ByteProperty) => Tag::Enum(name_variant("None", ctx.names)?),
+    MappedType::Simple(typ) => Tag::Simple(*typ),
+    MappedType::Enum { enum_name, .. } => Tag::Enum(name_variant(enum_name, ctx.names)?),
+    MappedType::Struct { struct_name } => Tag::Struct {
+      type_name: name_variant(struct_name, ctx.names)?,
+      guid: [0; 16],
+    },
+    MappedType::Array { inner } => Tag::Array {
+      inner_type: inner.prop_type(),
+    },
+    MappedType::Set { inner } => Tag::Set {
+      inner_type: inner.prop_type(),
+    },
+    MappedType::Map { key, value } => Tag::Map {
+      key_type: key.prop_type(),
+      value_type: value.prop_type(),
+    },
+  })
+}
+
+/// Reads the properties of `struct_name` until the end of the header's list
+pub fn deserialize_properties(
+  rdr: &mut ByteReader,
+  struct_name: &str,
+  mappings: &Mappings,
+  ctx: PropertyContext,
+) -> Result<Vec<Property>> {
+  let entries = read_header(rdr).with_context(|| format!("Header of {}", struct_name))?;
+  let mut properties = vec![];
+  for (schema_index, is_zero) in entries {
+    let (schema, array_index) = mappings.property_at(struct_name, schema_index)?;
+    let (tag, value) = if is_zero {
+      zero_element(&schema.typ, mappings, ctx)
+    } else {
+      deserialize_element(rdr, &schema.typ, mappings, ctx)
+    }
+    .with_context(|| format!("{}.{}[{}]", struct_name, schema.name, array_index))?;
+
+    let typ = schema.typ.prop_type();
+    let name = name_variant(&schema.name, ctx.names)?;
+    // The element tag of an array of structs is named after the array
+    let value = match value {
+      Value::Array {
+        meta_tag: Some((meta, inner_tag)),
+        values,
+      } => Value::Array {
+        meta_tag: Some((
+          Meta {
+            name: name.clone(),
+            ..meta
+          },
+          inner_tag,
+        )),
+        values,
+      },
+      value => value,
+    };
+    let loader = Property::get_loader_for(typ)?;
+    let size = loader.value_size(&value, &tag) as u32;
+    let meta = Meta {
+      unversioned_zero: Some(is_zero),
+      ..Meta::new(name, typ, size).with_array_index(array_index)
+    };
+    properties.push(Property { meta, tag, value });
+  }
+  Ok(properties)
+}
+
+/// Writes `properties` as unversioned properties of `struct_name`
+pub fn serialize_properties(
+  curs: &mut Cursor<Vec<u8>>,
+  properties: &[Property],
+  struct_name: &str,
+  mappings: &Mappings,
+  ctx: PropertyContext,
+) -> Result<()> {
+  // Properties have to be written in schema order
+  let mut indexed = vec![];
+  for property in properties {
+    let name = property.meta.name.to_string(ctx.names);
+    let schema_index = mappings.index_of(struct_name, &name, property.meta.array_index)?;
+    let (schema, _) = mappings.property_at(struct_name, schema_index)?;
+    indexed.push((schema_index, schema, property));
+  }
+  indexed.sort_by_key(|(schema_index, _, _)| *schema_index);
+  for pair in indexed.windows(2) {
+    if pair[0].0 == pair[1].0 {
+      bail!(
+        "Property {}[{}] of {} is there twice",
+        pair[0].1.name,
+        pair[0].2.meta.array_index,
+        struct_name
+      );
+    }
+  }
+
+  let mut entries = vec![];
+  for (schema_index, schema, property) in indexed.iter() {
+    let is_zero = match property.meta.unversioned_zero {
+      // Keep the bit that was read, unless the value isn't zero anymore
+      Some(true) => is_zero(&schema.typ, &property.tag, &property.value, mappings, ctx)?,
+      Some(false) => false,
+      // The engine only stores plain data as zero
+      None => {
+        is_plain_data(&schema.typ, &property.value)
+          && is_zero(&schema.typ, &property.tag, &property.value, mappings, ctx)?
+      }
+    };
+    entries.push((*schema_index, is_zero));
+  }
+  write_header(curs, &entries, mappings.schema_size(struct_name)?)?;
+
+  for ((_, schema, property), (_, is_zero)) in indexed.iter().zip(entries) {
+    if !is_zero {
+      serialize_element(
+        curs,
+        &schema.typ,
+        &property.tag,
+        &property.value,
+        mappings,
+        ctx,
+      )
+      .with_context(|| {
+        format!(
+          "{}.{}[{}]",
+          struct_name, schema.name, property.meta.array_index
+        )
+      })?;
+    }
+  }
+  Ok(())
+}
+
+/// Enums are stored as their index in the enum, with the size of the
+/// underlying type
+fn read_enum_index(rdr: &mut ByteReader, inner: &MappedType) -> Result<u64> {
+  Ok(match inner.prop_type() {
+    PropType::ByteProperty | PropType::UInt8Property | PropType::Int8Property => {
+      rdr.read_u8()?.into()
+    }
+    PropType::UInt16Property | PropType::Int16Property => rdr.read_u16::<LittleEndian>()?.into(),
+    PropType::IntProperty | PropType::UInt32Property => rdr.read_u32::<LittleEndian>()?.into(),
+    PropType::Int64Property | PropType::UInt64Property => rdr.read_u64::<LittleEndian>()?,
+    typ => bail!("Invalid enum underlying type {}", typ),
+  })
+}
+
+fn write_enum_index(curs: &mut Cursor<Vec<u8>>, inner: &MappedType, index: usize) -> Result<()> {
+  match inner.prop_type() {
+    PropType::ByteProperty | PropType::UInt8Property | PropType::Int8Property => {
+      curs.write_u8(index as u8)?
+    }
+    PropType::UInt16Property | PropType::Int16Property => {
+      curs.write_u16::<LittleEndian>(index as u16)?
+    }
+    PropType::IntProperty | PropType::UInt32Property => {
+      curs.write_u32::<LittleEndian>(index as u32)?
+    }
+    PropType::Int64Property | PropType::UInt64Property => {
+      curs.write_u64::<LittleEndian>(index as u64)?
+    }
+    typ => bail!("Invalid enum underlying type {}", typ),
+  }
+  Ok(())
+}
+
+fn enum_value(
+  enum_name: &str,
+  index: usize,
+  mappings: &Mappings,
+  ctx: PropertyContext,
+) -> Result<Value> {
+  let entry = enum_entries(mappings, enum_name)?
+    .get(index)
+    .with_context(|| format!("Enum {} has no value {}", enum_name, index))?;
+  let name = Mappings::enum_value_name(enum_name, entry);
+  Ok(Value::Enum(name_variant(&name, ctx.names)?))
+}
+
+fn enum_index(
+  enum_name: &str,
+  value: &Value,
+  mappings: &Mappings,
+  ctx: PropertyContext,
+) -> Result<usize> {
+  let value = match value {
+    Value::Enum(value) => value.to_string(ctx.names),
+    _ => bail!("Expected an enum value for {}", enum_name),
+  };
+  enum_entries(mappings, enum_name)?
+    .iter()
+    .position(|entry| Mappings::enum_value_name(enum_name, entry) == value)
+    .with_context(|| format!("{} is not a value of {}", value, enum_name))
+}
+
+fn deserialize_element(
+  rdr: &mut ByteReader,
+  typ: &MappedType,
+  mappings: &Mappings,
+  ctx: PropertyContext,
+) -> Result<(Tag, Value)> {
+  let tag = tag_for(typ, ctx)?;
+  let value = match typ {
+    MappedType::Simple(PropType::BoolProperty) => {
+      return Ok((Tag::Bool(rdr.read_u8()? != 0), Value::Bool));
+    }
+    MappedType::Simple(PropType::ByteProperty) => Value::Byte(rdr.read_u8()?),
+    MappedType::Simple(typ) => {
+      let loader = Property::get_loader_for(*typ)?;
+      let max_size = rdr.remaining_bytes() as u64;
+      loader.deserialize_value(rdr, &tag, max_size, ctx)?
+    }
+    MappedType::Enum { inner, enum_name } => {
+      let index = read_enum_index(rdr, inner)?;
+      enum_value(enum_name, index as usize, mappings, ctx)?
+    }
+    MappedType::Struct { struct_name } => {
+      let value = if ctx.patterns.binary_size(struct_name).is_some() {
+        ctx.patterns.deserialize(rdr, struct_name, ctx)?
+      } else {
+        StructValue::PropertyList {
+          properties: deserialize_properties(rdr, struct_name, mappings, ctx)?,
+          has_none: false,
+        }
+      };
+      Value::Struct { value }
+    }
+    MappedType::Array { inner } => {
+      let count = read_u32(rdr)?;
+      let mut values = vec![];
+      for i in 0..count {
+        let element = deserialize_element(rdr, inner, mappings, ctx)
+          .with_context(|| format!("Array[{}]", i))?;
+        values.push(element);
+      }
+      // Tagged arrays of structs have a tag for their elements
+      let meta_tag = match inner.as_ref() {
+        MappedType::Struct { .. } => {
+          let inner_tag = tag_for(inner, ctx)?;
+          let name = name_variant("None", ctx.names)?;
+          Some((Meta::new(name, PropType::StructProperty, 0), inner_tag))
+        }
+        _ => None,
+      };
+      Value::Array { meta_tag, values }
+    }
+    MappedType::Set { inner } => {
+      let elements_to_remove = deserialize_elements(rdr, inner, mappings, ctx)
+        .with_context(|| "Set.elements_to_remove")?;
+      let elements =
+        deserialize_elements(rdr, inner, mappings, ctx).with_context(|| "Set.elements")?;
+      Value::Set {
+        elements_to_remove,
+        elements,
+      }
+    }
+    MappedType::Map { key, value } => {
+      let keys_to_remove =
+        deserialize_elements(rdr, key, mappings, ctx).with_context(|| "Map.keys_to_remove")?;
+      let count = read_u32(rdr)?;
+      let mut entries = vec![];
+      for i in 0..count {
+        let (_, key) = deserialize_element(rdr, key, mappings, ctx)
+          .with_context(|| format!("Map.key[{}]", i))?;
+        let (_, value) = deserialize_element(rdr, value, mappings, ctx)
+          .with_context(|| format!("Map.value[{}]", i))?;
+        entries.push((key, value));
+      }
+      Value::Map {
+        keys_to_remove,
+        entries,
+      }
+    }
+  };
+  Ok((tag, value))
+}
+
+/// Reads a count and that many values, for set elements and map keys
+fn deserialize_elements(
+  rdr: &mut ByteReader,
+  typ: &MappedType,
+  mappings: &Mappings,
+  ctx: PropertyContext,
+) -> Result<Vec<Value>> {
+  if typ.prop_type() == PropType::BoolProperty {
+    bail!("Bools can't be stored without a tag");
+  }
+  let count = read_u32(rdr)?;
+  let mut values = vec![];
+  for i in 0..count {
+    let (_, value) =
+      deserialize_element(rdr, typ, mappings, ctx).with_context(|| format!("[{}]", i))?;
+    values.push(value);
+  }
+  Ok(values)
+}
+
+fn serialize_element(
+  curs: &mut Cursor<Vec<u8>>,
+  typ: &MappedType,
+  tag: &Tag,
+  value: &Value,
+  mappings: &Mappings,
+  ctx: PropertyContext,
+) -> Result<()> {
+  match (typ, value) {
+    (MappedType::Simple(PropType::BoolProperty), _) => match tag {
+      Tag::Bool(value) => curs.write_u8(if *value { 1 } else { 0 })?,
+      _ => bail!("Expected a bool tag"),
+    },
+    (MappedType::Simple(PropType::ByteProperty), Value::Byte(value)) => curs.write_u8(*value)?,
+    (MappedType::Simple(PropType::ByteProperty), _) => bail!("Expected a byte value"),
+    (MappedType::Simple(typ), _) => {
+      let loader = Property::get_loader_for(*typ)?;
+      loader.serialize_value(curs, value, &Tag::Simple(*typ), ctx)?;
+    }
+    (MappedType::Enum { inner, enum_name }, _) => {
+      let index = enum_index(enum_name, value, mappings, ctx)?;
+      write_enum_index(curs, inner, index)?;
+    }
+    (MappedType::Struct { struct_name }, Value::Struct { value }) => match value {
+      StructValue::PropertyList { properties, .. } => {
+        serialize_properties(curs, properties, struct_name, mappings, ctx)?
+      }
+      _ => value.serialize(curs, ctx)?,
+    },
+    (MappedType::Array { inner }, Value::Array { values, .. }) => {
+      write_u32(curs, values.len() as u32)?;
+      for (i, (tag, value)) in values.iter().enumerate() {
+        serialize_element(curs, inner, tag, value, mappings, ctx)
+          .with_context(|| format!("Array[{}]", i))?;
+      }
+    }
+    (
+      MappedType::Set { inner },
+      Value::Set {
+        elements_to_remove,
+        elements,
+      },
+    ) => {
+      serialize_elements(curs, inner, elements_to_remove, mappings, ctx)
+        .with_context(|| "Set.elements_to_remove")?;
+      serialize_elements(curs, inner, elements, mappings, ctx).with_context(|| "Set.elements")?;
+    }
+    (
+      MappedType::Map { key, value },
+      Value::Map {
+        keys_to_remove,
+        entries,
+      },
+    ) => {
+      serialize_elements(curs, key, keys_to_remove, mappings, ctx)
+        .with_context(|| "Map.keys_to_remove")?;
+      write_u32(curs, entries.len() as u32)?;
+      let key_tag = tag_for(key, ctx)?;
+      let value_tag = tag_for(value, ctx)?;
+      for (i, (k, v)) in entries.iter().enumerate() {
+        serialize_element(curs, key, &key_tag, k, mappings, ctx)
+          .with_context(|| format!("Map.key[{}]", i))?;
+        serialize_element(curs, value, &value_tag, v, mappings, ctx)
+          .with_context(|| format!("Map.value[{}]", i))?;
+      }
+    }
+    _ => bail!("Value doesn't match the mapped type {}", typ.prop_type()),
+  }
+  Ok(())
+}
+
+fn serialize_elements(
+  curs: &mut Cursor<Vec<u8>>,
+  typ: &MappedType,
+  values: &[Value],
+  mappings: &Mappings,
+  ctx: PropertyContext,
+) -> Result<()> {
+  let tag = tag_for(typ, ctx)?;
+  write_u32(curs, values.len() as u32)?;
+  for (i, value) in values.iter().enumerate() {
+    serialize_element(curs, typ, &tag, value, mappings, ctx).with_context(|| format!("[{}]", i))?;
+  }
+  Ok(())
+}
+
+/// The value of a property whose zero mask bit is set
+fn zero_element(
+  typ: &MappedType,
+  mappings: &Mappings,
+  ctx: PropertyContext,
+) -> Result<(Tag, Value)> {
+  let tag = tag_for(typ, ctx)?;
+  let none = || name_variant("None", ctx.names);
+  let value = match typ {
+    MappedType::Simple(typ) => match typ {
+      PropType::BoolProperty => Value::Bool,
+      PropType::ByteProperty => Value::Byte(0),
+      PropType::Int8Property => Value::Int8(0),
+      PropType::Int16Property => Value::Int16(0),
+      PropType::IntProperty => Value::Int(0),
+      PropType::Int64Property => Value::Int64(0),
+      PropType::UInt8Property => Value::UInt8(0),
+      PropType::UInt16Property => Value::UInt16(0),
+      PropType::UInt32Property => Value::UInt32(0),
+      PropType::UInt64Property => Value::UInt64(0),
+      PropType::FloatProperty => Value::Float(0.0),
+      PropType::DoubleProperty => Value::Double(0.0),
+      PropType::ObjectProperty => Value::Object(Reference::UObject),
+      PropType::InterfaceProperty => Value::Interface(Reference::UObject),
+      PropType::LazyObjectProperty => Value::LazyObject { guid: [0; 16] },
+      PropType::DelegateProperty => Value::Delegate(Delegate {
+        object: Reference::UObject,
+        function_name: none()?,
+      }),
+      PropType::MulticastDelegateProperty => Value::MulticastDelegate {
+        invocation_list: vec![],
+      },
+      PropType::FieldPathProperty => Value::FieldPath {
+        path: vec![],
+        resolved_owner: Reference::UObject,
+      },
+      PropType::NameProperty => Value::Name(none()?),
+      PropType::StrProperty => Value::Str(String::new()),
+      typ => bail!("{} can't be stored as zero", typ),
+    },
+    MappedType::Enum { enum_name, .. } => enum_value(enum_name, 0, mappings, ctx)?,
+    MappedType::Struct { struct_name } => {
+      let value = match ctx.patterns.binary_size(struct_name) {
+        Some(size) => {
+          let mut rdr = ByteReader::new(vec![0; size]);
+          ctx.patterns.deserialize(&mut rdr, struct_name, ctx)?
+        }
+        None => StructValue::PropertyList {
+          properties: vec![],
+          has_none: false,
+        },
+      };
+      Value::Struct { value }
+    }
+    MappedType::Array { inner } => Value::Array {
+      meta_tag: match inner.as_ref() {
+        MappedType::Struct { .. } => Some((
+          Meta::new(none()?, PropType::StructProperty, 0),
+          tag_for(inner, ctx)?,
+        )),
+        _ => None,
+      },
+      values: vec![],
+    },
+    MappedType::Set { .. } => Value::Set {
+      elements_to_remove: vec![],
+      elements: vec![],
+    },
+    MappedType::Map { .. } => Value::Map {
+      keys_to_remove: vec![],
+      entries: vec![],
+    },
+  };
+  Ok((tag, value))
+}
+
+/// Whether a property is plain data, which can be stored as zero when all of
+/// its bytes are zero. Strings, containers, and structs with properties are
+/// never stored as zero by the engine, even when they are empty.
+fn is_plain_data(typ: &MappedType, value: &Value) -> bool {
+  match typ {
+    MappedType::Simple(typ) => matches!(
+      typ,
+      PropType::BoolProperty
+        | PropType::ByteProperty
+        | PropType::Int8Property
+        | PropType::Int16Property
+        | PropType::IntProperty
+        | PropType::Int64Property
+        | PropType::UInt8Property
+        | PropType::UInt16Property
+        | PropType::UInt32Property
+        | PropType::UInt64Property
+        | PropType::FloatProperty
+        | PropType::DoubleProperty
+    ),
+    MappedType::Enum { .. } => true,
+    MappedType::Struct { .. } => !matches!(
+      value,
+      Value::Struct {
+        value: StructValue::PropertyList { .. }
+      }
+    ),
+    _ => false,
+  }
+}
+
+/// Whether a value can be stored with its zero mask bit set
+fn is_zero(
+  typ: &MappedType,
+  tag: &Tag,
+  value: &Value,
+  mappings: &Mappings,
+  ctx: PropertyContext,
+) -> Result<bool> {
+  let is_none = |name: &NameVariant| name.to_string(ctx.names) == "None";
+  Ok(match (typ, value) {
+    (MappedType::Simple(PropType::BoolProperty), _) => matches!(tag, Tag::Bool(false)),
+    (MappedType::Enum { enum_name, .. }, _) => enum_index(enum_name, value, mappings, ctx)? == 0,
+    (MappedType::Struct { .. }, Value::Struct { value }) => match value {
+      StructValue::PropertyList { properties, .. } => properties.is_empty(),
+      _ => {
+        let mut curs = Cursor::new(vec![]);
+        value.serialize(&mut curs, ctx)?;
+        curs.into_inner().iter().all(|b| *b == 0)
+      }
+    },
+    (_, Value::Byte(v)) | (_, Value::UInt8(v)) => *v == 0,
+    (_, Value::Int8(v)) => *v == 0,
+    (_, Value::Int16(v)) => *v == 0,
+    (_, Value::Int(v)) => *v == 0,
+    (_, Value::Int64(v)) => *v == 0,
+    (_, Value::UInt16(v)) => *v == 0,
+    (_, Value::UInt32(v)) => *v == 0,
+    (_, Value::UInt64(v)) => *v == 0,
+    (_, Value::Float(v)) => v.to_bits() == 0,
+    (_, Value::Double(v)) => v.to_bits() == 0,
+    (_, Value::Object(Reference::UObject)) | (_, Value::Interface(Reference::UObject)) => true,
+    (_, Value::LazyObject { guid }) => guid.iter().all(|b| *b == 0),
+    (_, Value::Delegate(delegate)) => {
+      matches!(delegate.object, Reference::UObject) && is_none(&delegate.function_name)
+    }
+    (_, Value::MulticastDelegate { invocation_list }) => invocation_list.is_empty(),
+    (
+      _,
+      Value::FieldPath {
+        path,
+        resolved_owner,
+      },
+    ) => path.is_empty() && matches!(resolved_owner, Reference::UObject),
+    (_, Value::Name(name)) => is_none(name),
+    (_, Value::Str(string)) => string.is_empty(),
+    (_, Value::Array { values, .. }) => values.is_empty(),
+    (
+      _,
+      Value::Set {
+        elements_to_remove,
+        elements,
+      },
+    ) => elements_to_remove.is_empty() && elements.is_empty(),
+    (
+      _,
+      Value::Map {
+        keys_to_remove,
+        entries,
+      },
+    ) => keys_to_remove.is_empty() && entries.is_empty(),
+    _ => false,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::HashMap;
+
+  fn header_bytes(entries: &[(usize, bool)], schema_size: usize) -> Vec<u8> {
+    let mut curs = Cursor::new(vec![]);
+    write_header(&mut curs, entries, schema_size).unwrap();
+    let bytes = curs.into_inner();
+    let mut rdr = ByteReader::new(bytes.clone());
+    assert_eq!(read_header(&mut rdr).unwrap(), entries);
+    assert_eq!(rdr.remaining_bytes(), 0);
+    bytes
+  }
+
+  #[test]
+  fn header_fragments() {
+    // Skip of the whole schema, and is last
+    assert_eq!(header_bytes(&[], 3), [0x03, 0x01]);
+    // One value, then a skip and a zero value with a one byte zero mask
+    assert_eq!(
+      header_bytes(&[(0, false), (2, true)], 3),
+      [0x00, 0x02, 0x81, 0x03, 0x01]
+    );
+  }
+
+  #[test]
+  fn long_headers_round_trip() {
+    // Runs longer than a fragment can hold, and zero masks of every size
+    let mut entries: Vec<(usize, bool)> = (0..200).map(|i| (i, i % 3 == 0)).collect();
+    entries.push((500, false));
+    header_bytes(&entries, 501);
+    let entries: Vec<(usize, bool)> = (10..22).map(|i| (i, i == 21)).collect();
+    header_bytes(&entries, 22);
+  }
+
+  fn mappings() -> Mappings {
+    let property = |index, name: &str, typ| PropertySchema {
+      index,
+      array_dim: 1,
+      name: name.to_string(),
+      typ: MappedType::Simple(typ),
+    };
+    let schema = StructSchema {
+      name: "Test".to_string(),
+      super_type: None,
+      property_count: 2,
+      properties: vec![
+        property(0, "Count", PropType::IntProperty),
+        property(1, "Label", PropType::StrProperty),
+      ],
+    };
+    Mappings {
+      enums: HashMap::new(),
+      structs: HashMap::from([("Test".to_string(), schema)]),
+    }
+  }
+
+  fn properties(package: &TestPackage, count: i32, zeroes: [Option<bool>; 2]) -> Vec<Property> {
+    let property = |name, typ, value, unversioned_zero| Property {
+      meta: Meta {
+        unversioned_zero,
+        ..Meta::new(NameVariant::new(name, 0, &package.names), typ, 0)
+      },
+      tag: Tag::Simple(typ),
+      value,
+    };
+    vec![
+      property("Count", PropType::IntProperty, Value::Int(count), zeroes[0]),
+      property(
+        "Label",
+        PropType::StrProperty,
+        Value::Str(String::new()),
+        zeroes[1],
+      ),
+    ]
+  }
+
+  fn write(package: &TestPackage, properties: &[Property]) -> Vec<u8> {
+    let mut curs = Cursor::new(vec![]);
+    serialize_properties(&mut curs, properties, "Test", &mappings(), package.ctx()).unwrap();
+    curs.into_inner()
+  }
+
+  #[test]
+  fn zero_bits_that_were_read_are_kept() {
+    let package = TestPackage::new(&["Count", "Label"]);
+    let bytes = write(
+      &package,
+      &properties(&package, 0, [Some(false), Some(true)]),
+    );
+    // Only the empty string has its zero bit set
+    assert_eq!(bytes, [0x80, 0x05, 0x02, 0, 0, 0, 0]);
+
+    let mut rdr = ByteReader::new(bytes.clone());
+    let read = deserialize_properties(&mut rdr, "Test", &mappings(), package.ctx()).unwrap();
+    assert_eq!(rdr.remaining_bytes(), 0);
+    let zeroes: Vec<_> = read.iter().map(|p| p.meta.unversioned_zero).collect();
+    assert_eq!(zeroes, [Some(false), Some(true)]);
+    assert_eq!(write(&package, &read), bytes);
+  }
+
+  #[test]
+  fn zero_bits_of_new_and_changed_properties() {
+    let package = TestPackage::new(&["Count", "Label"]);
+    // New plain data is zero, but new strings aren't
+    let bytes = write(&package, &properties(&package, 0, [None, None]));
+    assert_eq!(bytes[..3], [0x80, 0x05, 0x01]);
+    // A value that isn't zero anymore loses its bit
+    let bytes = write(
+      &package,
+      &properties(&package, 5, [Some(true), Some(false)]),
+    );
+    assert_eq!(bytes[..6], [0x00, 0x05, 5, 0, 0, 0]);
+  }
+}
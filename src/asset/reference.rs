@@ -86,6 +86,28 @@ impl Reference {
     Ok(())
   }
 
+  /// Gets the index to write for this reference. `original` is the index it
+  /// was read from, which is kept while it points at an object with the same
+  /// name so references to objects with duplicate names don't change.
+  /// Otherwise the object is looked up by name, which fails if the name is
+  /// ambiguous.
+  pub fn index_for_write(
+    &self,
+    original: i32,
+    names: &Names,
+    imports: &Imports,
+    exports: &Exports,
+  ) -> Result<i32> {
+    if Self::deserialize(original, imports, exports).ok().as_ref() == Some(self) {
+      return Ok(original);
+    }
+    match self {
+      Self::UObject => Ok(0),
+      Self::Import { class, name } => imports.unique_index_of(class, name, names),
+      Self::Export(name) => exports.unique_index_of(name, names),
+    }
+  }
+
   pub fn to_string(&self, names: &Names) -> String {
     match self {
       Self::UObject => "UObject".to_string(),
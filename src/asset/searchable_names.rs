@@ -0,0 +1,74 @@
+use crate::asset::*;
+use crate::reader::*;
+use crate::util::*;
+use anyhow::*;
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::io::Cursor;
+
+/// Names that can be searched for in each object of the package. Only written
+/// for packages that aren't cooked.
+#[derive(Debug)]
+pub struct SearchableNames {
+  pub names: Vec<(Reference, Vec<NameVariant>)>,
+}
+
+impl SearchableNames {
+  pub fn read(
+    rdr: &mut ByteReader,
+    summary: &FileSummary,
+    names: &Names,
+    imports: &Imports,
+    exports: &Exports,
+  ) -> Result<Self> {
+    if rdr.position() != summary.searchable_names_offset as u64 {
+      bail!(
+        "Wrong searchable names starting position: Expected to be at position {:#X}, but I'm at position {:#X}",
+        summary.searchable_names_offset,
+        rdr.position(),
+      );
+    }
+
+    let count = rdr.read_u32::<LittleEndian>()?;
+    let mut searchable_names = vec![];
+    for i in 0..count {
+      let object = Reference::read(rdr, imports, exports)
+        .with_context(|| format!("Failed to read searchable names object {}", i))?;
+      let name_count = read_u32(rdr)?;
+      let mut object_names = vec![];
+      for _ in 0..name_count {
+        object_names.push(NameVariant::read(rdr, names)?);
+      }
+      searchable_names.push((object, object_names));
+    }
+    Ok(Self {
+      names: searchable_names,
+    })
+  }
+
+  pub fn write(
+    &self,
+    curs: &mut Cursor<Vec<u8>>,
+    names: &Names,
+    imports: &Imports,
+    exports: &Exports,
+  ) -> Result<()> {
+    write_u32(curs, self.names.len() as u32)?;
+    for (object, object_names) in self.names.iter() {
+      object.write(curs, names, imports, exports)?;
+      write_u32(curs, object_names.len() as u32)?;
+      for name in object_names.iter() {
+        name.write(curs, names)?;
+      }
+    }
+    Ok(())
+  }
+
+  pub fn byte_size(&self) -> usize {
+    // Count, then for each object a package index, name count, and names
+    4 + self
+      .names
+      .iter()
+      .map(|(_, names)| 8 + 8 * names.len())
+      .sum::<usize>()
+  }
+}
@@ -0,0 +1,43 @@
+use crate::asset::*;
+use crate::reader::*;
+use anyhow::*;
+use std::io::Cursor;
+
+/// Packages referenced by soft object paths in this package. Only written for
+/// packages that aren't cooked, or ones that actually have soft references.
+#[derive(Debug)]
+pub struct SoftPackageReferences {
+  pub references: Vec<NameVariant>,
+}
+
+impl SoftPackageReferences {
+  pub fn read(rdr: &mut ByteReader, summary: &FileSummary, names: &Names) -> Result<Self> {
+    if rdr.position() != summary.soft_package_references_offset as u64 {
+      bail!(
+        "Wrong soft package references starting position: Expected to be at position {:#X}, but I'm at position {:#X}",
+        summary.soft_package_references_offset,
+        rdr.position(),
+      );
+    }
+
+    let mut references = vec![];
+    for i in 0..summary.soft_package_references_count {
+      let reference = NameVariant::read(rdr, names)
+        .with_context(|| format!("Failed to read soft package reference {}", i))?;
+      references.push(reference);
+    }
+    Ok(Self { references })
+  }
+
+  pub fn write(&self, curs: &mut Cursor<Vec<u8>>, names: &Names) -> Result<()> {
+    for reference in self.references.iter() {
+      reference.write(curs, names)?;
+    }
+    Ok(())
+  }
+
+  pub fn byte_size(&self) -> usize {
+    // Each name is 8 bytes
+    8 * self.references.len()
+  }
+}
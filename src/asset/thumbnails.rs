@@ -0,0 +1,167 @@
+use crate::asset::*;
+use crate::reader::*;
+use crate::util::*;
+use anyhow::*;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::prelude::*;
+use std::io::Cursor;
+use std::io::SeekFrom;
+
+#[derive(Debug)]
+pub struct Thumbnail {
+  pub object_class_name: Option<String>,
+  pub object_path_without_package_name: Option<String>,
+  pub image_width: i32,
+  // Negative when the image data is JPEG compressed instead of PNG
+  pub image_height: i32,
+  pub compressed_image_data: Vec<u8>,
+}
+
+/// Editor thumbnails of the assets in the package. The image data of every
+/// thumbnail comes first, followed by the table that points into it. Only
+/// written for packages that aren't cooked.
+#[derive(Debug)]
+pub struct Thumbnails {
+  pub thumbnails: Vec<Thumbnail>,
+}
+
+impl Thumbnail {
+  fn image_size(&self) -> usize {
+    12 + self.compressed_image_data.len()
+  }
+
+  fn table_entry_size(&self) -> usize {
+    fstring_size(&self.object_class_name) + fstring_size(&self.object_path_without_package_name) + 4
+  }
+}
+
+impl Thumbnails {
+  /// Reads the thumbnail images and table. `rdr` should be at the start of the
+  /// first image.
+  pub fn read(rdr: &mut ByteReader, summary: &FileSummary) -> Result<Self> {
+    let images_start = rdr.position();
+    rdr.seek(SeekFrom::Start(summary.thumbnail_table_offset as u64))?;
+
+    let count = read_u32(rdr)?;
+    let mut thumbnails = vec![];
+    for i in 0..count {
+      let object_class_name = read_fstring(rdr)?;
+      let object_path_without_package_name = read_fstring(rdr)?;
+      let file_offset = rdr.read_i32::<LittleEndian>()?;
+
+      let table_pos = rdr.position();
+      rdr.seek(SeekFrom::Start(file_offset as u64))?;
+      let image_width = rdr.read_i32::<LittleEndian>()?;
+      let image_height = rdr.read_i32::<LittleEndian>()?;
+      let data_len = read_u32(rdr)?;
+      let compressed_image_data = read_bytes(rdr, data_len as usize)
+        .with_context(|| format!("Failed to read thumbnail {} image data", i))?;
+      rdr.seek(SeekFrom::Start(table_pos))?;
+
+      thumbnails.push(Thumbnail {
+        object_class_name,
+        object_path_without_package_name,
+        image_width,
+        image_height,
+        compressed_image_data,
+      });
+    }
+
+    let thumbnails = Self { thumbnails };
+    let images_size = summary.thumbnail_table_offset as u64 - images_start;
+    if images_size != thumbnails.images_size() as u64 {
+      bail!(
+        "Thumbnail images are {:#X} bytes, but expected {:#X}",
+        images_size,
+        thumbnails.images_size()
+      );
+    }
+    Ok(thumbnails)
+  }
+
+  /// Writes the images and then the table. The thumbnail table offset in the
+  /// summary should be the start offset + `images_size`.
+  pub fn write(&self, curs: &mut Cursor<Vec<u8>>) -> Result<()> {
+    let mut file_offsets = vec![];
+    for thumbnail in self.thumbnails.iter() {
+      file_offsets.push(curs.position() as i32);
+      curs.write_i32::<LittleEndian>(thumbnail.image_width)?;
+      curs.write_i32::<LittleEndian>(thumbnail.image_height)?;
+      write_u32(curs, thumbnail.compressed_image_data.len() as u32)?;
+      curs.write_all(&thumbnail.compressed_image_data)?;
+    }
+
+    write_u32(curs, self.thumbnails.len() as u32)?;
+    for (thumbnail, file_offset) in self.thumbnails.iter().zip(file_offsets) {
+      write_fstring(curs, &thumbnail.object_class_name)?;
+      write_fstring(curs, &thumbnail.object_path_without_package_name)?;
+      curs.write_i32::<LittleEndian>(file_offset)?;
+    }
+    Ok(())
+  }
+
+  /// Size of the image data before the table
+  pub fn images_size(&self) -> usize {
+    self.thumbnails.iter().map(|t| t.image_size()).sum()
+  }
+
+  pub fn byte_size(&self) -> usize {
+    let table_size = 4
+      + self
+        .thumbnails
+        .iter()
+        .map(|t| t.table_entry_size())
+        .sum::<usize>();
+    self.images_size() + table_size
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn thumbnail_offsets_point_at_their_images() {
+    let thumbnail = |path: &str, data: Vec<u8>| Thumbnail {
+      object_class_name: Some("Texture2D".to_string()),
+      object_path_without_package_name: Some(path.to_string()),
+      image_width: 64,
+      image_height: -64,
+      compressed_image_data: data,
+    };
+    let thumbnails = Thumbnails {
+      thumbnails: vec![
+        thumbnail("T_Gun", vec![1; 5]),
+        thumbnail("T_Rifle", vec![2; 3]),
+      ],
+    };
+    // The images are written after the rest of the header
+    let header_size = 0x10;
+    let mut curs = Cursor::new(vec![0; header_size]);
+    curs.set_position(header_size as u64);
+    thumbnails.write(&mut curs).unwrap();
+    let bytes = curs.into_inner();
+    assert_eq!(bytes.len(), header_size + thumbnails.byte_size());
+    // The table points at the second image, after the first one's 12 byte
+    // header and 5 bytes of data
+    let table = &bytes[header_size + thumbnails.images_size()..];
+    assert_eq!(
+      table[table.len() - 4..],
+      ((header_size + 17) as i32).to_le_bytes()
+    );
+
+    let mut summary = TestPackage::new(&[]).summary;
+    summary.thumbnail_table_offset = (header_size + thumbnails.images_size()) as u32;
+    let mut rdr = ByteReader::new(bytes);
+    rdr.seek(SeekFrom::Start(header_size as u64)).unwrap();
+    let read = Thumbnails::read(&mut rdr, &summary).unwrap();
+    assert_eq!(read.thumbnails.len(), 2);
+    let rifle = &read.thumbnails[1];
+    assert_eq!(
+      rifle.object_path_without_package_name.as_deref(),
+      Some("T_Rifle")
+    );
+    assert_eq!(rifle.image_height, -64);
+    assert_eq!(rifle.compressed_image_data, [2; 3]);
+  }
+}
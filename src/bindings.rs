@@ -1,6 +1,9 @@
 use crate::asset::property::meta::*;
 use crate::asset::property::prop_type::*;
+use crate::asset::property::text::*;
 use crate::asset::*;
+use std::collections::HashSet;
+use std::hash::Hash;
 
 /// Represents a value that can be turned into the parts of a property
 pub trait AsProperty {
@@ -72,36 +75,85 @@ impl Property {
   }
 }
 
+/// Splits a property name like `Damage[2]` into its name and static array
+/// index. Names without an index have index 0.
+fn parse_indexed_name(name: &str) -> (&str, u32) {
+  if let Some(without_bracket) = name.strip_suffix(']') {
+    if let Some((name, index)) = without_bracket.rsplit_once('[') {
+      if let Ok(index) = index.parse::<u32>() {
+        return (name, index);
+      }
+    }
+  }
+  (name, 0)
+}
+
 impl Properties {
-  /// Get the value of a property by name
+  /// Get the value of a property by name. Elements of static arrays are
+  /// named like `Damage[2]`.
   pub fn get<T: FromProperty>(&self, name: &str, header: &AssetHeader) -> Option<T> {
+    let (name, array_index) = parse_indexed_name(name);
+    let name = NameVariant::parse(name, &header.names);
     for prop in self.properties.iter() {
-      if prop.meta.name == NameVariant::parse(name, &header.names) {
+      if prop.meta.name == name && prop.meta.array_index == array_index {
         return T::from_property(prop, header);
       }
     }
     None
   }
 
-  /// Set the value of a property by name
+  /// Set the value of a property by name. Elements of static arrays are
+  /// named like `Damage[2]`.
+  ///
+  /// Setting an existing SetProperty to an array (e.g. a `Vec<T>`) keeps it a
+  /// SetProperty.
   pub fn set<T: AsProperty>(&mut self, name: &str, value: T, header: &AssetHeader) {
+    let (name, array_index) = parse_indexed_name(name);
     let name = NameVariant::parse(name, &header.names);
-    let new_prop = value.as_property(name.clone(), header);
+    let mut new_prop = value.as_property(name.clone(), header);
+    new_prop.meta.array_index = array_index;
     match self
       .properties
       .iter()
-      .position(|prop| prop.meta.name == name)
+      .position(|prop| prop.meta.name == name && prop.meta.array_index == array_index)
     {
       None => {
         self.properties.push(new_prop);
       }
       Some(i) => {
+        new_prop.meta.property_guid = self.properties[i].meta.property_guid;
+        if self.properties[i].meta.typ == PropType::SetProperty {
+          new_prop = array_as_set(new_prop);
+        }
         self.properties[i] = new_prop;
       }
     }
   }
 }
 
+/// Turns an ArrayProperty into a SetProperty with the same elements. Other
+/// properties are returned unchanged.
+fn array_as_set(property: Property) -> Property {
+  match property {
+    Property {
+      mut meta,
+      tag: Tag::Array { inner_type },
+      value: Value::Array { values, .. },
+    } => {
+      meta.typ = PropType::SetProperty;
+      Property {
+        meta,
+        tag: Tag::Set { inner_type },
+        value: Value::Set {
+          elements_to_remove: vec![],
+          elements: values.into_iter().map(|(_, value)| value).collect(),
+        },
+      }
+    }
+    property => property,
+  }
+}
+
 impl AssetHeader {
   /// Import an object into the asset
   ///
@@ -132,8 +184,7 @@ impl AssetHeader {
     {
       None => {
         // Create a new import
-        let outer_index = outer.serialize(&self.imports, &self.exports);
-        self.imports.add(class_package, class, name, outer_index);
+        self.imports.add(class_package, class, name, outer);
       }
       Some(_) => {}
     }
@@ -255,6 +306,58 @@ impl FromValue for f32 {
   }
 }
 
+// UE doesn't have a UInt8Property, a uint8 is a ByteProperty without an enum
+impl AsProperty for u8 {
+  fn prop_type(&self) -> PropType {
+    PropType::ByteProperty
+  }
+  fn as_tag(&self, header: &AssetHeader) -> Tag {
+    Tag::Enum(NameVariant::new("None", 0, &header.names))
+  }
+  fn as_value(&self, _: &AssetHeader) -> Value {
+    Value::Byte(*self)
+  }
+}
+impl FromValue for u8 {
+  fn from_value(value: &Value, _: &AssetHeader) -> Option<Self> {
+    match value {
+      Value::Byte(value) => Some(*value),
+      _ => None,
+    }
+  }
+}
+
+/// Implements AsSimpleProperty and FromValue for a type stored directly in a
+/// single Value variant.
+macro_rules! simple_binding {
+  ( $t:ty , $typ:expr , $variant:ident ) => {
+    impl AsSimpleProperty for $t {
+      fn simple_prop_type() -> PropType {
+        $typ
+      }
+      fn as_simple_value(&self, _: &AssetHeader) -> Value {
+        Value::$variant(*self)
+      }
+    }
+    impl FromValue for $t {
+      fn from_value(value: &Value, _: &AssetHeader) -> Option<Self> {
+        match value {
+          Value::$variant(value) => Some(*value),
+          _ => None,
+        }
+      }
+    }
+  };
+}
+
+simple_binding!(i8, PropType::Int8Property, Int8);
+simple_binding!(i16, PropType::Int16Property, Int16);
+simple_binding!(i64, PropType::Int64Property, Int64);
+simple_binding!(u16, PropType::UInt16Property, UInt16);
+simple_binding!(u32, PropType::UInt32Property, UInt32);
+simple_binding!(u64, PropType::UInt64Property, UInt64);
+simple_binding!(f64, PropType::DoubleProperty, Double);
+
 impl<T> AsProperty for Vec<T>
 where
   T: AsProperty,
@@ -267,7 +370,10 @@ where
     Tag::Array { inner_type }
   }
   fn as_value(&self, header: &AssetHeader) -> Value {
-    let values: Vec<Value> = self.iter().map(|t| t.as_value(header)).collect();
+    let values: Vec<(Tag, Value)> = self
+      .iter()
+      .map(|t| (t.as_tag(header), t.as_value(header)))
+      .collect();
     Value::Array {
       meta_tag: None,
       values,
@@ -282,6 +388,12 @@ where
     match &value {
       Value::Array { values, .. } => Some(
         values
+          .iter()
+          .filter_map(|(_, v)| T::from_value(v, header))
+          .collect(),
+      ),
+      Value::Set { elements, .. } => Some(
+        elements
           .iter()
           .filter_map(|v| T::from_value(v, header))
           .collect(),
@@ -291,6 +403,34 @@ where
   }
 }
 
+impl<T> AsProperty for HashSet<T>
+where
+  T: AsSimpleProperty,
+{
+  fn prop_type(&self) -> PropType {
+    PropType::SetProperty
+  }
+  fn as_tag(&self, _: &AssetHeader) -> Tag {
+    Tag::Set {
+      inner_type: T::simple_prop_type(),
+    }
+  }
+  fn as_value(&self, header: &AssetHeader) -> Value {
+    Value::Set {
+      elements_to_remove: vec![],
+      elements: self.iter().map(|t| t.as_simple_value(header)).collect(),
+    }
+  }
+}
+impl<T> FromValue for HashSet<T>
+where
+  T: FromValue + Eq + Hash,
+{
+  fn from_value(value: &Value, header: &AssetHeader) -> Option<Self> {
+    Vec::<T>::from_value(value, header).map(|values| values.into_iter().collect())
+  }
+}
+
 impl AsSimpleProperty for Reference {
   fn simple_prop_type() -> PropType {
     PropType::ObjectProperty
@@ -324,3 +464,73 @@ impl FromValue for String {
     }
   }
 }
+
+impl AsSimpleProperty for Text {
+  fn simple_prop_type() -> PropType {
+    PropType::TextProperty
+  }
+  fn as_simple_value(&self, _: &AssetHeader) -> Value {
+    Value::Text(self.clone())
+  }
+}
+impl FromValue for Text {
+  fn from_value(value: &Value, _: &AssetHeader) -> Option<Self> {
+    match &value {
+      Value::Text(value) => Some(value.clone()),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::asset::property::TestPackage;
+
+  #[test]
+  fn bytes_are_byte_properties_without_an_enum() {
+    let names = ["Level", "ByteProperty"];
+    let package = TestPackage::new(&names);
+    let header = TestPackage::new(&names).header();
+    let mut properties = package.properties();
+    properties.set("Level", 7u8, &header);
+    assert_eq!(properties.properties[0].meta.typ, PropType::ByteProperty);
+
+    // Written as a single byte, which reads back as a byte
+    let property = package.round_trip(
+      &properties.properties[0],
+      |rdr, ctx| Ok(Property::deserialize(rdr, ctx)?.unwrap()),
+      |property, curs, ctx| property.serialize(curs, ctx),
+      |property| property.byte_size(),
+    );
+    assert_eq!(property.meta.size, 1);
+    assert!(matches!(property.value, Value::Byte(7)));
+    assert_eq!(property.try_into::<u8>(&header), Some(7));
+  }
+
+  #[test]
+  fn static_array_elements_are_addressed_by_index() {
+    let names = ["Damage", "FloatProperty"];
+    let package = TestPackage::new(&names);
+    let header = TestPackage::new(&names).header();
+    let mut properties = package.properties();
+    for i in 0..3 {
+      properties.set(&format!("Damage[{}]", i), i as f32 * 10.0, &header);
+    }
+    properties.set("Damage[1]", 15.0f32, &header);
+    assert_eq!(properties.properties.len(), 3);
+    assert_eq!(properties.get::<f32>("Damage", &header), Some(0.0));
+    assert_eq!(properties.get::<f32>("Damage[1]", &header), Some(15.0));
+    assert_eq!(properties.get::<f32>("Damage[3]", &header), None);
+
+    // The index is part of the tag, so it reads back the same
+    let property = package.round_trip(
+      &properties.properties[2],
+      |rdr, ctx| Ok(Property::deserialize(rdr, ctx)?.unwrap()),
+      |property, curs, ctx| property.serialize(curs, ctx),
+      |property| property.byte_size(),
+    );
+    assert_eq!(property.meta.array_index, 2);
+    assert_eq!(property.meta.indexed_name(&header.names), "Damage[2]");
+  }
+}
@@ -1,5 +1,6 @@
 pub mod asset;
 pub mod bindings;
+pub mod pak;
 mod reader;
 mod util;
 
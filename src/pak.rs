@@ -0,0 +1,673 @@
+use crate::asset::*;
+use crate::util::*;
+use anyhow::*;
+use byteorder::{LittleEndian, ReadBytesExt};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{Cursor, SeekFrom};
+use std::path::Path;
+
+const PAK_MAGIC: u32 = 0x5A6F12E1;
+
+// Versions that change how the footer, index, or entries are serialized
+const VERSION_NO_TIMESTAMPS: u32 = 2;
+const VERSION_COMPRESSION_ENCRYPTION: u32 = 3;
+const VERSION_INDEX_ENCRYPTION: u32 = 4;
+const VERSION_RELATIVE_CHUNK_OFFSETS: u32 = 5;
+const VERSION_FNAME_BASED_COMPRESSION_METHOD: u32 = 8;
+const VERSION_FROZEN_INDEX: u32 = 9;
+const VERSION_PATH_HASH_INDEX: u32 = 10;
+
+// An entry's compression method for pak versions before compression methods
+// were named
+const COMPRESS_ZLIB: u32 = 0x01;
+const COMPRESS_GZIP: u32 = 0x02;
+
+/// The footer at the end of a pak file
+#[derive(Debug, Clone)]
+struct PakInfo {
+  version: u32,
+  encrypted_index: bool,
+  index_offset: u64,
+  index_size: u64,
+  compression_methods: Vec<String>,
+  // 4.22 paks store an entry's compression method index in one byte
+  narrow_compression_method: bool,
+}
+
+impl PakInfo {
+  fn read(file: &mut File) -> Result<Self> {
+    let len = file.seek(SeekFrom::End(0))?;
+    // Distance of the magic from the end of the file, the number of
+    // compression method names, and whether the index can be frozen
+    let layouts: [(u64, usize, bool); 4] = [
+      (44, 0, false),
+      (172, 4, false),
+      (204, 5, false),
+      (205, 5, true),
+    ];
+    for (magic_from_end, method_count, frozen) in layouts {
+      if magic_from_end + 1 > len {
+        continue;
+      }
+      file.seek(SeekFrom::Start(len - magic_from_end - 1))?;
+      let encrypted_index = file.read_u8()? != 0;
+      if file.read_u32::<LittleEndian>()? != PAK_MAGIC {
+        continue;
+      }
+      let version = file.read_u32::<LittleEndian>()?;
+      let encrypted_index = version >= VERSION_INDEX_ENCRYPTION && encrypted_index;
+      let matches_layout = match method_count {
+        0 => version < VERSION_FNAME_BASED_COMPRESSION_METHOD,
+        4 => version == VERSION_FNAME_BASED_COMPRESSION_METHOD,
+        _ if frozen => version == VERSION_FROZEN_INDEX,
+        _ => {
+          version == VERSION_FNAME_BASED_COMPRESSION_METHOD || version >= VERSION_PATH_HASH_INDEX
+        }
+      };
+      if !matches_layout {
+        continue;
+      }
+
+      let index_offset = file.read_u64::<LittleEndian>()?;
+      let index_size = file.read_u64::<LittleEndian>()?;
+      let _index_hash: [u8; 20] = read_bytes(file, 20)?;
+      if frozen && file.read_u8()? != 0 {
+        bail!("Paks with frozen indices are not supported");
+      }
+      let mut compression_methods = vec![];
+      for _ in 0..method_count {
+        let name: [u8; 32] = read_bytes(file, 32)?;
+        let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        compression_methods.push(String::from_utf8_lossy(&name[..end]).into_owned());
+      }
+      // Unused slots are empty, but the index into them is still meaningful
+      while compression_methods
+        .last()
+        .is_some_and(|name| name.is_empty())
+      {
+        compression_methods.pop();
+      }
+
+      return Ok(Self {
+        version,
+        encrypted_index,
+        index_offset,
+        index_size,
+        compression_methods,
+        narrow_compression_method: method_count == 4,
+      });
+    }
+    bail!("Could not find pak footer, is this a pak file?")
+  }
+}
+
+/// A file stored in a pak
+#[derive(Debug, Clone, PartialEq)]
+pub struct PakEntry {
+  // Path of the file with the pak's mount point, like FSD/Content/...
+  pub path: String,
+  // Offset of the entry's header, which is followed by its data
+  pub offset: u64,
+  pub size: u64,
+  pub uncompressed_size: u64,
+  // 0 if uncompressed. Before pak version 8 this is a COMPRESS_* flag,
+  // afterwards it is 1 + the index of the method in the footer.
+  pub compression_method: u32,
+  // Start and end of each compressed block
+  pub compression_blocks: Vec<(u64, u64)>,
+  pub compression_block_size: u32,
+  pub encrypted: bool,
+}
+
+impl PakEntry {
+  fn read<R: Read>(rdr: &mut R, info: &PakInfo) -> Result<Self> {
+    let offset = rdr.read_u64::<LittleEndian>()?;
+    let size = rdr.read_u64::<LittleEndian>()?;
+    let uncompressed_size = rdr.read_u64::<LittleEndian>()?;
+    let compression_method = if info.narrow_compression_method {
+      rdr.read_u8()?.into()
+    } else {
+      rdr.read_u32::<LittleEndian>()?
+    };
+    if info.version < VERSION_NO_TIMESTAMPS {
+      rdr.read_u64::<LittleEndian>()?;
+    }
+    let _hash: [u8; 20] = read_bytes(rdr, 20)?;
+    let mut compression_blocks = vec![];
+    let mut encrypted = false;
+    let mut compression_block_size = 0;
+    if info.version >= VERSION_COMPRESSION_ENCRYPTION {
+      if compression_method != 0 {
+        let block_count = rdr.read_u32::<LittleEndian>()?;
+        for _ in 0..block_count {
+          let start = rdr.read_u64::<LittleEndian>()?;
+          let end = rdr.read_u64::<LittleEndian>()?;
+          compression_blocks.push((start, end));
+        }
+      }
+      encrypted = rdr.read_u8()? != 0;
+      compression_block_size = rdr.read_u32::<LittleEndian>()?;
+    }
+    Ok(Self {
+      path: String::new(),
+      offset,
+      size,
+      uncompressed_size,
+      compression_method,
+      compression_blocks,
+      compression_block_size,
+      encrypted,
+    })
+  }
+
+  /// Reads an entry from the bit-packed format of the path hash index
+  fn read_encoded<R: Read>(rdr: &mut R, info: &PakInfo) -> Result<Self> {
+    let bits = rdr.read_u32::<LittleEndian>()?;
+    let compression_block_size = if bits & 0x3f == 0x3f {
+      rdr.read_u32::<LittleEndian>()?
+    } else {
+      (bits & 0x3f) << 11
+    };
+    let compression_method = (bits >> 23) & 0x3f;
+    let mut read_size = |is_32_bit: bool| -> Result<u64> {
+      if is_32_bit {
+        Ok(rdr.read_u32::<LittleEndian>()?.into())
+      } else {
+        Ok(rdr.read_u64::<LittleEndian>()?)
+      }
+    };
+    let offset = read_size(bits & (1 << 31) != 0)?;
+    let uncompressed_size = read_size(bits & (1 << 30) != 0)?;
+    let size = if compression_method != 0 {
+      read_size(bits & (1 << 29) != 0)?
+    } else {
+      uncompressed_size
+    };
+    let encrypted = bits & (1 << 22) != 0;
+    let block_count = (bits >> 6) & 0xffff;
+
+    let mut entry = Self {
+      path: String::new(),
+      offset,
+      size,
+      uncompressed_size,
+      compression_method,
+      compression_blocks: vec![],
+      compression_block_size,
+      encrypted,
+    };
+    // Blocks aren't stored, but they follow the entry's header back to back
+    let mut start = header_size(info, compression_method, block_count as usize) as u64;
+    if block_count == 1 && !encrypted {
+      entry.compression_blocks.push((start, start + size));
+    } else {
+      for _ in 0..block_count {
+        let block_size = u64::from(rdr.read_u32::<LittleEndian>()?);
+        entry.compression_blocks.push((start, start + block_size));
+        start += if encrypted {
+          block_size.div_ceil(16) * 16
+        } else {
+          block_size
+        };
+      }
+    }
+    Ok(entry)
+  }
+
+  /// Size of the copy of the entry written before its data
+  fn header_size(&self, info: &PakInfo) -> usize {
+    header_size(info, self.compression_method, self.compression_blocks.len())
+  }
+
+  pub fn is_compressed(&self) -> bool {
+    self.compression_method != 0
+  }
+}
+
+/// A pak archive. Entries are read from the file on demand.
+#[derive(Debug)]
+pub struct Pak {
+  file: File,
+  info: PakInfo,
+  pub mount_point: String,
+  pub entries: Vec<PakEntry>,
+}
+
+impl Pak {
+  pub fn open(pak_loc: &Path) -> Result<Self> {
+    let mut file =
+      File::open(pak_loc).with_context(|| format!("Failed to open pak {:?}", pak_loc))?;
+    let info = PakInfo::read(&mut file).with_context(|| "Failed to read pak footer")?;
+    if info.encrypted_index {
+      bail!("Paks with encrypted indices are not supported");
+    }
+
+    let index = read_at(&mut file, info.index_offset, info.index_size as usize)
+      .with_context(|| "Failed to read pak index")?;
+    let mut rdr = Cursor::new(index);
+    let mount_point = read_fstring_or_empty(&mut rdr)?;
+    let mut entries = if info.version >= VERSION_PATH_HASH_INDEX {
+      Self::read_path_hash_index(&mut file, &mut rdr, &info)
+    } else {
+      Self::read_legacy_index(&mut rdr, &info)
+    }
+    .with_context(|| "Failed to read pak index")?;
+
+    // Mount points are relative to the engine's binaries, ../../../ is the
+    // project's root
+    let mount_prefix = mount_point.trim_start_matches("../../../");
+    for entry in entries.iter_mut() {
+      entry.path = format!("{}{}", mount_prefix, entry.path);
+    }
+
+    Ok(Self {
+      file,
+      info,
+      mount_point,
+      entries,
+    })
+  }
+
+  fn read_legacy_index(rdr: &mut Cursor<Vec<u8>>, info: &PakInfo) -> Result<Vec<PakEntry>> {
+    let count = read_u32(rdr)?;
+    let mut entries = vec![];
+    for _ in 0..count {
+      let path = read_fstring_or_empty(rdr)?;
+      let mut entry = PakEntry::read(rdr, info).with_context(|| format!("For entry {}", path))?;
+      entry.path = path;
+      entries.push(entry);
+    }
+    Ok(entries)
+  }
+
+  fn read_path_hash_index(
+    file: &mut File,
+    rdr: &mut Cursor<Vec<u8>>,
+    info: &PakInfo,
+  ) -> Result<Vec<PakEntry>> {
+    let _count = read_u32(rdr)?;
+    let _path_hash_seed = rdr.read_u64::<LittleEndian>()?;
+    if read_bool(rdr)? {
+      // The path hash index only maps hashes to entries, so it isn't needed
+      let _path_hash_index: [u8; 36] = read_bytes(rdr, 36)?;
+    }
+    if !read_bool(rdr)? {
+      bail!("Pak does not have a full directory index");
+    }
+    let directory_index_offset = rdr.read_u64::<LittleEndian>()?;
+    let directory_index_size = rdr.read_u64::<LittleEndian>()?;
+    let _directory_index_hash: [u8; 20] = read_bytes(rdr, 20)?;
+
+    let encoded_size = read_u32(rdr)? as usize;
+    let encoded_entries: Vec<u8> = read_bytes(rdr, encoded_size)?;
+    let unencoded_count = read_u32(rdr)?;
+    let mut unencoded_entries = vec![];
+    for _ in 0..unencoded_count {
+      unencoded_entries.push(PakEntry::read(rdr, info)?);
+    }
+
+    let directory_index = read_at(file, directory_index_offset, directory_index_size as usize)
+      .with_context(|| "Failed to read pak directory index")?;
+    let mut rdr = Cursor::new(directory_index);
+    let mut entries = vec![];
+    let directory_count = read_u32(&mut rdr)?;
+    for _ in 0..directory_count {
+      let directory = read_fstring_or_empty(&mut rdr)?;
+      let file_count = read_u32(&mut rdr)?;
+      for _ in 0..file_count {
+        let name = read_fstring_or_empty(&mut rdr)?;
+        let location = rdr.read_i32::<LittleEndian>()?;
+        let path = format!("{}{}", directory.trim_start_matches('/'), name);
+        // Negative locations index the entries that couldn't be encoded,
+        // `i32::MIN` marks an invalid location
+        let mut entry = if location < 0 {
+          location
+            .checked_neg()
+            .and_then(|index| unencoded_entries.get(index as usize - 1))
+            .cloned()
+            .ok_or_else(|| anyhow!("Entry {} has invalid location {}", path, location))?
+        } else {
+          let mut encoded = encoded_entries
+            .get(location as usize..)
+            .ok_or_else(|| anyhow!("Entry {} has invalid location {}", path, location))?;
+          PakEntry::read_encoded(&mut encoded, info)
+            .with_context(|| format!("For entry {}", path))?
+        };
+        entry.path = path;
+        entries.push(entry);
+      }
+    }
+    Ok(entries)
+  }
+
+  pub fn version(&self) -> u32 {
+    self.info.version
+  }
+
+  pub fn entry(&self, path: &str) -> Option<&PakEntry> {
+    let path = path.replace('\\', "/");
+    let path = path.trim_start_matches('/');
+    self.entries.iter().find(|entry| entry.path == path)
+  }
+
+  /// Name of the method an entry is compressed with
+  pub fn compression_method_name(&self, entry: &PakEntry) -> Option<String> {
+    if !entry.is_compressed() {
+      None
+    } else if self.info.version < VERSION_FNAME_BASED_COMPRESSION_METHOD {
+      Some(match entry.compression_method {
+        COMPRESS_ZLIB => "Zlib".to_string(),
+        COMPRESS_GZIP => "Gzip".to_string(),
+        method => format!("Unknown ({})", method),
+      })
+    } else {
+      Some(
+        self
+          .info
+          .compression_methods
+          .get(entry.compression_method as usize - 1)
+          .cloned()
+          .unwrap_or_else(|| format!("Unknown ({})", entry.compression_method)),
+      )
+    }
+  }
+
+  pub fn read_entry(&self, entry: &PakEntry) -> Result<Vec<u8>> {
+    if entry.encrypted {
+      bail!("Entry {} is encrypted, which is not supported", entry.path);
+    }
+    let mut file = &self.file;
+
+    let method = match self.compression_method_name(entry) {
+      None => {
+        let data_offset = entry.offset + entry.header_size(&self.info) as u64;
+        return read_at(&mut file, data_offset, entry.size as usize)
+          .with_context(|| format!("Failed to read entry {}", entry.path));
+      }
+      Some(method) => method,
+    };
+
+    // Blocks used to be stored with absolute offsets
+    let base = if self.info.version >= VERSION_RELATIVE_CHUNK_OFFSETS {
+      entry.offset
+    } else {
+      0
+    };
+    let mut data = Vec::with_capacity(entry.uncompressed_size as usize);
+    for &(start, end) in &entry.compression_blocks {
+      let block = read_at(&mut file, base + start, (end - start) as usize)
+        .with_context(|| format!("Failed to read entry {}", entry.path))?;
+      match method.to_lowercase().as_str() {
+        "zlib" => ZlibDecoder::new(&block[..]).read_to_end(&mut data),
+        "gzip" => GzDecoder::new(&block[..]).read_to_end(&mut data),
+        _ => bail!(
+          "Entry {} uses unsupported compression method {}",
+          entry.path,
+          method
+        ),
+      }
+      .with_context(|| format!("Failed to decompress entry {}", entry.path))?;
+    }
+    if data.len() as u64 != entry.uncompressed_size {
+      bail!(
+        "Entry {} decompressed to {} bytes, expected {}",
+        entry.path,
+        data.len(),
+        entry.uncompressed_size
+      );
+    }
+    Ok(data)
+  }
+
+  pub fn read(&self, path: &str) -> Result<Vec<u8>> {
+    let entry = self
+      .entry(path)
+      .ok_or_else(|| anyhow!("Pak does not contain {}", path))?;
+    self.read_entry(entry)
+  }
+
+  /// Reads a file of an asset by extension, with `asset_path` being the path
+  /// of the asset with or without an extension
+  fn read_asset_file(&self, asset_path: &str, extension: &str) -> Result<Option<Vec<u8>>> {
+    let path = Path::new(asset_path).with_extension(extension);
+    match self.entry(&path.to_string_lossy()) {
+      None => Ok(None),
+      Some(entry) => self.read_entry(entry).map(Some),
+    }
+  }
+
+  pub fn read_header(&self, asset_path: &str) -> Result<AssetHeader> {
+    let uasset = self
+      .read_asset_file(asset_path, "uasset")?
+      .ok_or_else(|| anyhow!("Pak does not contain a uasset for {}", asset_path))?;
+    AssetHeader::read(uasset)
+  }
+
+  pub fn read_exports(&self, header: &AssetHeader, asset_path: &str) -> Result<AssetExports> {
+    let uexp = self
+      .read_asset_file(asset_path, "uexp")?
+      .ok_or_else(|| anyhow!("Pak does not contain a uexp for {}", asset_path))?;
+    let mut exports = AssetExports::read(header, uexp)?;
+    exports.ubulk = self.read_asset_file(asset_path, "ubulk")?;
+    Ok(exports)
+  }
+
+  pub fn read_asset(&self, asset_path: &str) -> Result<Asset> {
+    let header = self.read_header(asset_path)?;
+    let exports = self.read_exports(&header, asset_path)?;
+    Ok(Asset::new(header, exports))
+  }
+}
+
+fn header_size(info: &PakInfo, compression_method: u32, block_count: usize) -> usize {
+  let mut size = 8 + 8 + 8 + 20;
+  size += if info.narrow_compression_method { 1 } else { 4 };
+  if info.version < VERSION_NO_TIMESTAMPS {
+    size += 8;
+  }
+  if info.version >= VERSION_COMPRESSION_ENCRYPTION {
+    if compression_method != 0 {
+      size += 4 + 16 * block_count;
+    }
+    size += 1 + 4;
+  }
+  size
+}
+
+fn read_at<R: Read + Seek>(rdr: &mut R, offset: u64, len: usize) -> Result<Vec<u8>> {
+  rdr.seek(SeekFrom::Start(offset))?;
+  read_bytes(rdr, len)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use byteorder::WriteBytesExt;
+
+  fn info() -> PakInfo {
+    PakInfo {
+      version: VERSION_PATH_HASH_INDEX + 1,
+      encrypted_index: false,
+      index_offset: 0,
+      index_size: 0,
+      compression_methods: vec!["Zlib".to_string()],
+      narrow_compression_method: false,
+    }
+  }
+
+  fn entry(offset: u64, size: u64, compression_block_size: u32) -> PakEntry {
+    PakEntry {
+      path: String::new(),
+      offset,
+      size,
+      uncompressed_size: 0x30000,
+      compression_method: 1,
+      compression_blocks: vec![],
+      compression_block_size,
+      encrypted: false,
+    }
+  }
+
+  /// The header written before an entry's data, which is also how entries
+  /// are stored in the legacy index
+  fn header(entry: &PakEntry) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.write_u64::<LittleEndian>(entry.offset).unwrap();
+    bytes.write_u64::<LittleEndian>(entry.size).unwrap();
+    bytes
+      .write_u64::<LittleEndian>(entry.uncompressed_size)
+      .unwrap();
+    bytes
+      .write_u32::<LittleEndian>(entry.compression_method)
+      .unwrap();
+    bytes.extend_from_slice(&[0; 20]);
+    if entry.compression_method != 0 {
+      let block_count = entry.compression_blocks.len() as u32;
+      bytes.write_u32::<LittleEndian>(block_count).unwrap();
+      for (start, end) in entry.compression_blocks.iter() {
+        bytes.write_u64::<LittleEndian>(*start).unwrap();
+        bytes.write_u64::<LittleEndian>(*end).unwrap();
+      }
+    }
+    bytes.push(entry.encrypted as u8);
+    bytes
+      .write_u32::<LittleEndian>(entry.compression_block_size)
+      .unwrap();
+    bytes
+  }
+
+  /// An entry in the bit-packed format of the path hash index
+  fn encoded(entry: &PakEntry, block_sizes: &[u32]) -> Vec<u8> {
+    let fits_u32 = |value: u64| value <= u32::MAX.into();
+    let block_size_bits = match entry.compression_block_size {
+      size if size % 0x800 == 0 && size >> 11 < 0x3f => size >> 11,
+      _ => 0x3f,
+    };
+    let mut bits = block_size_bits
+      | (block_sizes.len() as u32) << 6
+      | (entry.encrypted as u32) << 22
+      | entry.compression_method << 23;
+    for (bit, value) in [
+      (29, entry.size),
+      (30, entry.uncompressed_size),
+      (31, entry.offset),
+    ] {
+      if fits_u32(value) {
+        bits |= 1 << bit;
+      }
+    }
+
+    let mut bytes = vec![];
+    bytes.write_u32::<LittleEndian>(bits).unwrap();
+    if block_size_bits == 0x3f {
+      bytes
+        .write_u32::<LittleEndian>(entry.compression_block_size)
+        .unwrap();
+    }
+    let mut write_size = |value: u64| {
+      if fits_u32(value) {
+        bytes.write_u32::<LittleEndian>(value as u32).unwrap();
+      } else {
+        bytes.write_u64::<LittleEndian>(value).unwrap();
+      }
+    };
+    write_size(entry.offset);
+    write_size(entry.uncompressed_size);
+    if entry.compression_method != 0 {
+      write_size(entry.size);
+    }
+    if block_sizes.len() != 1 || entry.encrypted {
+      for block_size in block_sizes {
+        bytes.write_u32::<LittleEndian>(*block_size).unwrap();
+      }
+    }
+    bytes
+  }
+
+  /// Checks that both formats of `entry` read back the same
+  fn check(entry: &PakEntry, block_sizes: &[u32]) {
+    let header = header(entry);
+    let block_count = entry.compression_blocks.len();
+    assert_eq!(
+      header_size(&info(), entry.compression_method, block_count),
+      header.len()
+    );
+    assert_eq!(&PakEntry::read(&mut &header[..], &info()).unwrap(), entry);
+
+    let encoded = encoded(entry, block_sizes);
+    let mut rdr = &encoded[..];
+    assert_eq!(&PakEntry::read_encoded(&mut rdr, &info()).unwrap(), entry);
+    assert!(rdr.is_empty(), "Not everything was read");
+  }
+
+  #[test]
+  fn compressed_entries() {
+    // Blocks are relative to the entry and start after its 105 byte header
+    let mut blocks = entry(0x1000, 0x1C0, 0x10000);
+    blocks.compression_blocks = vec![(105, 361), (361, 489), (489, 553)];
+    check(&blocks, &[0x100, 0x80, 0x40]);
+
+    // Sizes of single blocks aren't stored
+    let mut single = entry(0x1_0000_0000, 0x50, 0x1234);
+    single.compression_blocks = vec![(73, 153)];
+    check(&single, &[0x50]);
+  }
+
+  #[test]
+  fn encrypted_blocks_are_aligned() {
+    let mut encrypted = entry(0x2000, 0x40, 0x10000);
+    encrypted.encrypted = true;
+    encrypted.compression_blocks = vec![(89, 106), (121, 153)];
+    check(&encrypted, &[0x11, 0x20]);
+
+    let mut single = entry(0x2000, 0x20, 0x10000);
+    single.encrypted = true;
+    single.compression_blocks = vec![(73, 105)];
+    check(&single, &[0x20]);
+  }
+
+  #[test]
+  fn uncompressed_entries() {
+    let mut uncompressed = entry(0x1_0000_0000, 0x10, 0);
+    uncompressed.uncompressed_size = 0x10;
+    uncompressed.compression_method = 0;
+    check(&uncompressed, &[]);
+    assert_eq!(header_size(&info(), 0, 0), 53);
+  }
+
+  #[test]
+  fn invalid_locations_are_errors() {
+    let mut directory_index = vec![];
+    directory_index.write_u32::<LittleEndian>(1).unwrap();
+    write_fstring(&mut directory_index, &Some("/".to_string())).unwrap();
+    directory_index.write_u32::<LittleEndian>(1).unwrap();
+    write_fstring(&mut directory_index, &Some("a.uasset".to_string())).unwrap();
+    directory_index.write_i32::<LittleEndian>(i32::MIN).unwrap();
+    let path = std::env::temp_dir().join(format!("drg-pak-test-{}", std::process::id()));
+    std::fs::write(&path, &directory_index).unwrap();
+
+    let mut index = vec![];
+    index.write_u32::<LittleEndian>(1).unwrap();
+    index.write_u64::<LittleEndian>(0).unwrap();
+    write_bool(&mut index, false).unwrap();
+    write_bool(&mut index, true).unwrap();
+    index.write_u64::<LittleEndian>(0).unwrap();
+    index
+      .write_u64::<LittleEndian>(directory_index.len() as u64)
+      .unwrap();
+    index.extend_from_slice(&[0; 20]);
+    index.write_u32::<LittleEndian>(0).unwrap();
+    index.write_u32::<LittleEndian>(0).unwrap();
+
+    let mut file = File::open(&path).unwrap();
+    let result = Pak::read_path_hash_index(&mut file, &mut Cursor::new(index), &info());
+    std::fs::remove_file(&path).unwrap();
+    let err = result.unwrap_err().to_string();
+    assert_eq!(
+      err,
+      format!("Entry a.uasset has invalid location {}", i32::MIN)
+    );
+  }
+}
@@ -1,5 +1,6 @@
 use anyhow::*;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::io::{Read, Write};
 use std::io::{Seek, SeekFrom};
@@ -83,3 +84,67 @@ pub fn write_bool<W: Write>(curs: &mut W, val: bool) -> Result<()> {
   write_u32(curs, if val { 1 } else { 0 })?;
   Ok(())
 }
+
+/// Reads an FString. Unlike `read_string`, this handles null strings (length
+/// 0, returned as `None`) and UTF-16 strings (negative length).
+pub fn read_fstring<R: Read>(rdr: &mut R) -> Result<Option<String>> {
+  let length = rdr.read_i32::<LittleEndian>()?;
+  match length.cmp(&0) {
+    Ordering::Equal => Ok(None),
+    Ordering::Greater => {
+      let chars = read_bytes(rdr, length as usize - 1)?;
+      rdr.read_exact(&mut [0])?; // Skip past 0 terminator
+      Ok(Some(String::from_utf8(chars)?))
+    }
+    Ordering::Less => {
+      let length = -(length as i64) as usize;
+      let mut chars = Vec::with_capacity(length - 1);
+      for _ in 0..(length - 1) {
+        chars.push(rdr.read_u16::<LittleEndian>()?);
+      }
+      rdr.read_u16::<LittleEndian>()?; // Skip past 0 terminator
+      Ok(Some(String::from_utf16(&chars)?))
+    }
+  }
+}
+
+/// Writes an FString. Strings that aren't pure ASCII are written as UTF-16,
+/// the same way the engine does.
+pub fn write_fstring<W: Write>(curs: &mut W, string: &Option<String>) -> Result<()> {
+  match string {
+    None => write_u32(curs, 0)?,
+    Some(string) if string.is_ascii() => write_string(curs, string)?,
+    Some(string) => {
+      let chars: Vec<u16> = string.encode_utf16().collect();
+      curs.write_i32::<LittleEndian>(-(chars.len() as i32 + 1))?;
+      for c in chars {
+        curs.write_u16::<LittleEndian>(c)?;
+      }
+      curs.write_u16::<LittleEndian>(0)?;
+    }
+  }
+  Ok(())
+}
+
+pub fn fstring_size(string: &Option<String>) -> usize {
+  match string {
+    None => 4,
+    Some(string) if string.is_ascii() => 4 + string.len() + 1,
+    Some(string) => 4 + (string.encode_utf16().count() + 1) * 2,
+  }
+}
+
+/// Reads an FString where null strings are treated as empty
+pub fn read_fstring_or_empty<R: Read>(rdr: &mut R) -> Result<String> {
+  Ok(read_fstring(rdr)?.unwrap_or_default())
+}
+
+/// A new string to write with `write_fstring`. Empty strings are null strings,
+/// like the engine writes them.
+pub fn new_fstring(string: &str) -> Option<String> {
+  if string.is_empty() {
+    None
+  } else {
+    Some(string.to_string())
+  }
+}